@@ -1,7 +1,7 @@
 //! [`Block`] is the core type to represent Pdx script code
 
 use crate::date::Date;
-use crate::macros::MACRO_MAP;
+use crate::macros::macro_map;
 use crate::parse::pdxfile::{MacroComponent, MacroComponentKind, PdxfileMemory, parse_pdx_macro};
 use crate::token::{Loc, Token};
 
@@ -40,6 +40,9 @@ pub struct Block {
     pub tag: Option<Box<Token>>,
     /// The location of the start of the block. Used mostly for error reporting.
     pub loc: Loc,
+    /// The line and column of the closing brace, or `(0, 0)` if the block doesn't have one,
+    /// such as the top-level block of a file. Used to find the end of the block.
+    pub close: (u32, u32),
     /// If the block is a top-level block and contains macro substitutions, this field will
     /// hold the original source for re-parsing.
     /// The source has already been split into a vec that alternates content with macro parameters.
@@ -51,7 +54,7 @@ pub struct Block {
 impl Block {
     /// Open a new `Block` at the given location.
     pub fn new(loc: Loc) -> Self {
-        Block { v: Vec::new(), tag: None, loc, source: None }
+        Block { v: Vec::new(), tag: None, loc, close: (0, 0), source: None }
     }
 
     /// Add a loose value to this `Block`. Mostly used by the parser.
@@ -410,7 +413,7 @@ impl Block {
     /// not in any history block, is considered to be the field value at the beginning of time.
     /// History fields are ones that have a date as the key, like `900.1.1 = { ... }`.
     #[allow(dead_code)] // Not used by all games
-    pub fn get_field_at_date(&self, name: &str, date: Date) -> Option<&BV> {
+    pub(crate) fn get_field_at_date(&self, name: &str, date: Date) -> Option<&BV> {
         let mut found_date: Option<Date> = None;
         let mut found: Option<&BV> = None;
 
@@ -461,7 +464,7 @@ impl Block {
         loc: Loc,
         global: &PdxfileMemory,
     ) -> Option<Block> {
-        let link_index = MACRO_MAP.get_or_insert_loc(loc);
+        let link_index = macro_map().get_or_insert_loc(loc);
         if let Some(block_source) = &self.source {
             let (ref source, ref local) = **block_source;
             let mut content = Vec::new();
@@ -480,7 +483,7 @@ impl Block {
                                 let orig_loc = val.loc;
                                 val.loc = token.loc;
                                 val.loc.column -= 1; // point at the $, it looks better
-                                val.loc.link_idx = Some(MACRO_MAP.get_or_insert_loc(orig_loc));
+                                val.loc.link_idx = Some(macro_map().get_or_insert_loc(orig_loc));
                                 content.push(val);
                                 break;
                             }
@@ -514,7 +517,7 @@ impl Block {
     ///
     /// This function is used as a last resort when validating awkward syntax.
     #[allow(dead_code)]
-    pub fn condense_tag(self, tag: &str) -> Self {
+    pub(crate) fn condense_tag(self, tag: &str) -> Self {
         let mut other = Block::new(self.loc);
         let mut reserve: Option<(Token, Comparator, Token)> = None;
         for item in self.v {
@@ -174,6 +174,14 @@ impl Characters {
         }
     }
 
+    /// Forget which characters have been checked for ancestor cycles, so that the next
+    /// validation checks them again.
+    pub fn clear_caches(&mut self) {
+        for item in self.characters.values_mut().chain(self.duplicates.iter_mut()) {
+            item.ancestor_state.store(AncestorState::Unchecked, Ordering::Release);
+        }
+    }
+
     // Recursive depth-first search of ancestor graph to see if there are any cycles.
     // If a cycle is found, print a warning about it that includes all the characters in the cycle.
     //
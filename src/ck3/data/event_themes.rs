@@ -39,6 +39,10 @@ impl EventTheme {
 impl DbKind for EventTheme {
     fn validate(&self, _key: &Token, _block: &Block, _data: &Everything) {}
 
+    fn clear_cache(&mut self) {
+        *self.validated_scopes.get_mut().unwrap() = Scopes::empty();
+    }
+
     /// Themes are unusual in that they are validated through the events that use them.
     /// This means that unused themes are not validated, which is ok.
     /// The purpose is to allow the triggers to be validated in the context of the scope
@@ -98,6 +102,10 @@ impl EventBackground {
 impl DbKind for EventBackground {
     fn validate(&self, _key: &Token, _block: &Block, _data: &Everything) {}
 
+    fn clear_cache(&mut self) {
+        *self.validated_scopes.get_mut().unwrap() = Scopes::empty();
+    }
+
     /// Like `EventTheme`, `EventBackground` are validated through the events (and themes) that use them.
     fn validate_call(
         &self,
@@ -155,6 +163,10 @@ impl EventTransition {
 impl DbKind for EventTransition {
     fn validate(&self, _key: &Token, _block: &Block, _data: &Everything) {}
 
+    fn clear_cache(&mut self) {
+        *self.validated_scopes.get_mut().unwrap() = Scopes::empty();
+    }
+
     /// Like `EventTheme`, `EventTransition` are validated through the events (and themes) that use them.
     fn validate_call(
         &self,
@@ -271,26 +271,38 @@ fn validate_artifact(block: &Block, data: &Everything, sc: &mut ScopeContext) {
 }
 
 fn validate_animations(vd: &mut Validator) {
-    vd.field_validated_value("animation", |_, mut vd| {
+    vd.field_validated_value("animation", |key, mut vd| {
         if !vd.maybe_item(Item::PortraitAnimation) && vd.maybe_item(Item::ScriptedAnimation) {
             let msg = format!(
                 "portrait animation {vd} not defined in {}",
                 Item::PortraitAnimation.path()
             );
             let info = format!("Did you mean `scripted_animation = {vd}`?");
-            warn(ErrorKey::MissingItem).strong().msg(msg).info(info).loc(vd).push();
+            warn(ErrorKey::MissingItem)
+                .strong()
+                .msg(msg)
+                .info(info)
+                .fix(key, "scripted_animation")
+                .loc(vd)
+                .push();
         } else {
             vd.item(Item::PortraitAnimation);
         }
     });
-    vd.field_validated_value("scripted_animation", |_, mut vd| {
+    vd.field_validated_value("scripted_animation", |key, mut vd| {
         if !vd.maybe_item(Item::ScriptedAnimation) && vd.maybe_item(Item::PortraitAnimation) {
             let msg = format!(
                 "scripted animation {vd} not defined in {}",
                 Item::ScriptedAnimation.path()
             );
             let info = format!("Did you mean `animation = {vd}`?");
-            warn(ErrorKey::MissingItem).strong().msg(msg).info(info).loc(vd).push();
+            warn(ErrorKey::MissingItem)
+                .strong()
+                .msg(msg)
+                .info(info)
+                .fix(key, "animation")
+                .loc(vd)
+                .push();
         } else {
             vd.item(Item::ScriptedAnimation);
         }
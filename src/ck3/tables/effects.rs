@@ -52,6 +52,14 @@ static SCOPE_EFFECT_MAP: LazyLock<TigerHashMap<&'static str, (Scopes, Effect)>>
         hash
     });
 
+/// Iterate over the names of all the builtin effects, except the ones that have been removed from the game.
+pub fn iter_effect_names() -> impl Iterator<Item = &'static str> {
+    SCOPE_EFFECT
+        .iter()
+        .filter(|(_, _, v)| !matches!(v, Effect::Removed(..)))
+        .map(|(_, name, _)| *name)
+}
+
 // LAST UPDATED CK3 VERSION 1.18.08.08.08.08.08.08.08.0
 // See `effects.log` from the game data dumps
 const SCOPE_EFFECT: &[(Scopes, &str, Effect)] = &[
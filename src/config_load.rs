@@ -10,8 +10,8 @@ use strum::IntoEnumIterator;
 use crate::block::{BV, Block, BlockItem, Comparator, Eq::*, Field};
 use crate::helpers::stringify_list;
 use crate::report::{
-    Confidence, ErrorKey, ErrorLoc, FilterRule, PointedMessage, Severity, err, set_predicate,
-    set_show_loaded_mods, set_show_vanilla,
+    Confidence, ErrorKey, ErrorLoc, FilterRule, PointedMessage, Severity, err,
+    set_fail_on_predicate, set_predicate, set_show_loaded_mods, set_show_vanilla,
 };
 
 /// Checks for legacy ignore blocks (that no longer work) and report an error if they are present.
@@ -66,6 +66,7 @@ pub fn load_filter(config: &Block) {
         assert_one_key("trigger", filter);
         assert_one_key("show_vanilla", filter);
         assert_one_key("show_loaded_mods", filter);
+        assert_one_key("fail_on", filter);
         set_show_vanilla(filter.get_field_bool("show_vanilla").unwrap_or(false));
         set_show_loaded_mods(filter.get_field_bool("show_loaded_mods").unwrap_or(false));
         if let Some(trigger) = filter.get_field_block("trigger") {
@@ -73,6 +74,8 @@ pub fn load_filter(config: &Block) {
         } else {
             set_predicate(FilterRule::default());
         }
+        let fail_on = filter.get_field_block("fail_on");
+        set_fail_on_predicate(fail_on.map(|block| FilterRule::Conjunction(load_rules(block))));
     }
 }
 
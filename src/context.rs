@@ -154,6 +154,9 @@ pub enum Reason {
     /// The scope was supplied by the game engine. The `Token` points at a key explaining this, for
     /// example the key of an `Item` or the field key of a trigger or effect in an item.
     Builtin(Token),
+    /// The scope type was declared in the signature of a scripted trigger or effect. The `Token`
+    /// points at the declared type.
+    Declared(Token),
     /// The vic3 engine evaluates `multiplier` in `add_modifier` in root scope, which is probably a
     /// bug. Explain it to the user when it comes up. The `Token` points at the `multiplier` key.
     #[cfg(feature = "vic3")]
@@ -185,6 +188,17 @@ pub struct Signature {
     local_list_names: Vec<(&'static str, Scopes)>,
 }
 
+/// What validation found out about how a scripted trigger or effect must be called.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptSignature {
+    /// The scope types that `this` can be when it is called.
+    pub scopes: Scopes,
+    /// The named scopes that it uses or saves, with their scope types. Sorted by name.
+    pub named_scopes: Vec<(&'static str, Scopes)>,
+    /// The named lists that it uses or adds to, with their scope types. Sorted by name.
+    pub named_lists: Vec<(&'static str, Scopes)>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Temporary {
     No,
@@ -200,7 +214,7 @@ const PREV: usize = 2;
 impl Reason {
     pub fn token(&self) -> &Token {
         match self {
-            Reason::Token(t) | Reason::Name(t) | Reason::Builtin(t) => t,
+            Reason::Token(t) | Reason::Name(t) | Reason::Builtin(t) | Reason::Declared(t) => t,
             #[cfg(feature = "vic3")]
             Reason::MultiplierBug(t) => t,
             #[cfg(feature = "jomini")]
@@ -214,6 +228,7 @@ impl Reason {
             Reason::Token(t) => Cow::Owned(format!("deduced from `{t}` here")),
             Reason::Name(_) => Cow::Borrowed("deduced from the scope's name"),
             Reason::Builtin(_) => Cow::Borrowed("supplied by the game engine"),
+            Reason::Declared(_) => Cow::Borrowed("declared in its signature"),
             #[cfg(feature = "vic3")]
             Reason::MultiplierBug(_) => {
                 Cow::Borrowed("evaluated in root scope for `multiplier` (as of 1.9.8")
@@ -505,6 +520,39 @@ impl ScopeContext {
         }
     }
 
+    /// Declare that `this` is of the given scope types, as stated by the signature of a scripted
+    /// trigger or effect. `token` points at the declaration.
+    pub(crate) fn declare_this(&mut self, scopes: Scopes, token: &Token) {
+        *self.scope_stack.last_mut().unwrap() =
+            ScopeEntry::Scope(scopes, Reason::Declared(token.clone()));
+    }
+
+    /// Declare that `root` is of the given scope types, as stated by the signature of a scripted
+    /// trigger or effect. `token` points at the declaration.
+    pub(crate) fn declare_root(&mut self, scopes: Scopes, token: &Token) {
+        self.root = ScopeEntry::Scope(scopes, Reason::Declared(token.clone()));
+    }
+
+    /// Declare that the caller must supply the named scope `name` of the given scope types, as
+    /// stated by the signature of a scripted trigger or effect. `token` points at the declaration.
+    pub(crate) fn declare_input_name(&mut self, name: &'static str, scopes: Scopes, token: &Token) {
+        if !self.scope_names.contains_key(name) {
+            self.scope_names.insert(name, (self.named.len(), Temporary::No));
+            self.named.push(ScopeEntry::Scope(scopes, Reason::Declared(token.clone())));
+            self.is_input.push(Some(token.clone()));
+        }
+    }
+
+    /// Return the scope types of the named scope `name` if it was saved by the script itself,
+    /// rather than supplied by the caller.
+    pub(crate) fn saved_name_scopes(&self, name: &str, data: &Everything) -> Option<Scopes> {
+        let &(idx, temp) = self.scope_names.get(name)?;
+        if temp == Temporary::Wiped || self.is_input[idx].is_some() {
+            return None;
+        }
+        Some(self.resolve_named(idx, data).0)
+    }
+
     /// This is called when the script does `exists = local_var:name` or `has_local_variable = name`.
     ///
     /// It records `name` as "known", but with no scope type information, and records that the
@@ -799,6 +847,17 @@ impl ScopeContext {
         }
     }
 
+    /// Return the parts of this `ScopeContext` that callers of a scripted trigger or effect need
+    /// to know about.
+    pub fn script_signature(&self, data: &Everything) -> ScriptSignature {
+        let signature = self.signature(data);
+        ScriptSignature {
+            scopes: self.scopes(data),
+            named_scopes: signature.scope_names,
+            named_lists: signature.scope_list_names,
+        }
+    }
+
     /// Replace the `this` in a temporary scope level with the given `scopes` type and record
     /// `token` as the reason for this type.
     ///
@@ -1201,6 +1260,55 @@ impl ScopeContext {
         }
     }
 
+    /// Describe everything this `ScopeContext` knows about its scopes: `root`, `this`, `prev` if
+    /// known, and the named scopes, lists and local variables, each with its scope types and the
+    /// reason for them. The `bool` is true for names that are expected to be set by the caller.
+    ///
+    /// This is for `--explain`, which shows users why tiger thinks what it does.
+    pub(crate) fn explain(&self, data: &Everything) -> Vec<(String, Scopes, &Reason, bool)> {
+        let mut result = Vec::new();
+        let (scopes, reason) = self.resolve_root();
+        result.push(("root".to_owned(), scopes, reason, false));
+        let (scopes, reason) = self.scopes_reason(data);
+        result.push(("this".to_owned(), scopes, reason, false));
+        if self.scope_stack.len() >= PREV {
+            let (scopes, reason) = self.scopes_reason_backref(PREV, data);
+            result.push(("prev".to_owned(), scopes, reason, false));
+        }
+
+        let mut names = Vec::new();
+        for (name, (idx, temp)) in &self.scope_names {
+            if *temp != Temporary::Wiped {
+                names.push((format!("scope:{name}"), *idx));
+            }
+        }
+        for (name, (idx, temp)) in &self.scope_list_names {
+            if *temp != Temporary::Wiped {
+                names.push((format!("list {name}"), *idx));
+            }
+        }
+        for (name, idx) in &self.local_names {
+            names.push((format!("local_var:{name}"), *idx));
+        }
+        for (name, idx) in &self.local_list_names {
+            names.push((format!("local list {name}"), *idx));
+        }
+        names.sort();
+        for (name, idx) in names {
+            let (scopes, reason) = self.resolve_named(idx, data);
+            result.push((name, scopes, reason, self.is_input[idx].is_some()));
+        }
+        result
+    }
+
+    /// Return where this `ScopeContext` was created, followed by the events and on-actions that
+    /// were triggered on the way from there, oldest first.
+    pub(crate) fn origin(&self) -> Vec<&Token> {
+        let mut result = vec![&self.source];
+        result.extend(self.traceback.iter().map(ActionOrEvent::token));
+        result
+    }
+
     /// Add messages to a report that describe where this `ScopeContext` came from.
     pub fn log_traceback(&self, mut builder: ReportBuilderFull) -> ReportBuilderFull {
         for elem in self.traceback.iter().rev() {
@@ -1,6 +1,8 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
 
 use rayon::prelude::*;
 
@@ -8,15 +10,16 @@ use crate::block::{Block, BlockItem, Field};
 use crate::context::{Reason, ScopeContext, Signature};
 use crate::data::scripted_effects::Effect;
 use crate::data::scripted_triggers::Trigger;
+use crate::event_graph::EntryPoints;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 use crate::game::Game;
-use crate::helpers::{TigerHashMap, TigerHashSet, dup_error};
+use crate::helpers::{TigerHashMap, TigerHashSet, dup_error, report_unused};
 use crate::item::Item;
 use crate::parse::ParserMemory;
 use crate::pathtable::PathTableIndex;
 use crate::pdxfile::PdxFile;
-use crate::report::{ErrorKey, err, warn};
+use crate::report::{Confidence, ErrorKey, err, warn};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::variables::Variables;
@@ -62,7 +65,7 @@ impl Events {
         if let Some(other) = self.triggers.get(&index) {
             dup_error(&key, &other.key, "scripted trigger");
         }
-        self.triggers.insert(index, Trigger::new(key, block, None));
+        self.triggers.insert(index, Trigger::new(key, block, None, None));
     }
 
     fn load_scripted_effect(&mut self, key: Token, block: Block) {
@@ -70,7 +73,7 @@ impl Events {
         if let Some(other) = self.effects.get(&index) {
             dup_error(&key, &other.key, "scripted effect");
         }
-        self.effects.insert(index, Effect::new(key, block, None));
+        self.effects.insert(index, Effect::new(key, block, None, None));
     }
 
     pub fn scan_variables(&self, registry: &mut Variables) {
@@ -88,16 +91,16 @@ impl Events {
     #[cfg(any(feature = "ck3", feature = "eu5"))]
     pub fn get_trigger(&self, key: &Token) -> Option<&Trigger> {
         let index = (key.loc.idx, key.as_str());
-        self.triggers.get(&index)
+        self.triggers.get(&index).inspect(|item| item.mark_used())
     }
 
     #[cfg(any(feature = "ck3", feature = "eu5"))]
     pub fn get_effect(&self, key: &Token) -> Option<&Effect> {
         let index = (key.loc.idx, key.as_str());
-        self.effects.get(&index)
+        self.effects.get(&index).inspect(|item| item.mark_used())
     }
 
-    fn get_event<'a>(&'a self, key: &'a str) -> Option<&'a Event> {
+    pub fn get_event<'a>(&'a self, key: &'a str) -> Option<&'a Event> {
         if let Some((namespace, id)) = key.split_once('.')
             && let Ok(id) = u16::from_str(id)
         {
@@ -106,8 +109,13 @@ impl Events {
         None
     }
 
+    /// Like `get_event`, but records that the event is used.
+    fn use_event<'a>(&'a self, key: &'a str) -> Option<&'a Event> {
+        self.get_event(key).inspect(|event| event.used.store(true, Relaxed))
+    }
+
     pub fn check_scope(&self, token: &Token, sc: &mut ScopeContext, data: &Everything) {
-        if let Some(event) = self.get_event(token.as_str()) {
+        if let Some(event) = self.use_event(token.as_str()) {
             sc.expect(event.expects_scope, &Reason::Token(token.clone()), data);
         }
     }
@@ -121,13 +129,7 @@ impl Events {
     }
 
     pub fn exists(&self, key: &str) -> bool {
-        if let Some((namespace, id)) = key.split_once('.')
-            && let Ok(id) = u16::from_str(id)
-            && self.events.contains_key(&(namespace, id))
-        {
-            return true;
-        }
-        false
+        self.use_event(key).is_some()
     }
 
     pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
@@ -136,20 +138,69 @@ impl Events {
 
     pub fn validate(&self, data: &Everything) {
         for item in self.effects.values() {
-            item.validate(data);
+            if data.should_validate(item.key.loc) {
+                item.validate(data);
+            }
         }
 
         for item in self.triggers.values() {
-            item.validate(data);
+            if data.should_validate(item.key.loc) {
+                item.validate(data);
+            }
         }
 
         self.events.par_iter().for_each(|(_, item)| {
-            item.validate(data);
+            if data.should_validate(item.key.loc) {
+                item.validate(data);
+            }
         });
     }
 
+    /// Forget the cached validation results, so that the next validation starts fresh.
+    pub fn clear_caches(&mut self) {
+        for item in self.events.values_mut() {
+            item.visited.get_mut().unwrap().clear();
+        }
+        for item in self.triggers.values_mut() {
+            item.clear_cache();
+        }
+        for item in self.effects.values_mut() {
+            item.clear_cache();
+        }
+    }
+
+    pub fn check_unused(&self, entry_points: &EntryPoints) {
+        let unused = self
+            .events
+            .values()
+            .filter(|item| !item.used.load(Relaxed) && !entry_points.has_event(item.key.as_str()));
+        let msg = "Event is never fired";
+        report_unused(
+            ErrorKey::UnusedDefinition,
+            Confidence::Reasonable,
+            msg,
+            unused.map(|i| &i.key),
+        );
+        let unused = self.triggers.values().filter(|item| !item.is_used());
+        let msg = "Unused scripted trigger";
+        report_unused(
+            ErrorKey::UnusedDefinition,
+            Confidence::Reasonable,
+            msg,
+            unused.map(|i| &i.key),
+        );
+        let unused = self.effects.values().filter(|item| !item.is_used());
+        let msg = "Unused scripted effect";
+        report_unused(
+            ErrorKey::UnusedDefinition,
+            Confidence::Reasonable,
+            msg,
+            unused.map(|i| &i.key),
+        );
+    }
+
     pub fn validate_call(&self, key: &Token, data: &Everything, sc: &mut ScopeContext) {
-        if let Some(event) = self.get_event(key.as_str()) {
+        if let Some(event) = self.use_event(key.as_str()) {
             event.validate_call(data, sc);
         }
     }
@@ -228,6 +279,27 @@ impl FileHandler<Block> for Events {
             }
         }
     }
+
+    fn defined_keys(&self, block: &Block) -> Vec<&'static str> {
+        block
+            .iter_fields()
+            .filter_map(|Field(key, _, bv)| {
+                if key.is("namespace") {
+                    bv.get_value().map(Token::as_str)
+                } else {
+                    bv.get_block().map(|_| key.as_str())
+                }
+            })
+            .collect()
+    }
+
+    fn forget_files(&mut self, forget: &dyn Fn(&Path) -> bool) -> bool {
+        self.events.retain(|_, event| !forget(event.key.loc.pathname()));
+        self.namespaces.retain(|namespace| !forget(namespace.loc.pathname()));
+        self.triggers.retain(|_, trigger| !forget(trigger.key.loc.pathname()));
+        self.effects.retain(|_, effect| !forget(effect.key.loc.pathname()));
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -237,9 +309,21 @@ pub struct Event {
     expects_scope: Scopes,
     expects_from_token: Token,
     visited: Mutex<TigerHashSet<Signature>>,
+    /// Whether anything fires this event
+    used: AtomicBool,
 }
 
 impl Event {
+    /// Return whether anything fires this event.
+    pub fn is_used(&self) -> bool {
+        self.used.load(Relaxed)
+    }
+
+    /// Return the scope types this event can be fired in.
+    pub fn expects_scope(&self) -> Scopes {
+        self.expects_scope
+    }
+
     pub fn new(key: Token, block: Block) -> Self {
         let (expects_scope, expects_from_token) = match Game::game() {
             #[cfg(feature = "ck3")]
@@ -254,7 +338,8 @@ impl Event {
             Game::Hoi4 => unimplemented!(),
         };
         let visited = Mutex::new(TigerHashSet::default());
-        Self { key, block, expects_scope, expects_from_token, visited }
+        let used = AtomicBool::new(false);
+        Self { key, block, expects_scope, expects_from_token, visited, used }
     }
 
     pub fn validate(&self, data: &Everything) {
@@ -265,7 +350,13 @@ impl Event {
             let info = "otherwise the event won't be found in-game";
             err(ErrorKey::EventNamespace).msg(msg).info(info).loc(&self.key).push();
         }
+        let sc = self.validated_scope_context(data);
+        data.explain.record_context("event", &self.key, &sc);
+    }
 
+    /// Validate the event on its own, as if fired from nowhere in particular, and return the
+    /// scope context it ended up with.
+    fn validated_scope_context(&self, data: &Everything) -> ScopeContext {
         let mut sc = ScopeContext::new(self.expects_scope, &self.expects_from_token);
         sc.set_strict_scopes(false);
         sc.set_source(&self.key);
@@ -282,6 +373,7 @@ impl Event {
             #[cfg(feature = "hoi4")]
             Game::Hoi4 => unimplemented!(),
         }
+        sc
     }
 
     pub fn validate_call(&self, data: &Everything, sc: &mut ScopeContext) {
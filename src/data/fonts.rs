@@ -1,7 +1,13 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
 use crate::block::Block;
+use crate::data::localization::Language;
 use crate::db::{Db, DbKind};
 use crate::everything::Everything;
 use crate::game::GameFlags;
+use crate::helpers::{TigerHashMap, TigerHashSet};
 use crate::item::{Item, ItemLoader, LoadAsFile, Recursive};
 use crate::pdxfile::PdxEncoding;
 use crate::report::{ErrorKey, Severity, untidy, warn};
@@ -75,7 +81,10 @@ impl DbKind for Font {
 pub struct Fontfiles {}
 
 impl DbKind for Fontfiles {
-    fn validate(&self, _key: &Token, block: &Block, data: &Everything) {
+    fn validate(&self, key: &Token, block: &Block, data: &Everything) {
+        // The game loads these itself, whether a font refers to them or not.
+        data.database.exists(Item::Fontfiles, key.as_str());
+
         let mut vd = Validator::new(block, data);
         vd.set_max_severity(Severity::Warning);
         vd.field_value("name");
@@ -88,3 +97,217 @@ impl DbKind for Fontfiles {
         });
     }
 }
+
+/// The characters that a font file has glyphs for, as sorted ranges of code points.
+#[derive(Clone, Debug, Default)]
+pub struct GlyphCoverage(Vec<(u32, u32)>);
+
+impl GlyphCoverage {
+    /// Read the character map of a TrueType or OpenType font, or of the first font in a
+    /// collection. Returns `None` if the file is not a font or has no Unicode character map.
+    fn read(fullpath: &Path) -> Option<Self> {
+        let mut file = File::open(fullpath).ok()?;
+        let mut header = [0; 12];
+        file.read_exact(&mut header).ok()?;
+        if header.starts_with(b"ttcf") {
+            let mut offset = [0; 4];
+            file.read_exact(&mut offset).ok()?;
+            file.seek(SeekFrom::Start(u64::from(u32::from_be_bytes(offset)))).ok()?;
+            file.read_exact(&mut header).ok()?;
+        }
+        let num_tables = usize::from(be16(&header, 4)?);
+        let mut records = vec![0; num_tables * 16];
+        file.read_exact(&mut records).ok()?;
+        let record = records.chunks_exact(16).find(|record| record.starts_with(b"cmap"))?;
+        let mut cmap = vec![0; usize::try_from(be32(record, 12)?).ok()?];
+        file.seek(SeekFrom::Start(u64::from(be32(record, 8)?))).ok()?;
+        file.read_exact(&mut cmap).ok()?;
+        Self::from_cmap(&cmap)
+    }
+
+    /// Parse a `cmap` table. The full Unicode subtables (format 12) are preferred over the ones
+    /// for the Basic Multilingual Plane (format 4).
+    fn from_cmap(cmap: &[u8]) -> Option<Self> {
+        let num_subtables = usize::from(be16(cmap, 2)?);
+        let mut bmp = None;
+        for i in 0..num_subtables {
+            let platform = be16(cmap, 4 + i * 8)?;
+            let encoding = be16(cmap, 6 + i * 8)?;
+            let subtable = cmap.get(usize::try_from(be32(cmap, 8 + i * 8)?).ok()?..)?;
+            match (platform, encoding, be16(subtable, 0)?) {
+                (0 | 3, _, 12) => return Self::from_format12(subtable),
+                (0, _, 4) | (3, 1, 4) => bmp = Some(subtable),
+                _ => (),
+            }
+        }
+        Self::from_format4(bmp?)
+    }
+
+    fn from_format4(subtable: &[u8]) -> Option<Self> {
+        // The table stores twice the number of segments.
+        let segments = usize::from(be16(subtable, 6)? >> 1);
+        let ends = 14;
+        let starts = ends + segments * 2 + 2;
+        let deltas = starts + segments * 2;
+        let range_offsets = deltas + segments * 2;
+        let mut ranges = Vec::new();
+        for i in 0..segments {
+            let end = be16(subtable, ends + i * 2)?;
+            let start = be16(subtable, starts + i * 2)?;
+            let delta = be16(subtable, deltas + i * 2)?;
+            let range_offset = usize::from(be16(subtable, range_offsets + i * 2)?);
+            for c in start..=end {
+                if c == 0xffff {
+                    break;
+                }
+                let glyph = if range_offset == 0 {
+                    c.wrapping_add(delta)
+                } else {
+                    let index = range_offsets + i * 2 + range_offset + usize::from(c - start) * 2;
+                    match be16(subtable, index)? {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(delta),
+                    }
+                };
+                if glyph != 0 {
+                    push_range(&mut ranges, u32::from(c), u32::from(c));
+                }
+            }
+        }
+        Some(Self(ranges))
+    }
+
+    fn from_format12(subtable: &[u8]) -> Option<Self> {
+        let groups = usize::try_from(be32(subtable, 12)?).ok()?;
+        let mut ranges = Vec::new();
+        for i in 0..groups {
+            let start = be32(subtable, 16 + i * 12)?;
+            let end = be32(subtable, 20 + i * 12)?;
+            if be32(subtable, 24 + i * 12)? == 0 {
+                // The first character maps to the missing glyph.
+                if start < end {
+                    push_range(&mut ranges, start + 1, end);
+                }
+            } else {
+                push_range(&mut ranges, start, end);
+            }
+        }
+        ranges.sort_unstable();
+        Some(Self(ranges))
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        let c = u32::from(c);
+        let i = self.0.partition_point(|(_, end)| *end < c);
+        self.0.get(i).is_some_and(|(start, _)| *start <= c)
+    }
+}
+
+/// Add a range to a sorted list of ranges, merging it with the last one if they touch.
+fn push_range(ranges: &mut Vec<(u32, u32)>, start: u32, end: u32) {
+    if let Some(last) = ranges.last_mut()
+        && last.1 + 1 == start
+    {
+        last.1 = end;
+    } else {
+        ranges.push((start, end));
+    }
+}
+
+fn be16(buffer: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(buffer.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn be32(buffer: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(buffer.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// The glyph coverage of the font files in the `fontfiles` groups, and which languages they are
+/// used for.
+///
+/// Reading the fonts is slow, so this is kept across validations until one of the files it was
+/// made from changes.
+#[derive(Debug, Default)]
+pub struct FontCoverage {
+    files: Vec<GlyphCoverage>,
+    /// The languages of each group, or `None` if it's for all languages, with the indexes into
+    /// `files` of its fonts.
+    groups: Vec<(Option<Vec<Language>>, Vec<usize>)>,
+    /// The `.font` files and the font files that this was made from.
+    sources: TigerHashSet<PathBuf>,
+    /// The font files that couldn't be read.
+    unreadable: Vec<Token>,
+}
+
+impl FontCoverage {
+    pub fn load(data: &Everything) -> Self {
+        let mut coverage = Self::default();
+        let mut loaded: TigerHashMap<&str, Option<usize>> = TigerHashMap::default();
+        for (key, block) in data.database.iter_key_block(Item::Fontfiles) {
+            coverage.sources.insert(key.loc.pathname().to_path_buf());
+            for group in block.get_field_blocks("group") {
+                let langs = group.get_field_list("languages").map(|tokens| {
+                    tokens
+                        .iter()
+                        .filter_map(|token| {
+                            let name = token.as_str();
+                            Language::try_from(name.strip_prefix("l_").unwrap_or(name)).ok()
+                        })
+                        .collect()
+                });
+                let mut files = Vec::new();
+                for token in group.get_field_list("files").unwrap_or_default() {
+                    let index = *loaded.entry(token.as_str()).or_insert_with(|| {
+                        let entry = data.fileset.get_entry(Path::new(token.as_str()))?;
+                        coverage.sources.insert(entry.path().to_path_buf());
+                        if let Some(glyphs) = GlyphCoverage::read(entry.fullpath()) {
+                            coverage.files.push(glyphs);
+                            Some(coverage.files.len() - 1)
+                        } else {
+                            coverage.unreadable.push(token.clone());
+                            None
+                        }
+                    });
+                    files.extend(index);
+                }
+                coverage.groups.push((langs, files));
+            }
+        }
+        coverage
+    }
+
+    /// Return whether this was made from any of `pathnames`, or may have been made from them if
+    /// they are new.
+    pub fn depends_on(&self, pathnames: &[PathBuf]) -> bool {
+        pathnames.iter().any(|pathname| {
+            self.sources.contains(pathname) || pathname.extension().is_some_and(|ext| ext == "font")
+        })
+    }
+
+    /// Warn about the font files that couldn't be read.
+    pub fn validate(&self, data: &Everything) {
+        for token in &self.unreadable {
+            if data.should_validate(token.loc) {
+                let msg = "could not read the character map of this font";
+                warn(ErrorKey::ReadError).msg(msg).loc(token).push();
+            }
+        }
+    }
+
+    /// Return whether any font is known to be used for `lang`.
+    pub fn has_fonts(&self, lang: Language) -> bool {
+        self.fonts(lang).next().is_some()
+    }
+
+    /// Return whether any of the fonts used for `lang` has a glyph for `c`.
+    pub fn has_glyph(&self, lang: Language, c: char) -> bool {
+        self.fonts(lang).any(|glyphs| glyphs.contains(c))
+    }
+
+    fn fonts(&self, lang: Language) -> impl Iterator<Item = &GlyphCoverage> {
+        self.groups
+            .iter()
+            .filter(move |(langs, _)| langs.as_ref().is_none_or(|langs| langs.contains(&lang)))
+            .flat_map(|(_, files)| files.iter().map(|i| &self.files[*i]))
+    }
+}
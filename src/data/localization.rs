@@ -3,14 +3,17 @@
 use std::borrow::Borrow;
 use std::cmp::Ordering;
 use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
 use std::ffi::OsStr;
+use std::fmt::Write as _;
 use std::fs::read_to_string;
 #[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator"))]
 use std::io::Cursor;
+use std::mem::take;
 use std::path::{Path, PathBuf};
-use std::sync::LazyLock;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering::Relaxed;
+use std::sync::{LazyLock, Mutex};
 
 use bitvec::order::Lsb0;
 use bitvec::{BitArr, bitarr};
@@ -25,8 +28,10 @@ use crate::block::Block;
 #[cfg(feature = "ck3")]
 use crate::ck3::tables::localization::{BUILTIN_MACROS_CK3, COMPLEX_TOOLTIPS_CK3};
 use crate::context::ScopeContext;
+#[cfg(feature = "jomini")]
+use crate::data::fonts::FontCoverage;
 use crate::datacontext::DataContext;
-use crate::datatype::{CodeChain, Datatype, validate_datatypes};
+use crate::datatype::{CodeArg, CodeChain, Datatype, validate_datatypes};
 #[cfg(feature = "eu5")]
 use crate::eu5::tables::localization::BUILTIN_MACROS_EU5;
 use crate::everything::Everything;
@@ -34,18 +39,20 @@ use crate::fileset::{FileEntry, FileHandler, FileKind};
 use crate::game::Game;
 #[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator"))]
 use crate::helpers::TigerHashMapExt;
-use crate::helpers::{TigerHashMap, dup_error, stringify_list};
+use crate::helpers::{TigerHashMap, TigerHashSet, dup_error, stringify_list};
 #[cfg(feature = "hoi4")]
 use crate::hoi4::tables::localization::BUILTIN_MACROS_HOI4;
 #[cfg(feature = "imperator")]
 use crate::imperator::tables::localization::BUILTIN_MACROS_IMPERATOR;
 use crate::item::{Item, ItemExt};
-use crate::macros::{MACRO_MAP, MacroMapIndex};
+use crate::loca_formats::{ImportedTranslation, TranslationUnit};
+use crate::loca_snapshot::{LocaSnapshot, Translated};
+use crate::macros::{MacroMapIndex, macro_map};
 use crate::parse::ParserMemory;
 use crate::parse::localization::{ValueParser, parse_loca};
 use crate::report::{ErrorKey, Severity, err, report, tips, warn};
 use crate::scopes::Scopes;
-use crate::token::Token;
+use crate::token::{Loc, Token};
 #[cfg(feature = "vic3")]
 use crate::vic3::tables::localization::BUILTIN_MACROS_VIC3;
 
@@ -77,6 +84,16 @@ pub struct Localization {
     mod_langs: BitArr!(for Language::COUNT, in u16),
     /// Database of all localizations, indexed first by language and then by localization key.
     locas: Languages,
+    /// Entries that lost out to another entry with the same key, indexed by language.
+    /// They are kept so that they can take over when the files they lost to are reloaded.
+    shadowed: [Vec<LocaEntry>; Language::COUNT],
+    /// The language that translations are compared with, according to the config file.
+    base_lang: Language,
+    /// Whether the config file asks for translations to be checked for parity with the base
+    /// language.
+    check_parity: bool,
+    /// The keys that were reported missing from the mod, indexed by language.
+    missing: [Mutex<BTreeSet<String>>; Language::COUNT],
 }
 
 /// List of languages that are supported by the game engine.
@@ -132,6 +149,130 @@ impl Language {
     fn to_idx(self) -> usize {
         self as usize
     }
+
+    /// The language code that translation tools use for this language.
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Spanish => "es",
+            Language::French => "fr",
+            Language::German => "de",
+            Language::Russian => "ru",
+            #[cfg(any(feature = "ck3", feature = "vic3", feature = "eu5"))]
+            Language::Korean => "ko",
+            Language::SimpChinese => "zh_CN",
+            #[cfg(any(feature = "vic3", feature = "hoi4", feature = "eu5"))]
+            Language::BrazPor => "pt_BR",
+            #[cfg(any(feature = "ck3", feature = "vic3", feature = "hoi4", feature = "eu5"))]
+            Language::Japanese => "ja",
+            #[cfg(any(feature = "ck3", feature = "vic3", feature = "hoi4", feature = "eu5"))]
+            Language::Polish => "pl",
+            #[cfg(any(feature = "vic3", feature = "eu5"))]
+            Language::Turkish => "tr",
+        }
+    }
+
+    /// Find the language for a language code like `fr`, `fr_FR` or `pt-BR`.
+    /// A code with a region that doesn't matter is accepted too.
+    pub(crate) fn from_code(code: &str) -> Option<Self> {
+        let code = code.replace('-', "_");
+        Language::iter().find(|lang| lang.code().eq_ignore_ascii_case(&code)).or_else(|| {
+            let primary = code.split('_').next()?;
+            Language::iter().find(|lang| lang.code().eq_ignore_ascii_case(primary))
+        })
+    }
+}
+
+/// Collect the plain text parts of a localization value.
+#[cfg(feature = "jomini")]
+fn collect_texts<'a>(value: &'a LocaValue, texts: &mut Vec<&'a Token>) {
+    match value {
+        LocaValue::Concat(v) => {
+            for value in v {
+                collect_texts(value, texts);
+            }
+        }
+        LocaValue::Text(token) => texts.push(token),
+        _ => (),
+    }
+}
+
+/// Append a line for the entry `key` to the contents of a localization file. Line breaks in the
+/// text are written as `\n`, because an entry has to fit on one line.
+fn write_loca_line(contents: &mut String, key: &str, version: Option<u32>, text: &str) {
+    let version = version.map(|v| v.to_string()).unwrap_or_default();
+    let text = text.replace("\r\n", "\\n").replace('\n', "\\n");
+    _ = writeln!(contents, " {key}:{version} \"{text}\"");
+}
+
+/// Write `translations` into the `contents` of a `lang` localization file, or into a new file if
+/// there are no contents yet.
+///
+/// An entry that the file already has is replaced in place, and the rest of the file, with its
+/// comments and blank lines, is kept as it is. The other translations are added at the end.
+pub(crate) fn merge_translations(
+    contents: Option<&str>,
+    lang: Language,
+    translations: &[ImportedTranslation],
+) -> String {
+    let mut result = String::new();
+    let mut placed = vec![false; translations.len()];
+    if let Some(contents) = contents {
+        for (linenr, line) in (1..).zip(contents.split_inclusive('\n')) {
+            let entry = line.trim_start();
+            let found = translations.iter().position(|t| {
+                t.line == Some(linenr)
+                    && entry.strip_prefix(t.key).is_some_and(|rest| rest.starts_with(':'))
+            });
+            let Some(idx) = found else {
+                result.push_str(line);
+                continue;
+            };
+            placed[idx] = true;
+            let t = &translations[idx];
+            result.push_str(&line[..line.len() - entry.len()]);
+            let mut new_line = String::new();
+            write_loca_line(&mut new_line, t.key, Some(t.version), t.text);
+            result.push_str(new_line.trim_start());
+            if line.ends_with("\r\n") {
+                result.insert(result.len() - 1, '\r');
+            }
+        }
+        if !result.is_empty() && !result.ends_with('\n') {
+            result.push('\n');
+        }
+    } else {
+        _ = writeln!(result, "\u{feff}l_{lang}:");
+    }
+    for (t, placed) in translations.iter().zip(placed) {
+        if !placed {
+            write_loca_line(&mut result, t.key, Some(t.version), t.text);
+        }
+    }
+    result
+}
+
+/// Return the pathname of the `lang` file that mirrors the base language file `pathname`, with
+/// the language's name in its folders and file name replaced.
+fn translated_pathname(pathname: &Path, base: Language, lang: Language) -> PathBuf {
+    let base_str: &str = base.into();
+    let lang_str: &str = lang.into();
+    let mut result = PathBuf::new();
+    if let Some(dir) = pathname.parent() {
+        for component in dir {
+            if component == base_str {
+                result.push(lang_str);
+            } else {
+                result.push(component);
+            }
+        }
+    }
+    let filename = pathname.file_stem().unwrap_or_default().to_string_lossy();
+    let filename = filename
+        .strip_suffix(&format!("_l_{base}"))
+        .map_or_else(|| format!("{filename}_l_{lang}.yml"), |stem| format!("{stem}_l_{lang}.yml"));
+    result.push(filename);
+    result
 }
 
 /// List of known built-in keys used between `$...$` in any localization.
@@ -161,6 +302,9 @@ pub struct LocaEntry {
     /// The original unparsed value, with enclosing `"` stripped.
     /// This is used for macro replacement.
     orig: Option<Token>,
+    /// The version number after the `:`, which is raised when the text changes so that the
+    /// translations can be updated.
+    version: Option<u32>,
     /// Whether this entry has been "used" (looked up) by anything in the mod
     used: AtomicBool,
     /// Whether this entry has been validated with a `ScopeContext`
@@ -189,7 +333,20 @@ impl Ord for LocaEntry {
 
 impl LocaEntry {
     pub fn new(key: Token, value: LocaValue, orig: Option<Token>) -> Self {
-        Self { key, value, orig, used: AtomicBool::new(false), validated: AtomicBool::new(false) }
+        Self {
+            key,
+            value,
+            orig,
+            version: None,
+            used: AtomicBool::new(false),
+            validated: AtomicBool::new(false),
+        }
+    }
+
+    #[must_use]
+    pub fn with_version(mut self, version: Option<u32>) -> Self {
+        self.version = version;
+        self
     }
 
     // returns false to abort expansion in case of an error
@@ -221,7 +378,7 @@ impl LocaEntry {
                                 from,
                                 count,
                                 sc,
-                                Some(MACRO_MAP.get_or_insert_loc(keyword.loc)),
+                                Some(macro_map().get_or_insert_loc(keyword.loc)),
                                 data,
                             ) {
                                 return false;
@@ -295,6 +452,104 @@ pub enum MacroValue {
     Keyword(Token),
 }
 
+/// The parts of a localization value that a translation should keep: the `$macro$` references,
+/// `[code]` calls and `@icon!` texticons, each with where it first occurs, and the number of
+/// `#markup` sections that are left open.
+#[derive(Debug, Default)]
+struct LocaShape {
+    parts: BTreeMap<String, Token>,
+    open_markup: i32,
+}
+
+impl LocaShape {
+    fn new(entry: &LocaEntry) -> Option<Self> {
+        let mut shape = Self::default();
+        if let LocaValue::Macro(v) = &entry.value {
+            // Put the macro keywords back as plain text, to see the structure around them.
+            let mut tokens = Vec::new();
+            for macrovalue in v {
+                match macrovalue {
+                    MacroValue::Text(token) => tokens.push(token),
+                    MacroValue::Keyword(keyword) => {
+                        shape.insert(format!("${keyword}$"), keyword);
+                        tokens.push(keyword);
+                    }
+                }
+            }
+            if !tokens.is_empty() {
+                shape.add(&ValueParser::new(tokens).parse())?;
+            }
+        } else {
+            shape.add(&entry.value)?;
+        }
+        Some(shape)
+    }
+
+    fn insert(&mut self, part: String, token: &Token) {
+        self.parts.entry(part).or_insert_with(|| token.clone());
+    }
+
+    /// Returns `None` if the value could not be parsed.
+    fn add(&mut self, value: &LocaValue) -> Option<()> {
+        match value {
+            LocaValue::Concat(v) | LocaValue::CalculatedIcon(v) => {
+                for value in v {
+                    self.add(value)?;
+                }
+            }
+            #[allow(unused_variables)] // format only used by ck3
+            LocaValue::Code(chain, format) => {
+                let first = &chain.codes.first()?.name;
+                #[cfg(feature = "ck3")]
+                if Game::is_ck3()
+                    && let Some(format) = format
+                    && (format.as_str().contains('E') || format.as_str().contains('e'))
+                    && let Some(name) = chain.as_gameconcept()
+                {
+                    self.insert(format!("[{name}|E]"), first);
+                    return Some(());
+                }
+                self.insert(format!("[{}]", code_chain_text(chain)), first);
+            }
+            LocaValue::Icon(token) => self.insert(format!("@{token}!"), token),
+            LocaValue::Markup => self.open_markup += 1,
+            LocaValue::MarkupEnd => self.open_markup -= 1,
+            LocaValue::Error => return None,
+            _ => (),
+        }
+        Some(())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.parts.is_empty() && self.open_markup == 0
+    }
+}
+
+/// Write a code chain the way it appears in localization, but without the contents of its
+/// literal arguments, because those may be translated.
+fn code_chain_text(chain: &CodeChain) -> String {
+    let codes: Vec<_> = chain
+        .codes
+        .iter()
+        .map(|code| {
+            if code.arguments.is_empty() {
+                code.name.to_string()
+            } else {
+                let args: Vec<_> = code
+                    .arguments
+                    .iter()
+                    .map(|arg| match arg {
+                        CodeArg::Chain(chain) => code_chain_text(chain),
+                        CodeArg::Literal(_) => "'...'".to_owned(),
+                    })
+                    .collect();
+                format!("{}({})", code.name, args.join(","))
+            }
+        })
+        .collect();
+    codes.join(".")
+}
+
 fn get_file_lang(filename: &OsStr) -> Option<Language> {
     // Deliberate discrepancy here between the check and the error msg below.
     // `l_{}` anywhere in the filename works, but `_l_{}.yml` is still recommended.
@@ -306,10 +561,17 @@ fn get_file_lang(filename: &OsStr) -> Option<Language> {
 }
 
 impl Localization {
-    fn iter_lang(&self) -> impl Iterator<Item = Language> {
+    pub fn iter_lang(&self) -> impl Iterator<Item = Language> {
         Language::iter().filter(|i| self.mod_langs[i.to_idx()])
     }
 
+    /// Return the text of the entry `key` in language `lang`, without the enclosing `"`.
+    /// Returns `None` if there is no such entry or if its text could not be parsed.
+    /// The entry is not marked as used.
+    pub fn get_text(&self, key: &str, lang: Language) -> Option<&Token> {
+        self.locas[lang].get(key).and_then(|entry| entry.orig.as_ref())
+    }
+
     pub fn exists(&self, key: &str) -> bool {
         for lang in self.iter_lang() {
             if !self.locas[lang].contains_key(key) {
@@ -349,6 +611,7 @@ impl Localization {
         }
         let langs_missing = self.mark_used_return_missing(key);
         if !langs_missing.is_empty() {
+            self.record_missing(key, &langs_missing, token);
             let msg = format!("missing {} localization key {key}", stringify_list(&langs_missing));
             // TODO: get confidence level from caller
             report(ErrorKey::MissingLocalization, Item::Localization.severity().at_most(max_sev))
@@ -385,6 +648,7 @@ impl Localization {
                 Severity::Warning
             };
 
+            self.record_missing(name.as_str(), &langs_missing, name);
             let msg =
                 format!("missing {} localization for name {name}", stringify_list(&langs_missing));
             report(ErrorKey::MissingLocalization, sev.at_most(max_sev))
@@ -413,6 +677,7 @@ impl Localization {
         }
         if let Some(lang) = lang {
             if !self.mark_used_lang_return_exists(key, lang) {
+                self.record_missing(key, &[lang.into()], token);
                 let msg = format!("missing {lang} localization key {key}");
                 // TODO: get confidence level from caller
                 warn(ErrorKey::MissingLocalization).msg(msg).loc(token).push();
@@ -444,6 +709,142 @@ impl Localization {
         langs_missing
     }
 
+    /// Remember that `key` is missing in `langs`, for [`Localization::missing_stubs`].
+    /// Only keys that are needed by the mod itself are remembered.
+    fn record_missing(&self, key: &str, langs: &[&'static str], token: &Token) {
+        if token.loc.kind != FileKind::Mod {
+            return;
+        }
+        for lang in Language::iter().filter(|lang| langs.contains(&(*lang).into())) {
+            self.missing[lang.to_idx()].lock().unwrap().insert(key.to_owned());
+        }
+    }
+
+    /// Make the lines to add to the stub file for `lang`. They have the keys that were reported
+    /// missing in `lang`, with the base language text or else the key as their text.
+    ///
+    /// Returns the lines and the number of keys that were missing, or `None` if there are none.
+    pub(crate) fn missing_stubs(&self, lang: Language) -> Option<(String, usize)> {
+        let missing = self.missing[lang.to_idx()].lock().unwrap();
+        if missing.is_empty() {
+            return None;
+        }
+
+        let mut lines = String::new();
+        for key in missing.iter() {
+            let text = self.locas[self.base_lang]
+                .get(key.as_str())
+                .and_then(|entry| entry.orig.as_ref())
+                .map_or(key.as_str(), |orig| orig.as_str());
+            write_loca_line(&mut lines, key, Some(0), text);
+        }
+        Some((lines, missing.len()))
+    }
+
+    /// The language that translations are compared with.
+    pub(crate) fn base_lang(&self) -> Language {
+        self.base_lang
+    }
+
+    /// Return the languages that the mod can be translated into: the checked languages other than
+    /// the base language.
+    pub(crate) fn translation_langs(&self) -> Vec<Language> {
+        Language::iter()
+            .filter(|lang| *lang != self.base_lang && self.check_langs[lang.to_idx()])
+            .collect()
+    }
+
+    /// Return a unit for each of the mod's entries in the base language that has text, with its
+    /// translation into `lang` if there is one.
+    pub(crate) fn translation_units(&self, lang: Language) -> Vec<TranslationUnit> {
+        let mut entries: Vec<_> = self.locas[self.base_lang]
+            .values()
+            .filter(|entry| entry.key.loc.kind == FileKind::Mod)
+            .collect();
+        entries.sort_unstable();
+        entries
+            .into_iter()
+            .filter_map(|entry| {
+                let source = entry.orig.as_ref().filter(|orig| !orig.as_str().is_empty())?;
+                let target = self.locas[lang]
+                    .get(entry.key.as_str())
+                    .and_then(|entry| entry.orig.as_ref())
+                    .map_or("", |orig| orig.as_str());
+                Some(TranslationUnit {
+                    key: entry.key.to_string(),
+                    source: source.to_string(),
+                    target: target.to_owned(),
+                    location: format!(
+                        "{}:{}",
+                        entry.key.loc.pathname().display(),
+                        entry.key.loc.line
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    /// Sort the translations in `units` into the mod's `lang` files that they should be written
+    /// to.
+    ///
+    /// A translation goes in the file that already has the key in `lang`, or else in the file
+    /// that mirrors the base language file that defines the key. A translation gets the version
+    /// number of the base language entry if its source text is the current one, so that it
+    /// doesn't count as stale.
+    ///
+    /// Returns the pathnames of the files, with the translations for each in the order of the
+    /// base language entries. Units for keys that aren't defined by the mod, or that have no
+    /// translation, are skipped.
+    pub(crate) fn import_translations<'a>(
+        &self,
+        lang: Language,
+        units: &'a [TranslationUnit],
+    ) -> Vec<(PathBuf, Vec<ImportedTranslation<'a>>)> {
+        let base = self.base_lang;
+        let mut imported: BTreeMap<PathBuf, Vec<(Loc, ImportedTranslation)>> = BTreeMap::new();
+        for unit in units {
+            if unit.target.is_empty() {
+                continue;
+            }
+            let Some(base_entry) = self.locas[base]
+                .get(unit.key.as_str())
+                .filter(|entry| entry.key.loc.kind == FileKind::Mod)
+            else {
+                continue;
+            };
+            let existing =
+                self.locas[lang].get(unit.key.as_str()).filter(|e| e.key.loc.kind == FileKind::Mod);
+            let pathname = existing.map_or_else(
+                || translated_pathname(base_entry.key.loc.pathname(), base, lang),
+                |entry| entry.key.loc.pathname().to_path_buf(),
+            );
+            let up_to_date = base_entry.orig.as_ref().is_some_and(|orig| orig.is(&unit.source));
+            let version = if up_to_date {
+                base_entry.version
+            } else {
+                existing.and_then(|entry| entry.version)
+            };
+            let translation = ImportedTranslation {
+                key: unit.key.as_str(),
+                line: existing.map(|entry| entry.key.loc.line),
+                version: version.unwrap_or(0),
+                text: unit.target.as_str(),
+            };
+            let translations = imported.entry(pathname).or_default();
+            // A key that is in the file more than once keeps only its last translation.
+            translations.retain(|(_, t)| t.key != translation.key);
+            translations.push((base_entry.key.loc, translation));
+        }
+
+        imported
+            .into_iter()
+            .map(|(pathname, mut translations)| {
+                translations.sort_by_key(|(loc, _)| *loc);
+                (pathname, translations.into_iter().map(|(_, t)| t).collect())
+            })
+            .collect()
+    }
+
     /// Marks a localization key as used for one language.
     /// Returns whether the key exists for this language (same as [`Localization::exists_lang`]).
     fn mark_used_lang_return_exists(&self, key: &str, lang: Language) -> bool {
@@ -468,6 +869,7 @@ impl Localization {
         // The loca is defined for some languages but not others.
         // This inconsistency is worth warning about.
         else if !langs_missing.is_empty() {
+            self.record_missing(key, &langs_missing, token);
             let msg = format!("missing {} localization key {key}", stringify_list(&langs_missing));
             report(ErrorKey::MissingLocalization, Item::Localization.severity())
                 .msg(msg)
@@ -666,8 +1068,228 @@ impl Localization {
         }
     }
 
+    /// Return the entries in `lang` together with the entries for the same keys in the base
+    /// language, sorted. Pairs where both come from vanilla or other mods are left out.
+    fn translations(&self, lang: Language) -> Vec<(&LocaEntry, &LocaEntry)> {
+        let mut entries: Vec<_> = self.locas[lang]
+            .values()
+            .filter_map(|entry| Some((entry, self.locas[self.base_lang].get(entry.key.as_str())?)))
+            .filter(|(entry, base_entry)| {
+                !entry.key.loc.kind.counts_as_vanilla()
+                    || !base_entry.key.loc.kind.counts_as_vanilla()
+            })
+            .collect();
+        entries.sort_unstable();
+        entries
+    }
+
+    /// Warn about translations in `lang` that are out of date: those with a lower version number
+    /// than the base language entry, and those whose base language text has changed since
+    /// `snapshot` was written while their own text hasn't.
+    ///
+    /// This is only done when a snapshot was loaded, because it means the mod keeps its
+    /// translations up to date with the base language.
+    fn check_stale(&self, lang: Language, snapshot: &LocaSnapshot) {
+        let base = self.base_lang;
+        let snapshot = (snapshot.base == <&str>::from(base)).then_some(snapshot);
+        for (entry, base_entry) in self.translations(lang) {
+            let version = entry.version.unwrap_or(0);
+            let base_version = base_entry.version.unwrap_or(0);
+            if version < base_version {
+                let msg = format!(
+                    "{lang} translation is version {version}, but the {base} text is version {base_version}"
+                );
+                let msg2 = format!("{base} localization");
+                warn(ErrorKey::StaleTranslation)
+                    .msg(msg)
+                    .loc(&entry.key)
+                    .loc_msg(&base_entry.key, msg2)
+                    .push();
+                continue;
+            }
+            let Some(snapshot) = snapshot else { continue };
+            let (Some(text), Some(base_text)) = (&entry.orig, &base_entry.orig) else { continue };
+            // An empty translation is a placeholder that's still waiting for its text.
+            if text.as_str().trim().is_empty() {
+                continue;
+            }
+            let Some(recorded) = snapshot
+                .keys
+                .get(entry.key.as_str())
+                .and_then(|langs| langs.get(<&str>::from(lang)))
+            else {
+                continue;
+            };
+            if recorded.is_stale(Translated::new(base_text.as_str(), text.as_str())) {
+                let msg = format!(
+                    "{lang} translation has not been updated since the {base} text changed"
+                );
+                let info = "according to the localization snapshot";
+                let msg2 = format!("{base} localization");
+                warn(ErrorKey::StaleTranslation)
+                    .msg(msg)
+                    .info(info)
+                    .loc(&entry.key)
+                    .loc_msg(&base_entry.key, msg2)
+                    .push();
+            }
+        }
+    }
+
+    /// Make a snapshot of the current translations, for finding out later which ones are stale.
+    /// The translations that are stale according to `old` keep their record from `old`.
+    pub(crate) fn snapshot(&self, old: Option<&LocaSnapshot>) -> LocaSnapshot {
+        let base = self.base_lang;
+        let old = old.filter(|old| old.base == <&str>::from(base));
+        let mut snapshot = LocaSnapshot::new(base.to_string());
+        for lang in self.iter_lang() {
+            if lang == base {
+                continue;
+            }
+            for (entry, base_entry) in self.translations(lang) {
+                let (Some(text), Some(base_text)) = (&entry.orig, &base_entry.orig) else {
+                    continue;
+                };
+                let mut record = Translated::new(base_text.as_str(), text.as_str());
+                if let Some(recorded) = old
+                    .and_then(|old| old.keys.get(entry.key.as_str()))
+                    .and_then(|langs| langs.get(<&str>::from(lang)))
+                    && recorded.is_stale(record)
+                {
+                    record = *recorded;
+                }
+                snapshot
+                    .keys
+                    .entry(entry.key.to_string())
+                    .or_default()
+                    .insert(lang.to_string(), record);
+            }
+        }
+        snapshot
+    }
+
+    /// Warn about the mod's entries in `lang` that have characters for which none of the fonts
+    /// for `lang` have a glyph. Those characters show as boxes in the game.
+    #[cfg(feature = "jomini")]
+    fn check_glyphs(&self, lang: Language, fonts: &FontCoverage) {
+        let mut entries: Vec<_> = self.locas[lang]
+            .values()
+            .filter(|entry| !entry.key.loc.kind.counts_as_vanilla())
+            .collect();
+        entries.sort_unstable();
+        for entry in entries {
+            // Put the macro keywords back as plain text, to see the structure around them.
+            let reparsed;
+            let value = if let LocaValue::Macro(v) = &entry.value {
+                let tokens = v
+                    .iter()
+                    .map(|macrovalue| match macrovalue {
+                        MacroValue::Text(token) | MacroValue::Keyword(token) => token,
+                    })
+                    .collect();
+                reparsed = ValueParser::new(tokens).parse();
+                &reparsed
+            } else {
+                &entry.value
+            };
+            let mut texts = Vec::new();
+            collect_texts(value, &mut texts);
+
+            let mut missing = Vec::new();
+            let mut first = None;
+            for token in texts {
+                for (i, c) in token.as_str().chars().enumerate() {
+                    if c.is_whitespace() || c.is_control() || missing.contains(&c) {
+                        continue;
+                    }
+                    if !fonts.has_glyph(lang, c) {
+                        missing.push(c);
+                        first.get_or_insert_with(|| {
+                            let mut loc = token.loc;
+                            loc.column += u32::try_from(i).expect("internal error: 2^32 columns");
+                            loc
+                        });
+                    }
+                }
+            }
+            if let Some(loc) = first {
+                let chars: Vec<_> =
+                    missing.iter().map(|c| format!("`{c}` (U+{:04X})", u32::from(*c))).collect();
+                let chars: Vec<_> = chars.iter().map(String::as_str).collect();
+                let msg = format!("the {lang} fonts have no glyph for {}", stringify_list(&chars));
+                let info = "these characters will show as boxes in the game";
+                warn(ErrorKey::MissingGlyph).msg(msg).info(info).loc(loc).push();
+            }
+        }
+    }
+
+    /// Compare the translations in `lang` with the entries in the base language, and warn about
+    /// `$macro$` references, `[code]` calls and `@icon!` texticons that one has and the other
+    /// doesn't, and about `#markup` that isn't closed the same way.
+    fn check_parity(&self, lang: Language) {
+        let base = self.base_lang;
+        for (entry, base_entry) in self.translations(lang) {
+            let (Some(shape), Some(base_shape)) =
+                (LocaShape::new(entry), LocaShape::new(base_entry))
+            else {
+                continue;
+            };
+            // An empty translation is a placeholder that's still waiting for its text.
+            if shape.is_empty()
+                && entry.orig.as_ref().is_some_and(|orig| orig.as_str().trim().is_empty())
+            {
+                continue;
+            }
+            for (part, token) in &base_shape.parts {
+                if !shape.parts.contains_key(part) {
+                    let msg = format!(
+                        "{lang} localization is missing `{part}`, which the {base} localization has"
+                    );
+                    let msg2 = format!("{base} localization");
+                    let mut builder = warn(ErrorKey::TranslationMismatch);
+                    if part.starts_with('[') {
+                        builder = builder.weak();
+                    }
+                    builder.msg(msg).loc(&entry.key).loc_msg(token, msg2).push();
+                }
+            }
+            for (part, token) in &shape.parts {
+                if !base_shape.parts.contains_key(part) {
+                    let msg = format!(
+                        "{lang} localization has `{part}`, which the {base} localization doesn't have"
+                    );
+                    let msg2 = format!("{base} localization");
+                    let mut builder = warn(ErrorKey::TranslationMismatch);
+                    if part.starts_with('[') {
+                        builder = builder.weak();
+                    }
+                    builder.msg(msg).loc(token).loc_msg(&base_entry.key, msg2).push();
+                }
+            }
+            if shape.open_markup != base_shape.open_markup {
+                let msg = format!(
+                    "{lang} localization does not close its #markup the same way as the {base} localization"
+                );
+                let info = "every #markup should be closed with #!";
+                let msg2 = format!("{base} localization");
+                warn(ErrorKey::TranslationMismatch)
+                    .msg(msg)
+                    .info(info)
+                    .loc(&entry.key)
+                    .loc_msg(&base_entry.key, msg2)
+                    .push();
+            }
+        }
+    }
+
     // This is in pass2 to make sure all `validated` entries have been marked.
     pub fn validate_pass2(&self, data: &Everything) {
+        #[cfg(feature = "jomini")]
+        let fonts = Game::is_jomini().then(|| {
+            let fonts = data.font_coverage.get_or_init(|| FontCoverage::load(data));
+            fonts.validate(data);
+            fonts
+        });
         #[allow(unused_variables)]
         scope(|s| {
             for lang in self.iter_lang() {
@@ -675,6 +1297,20 @@ impl Localization {
                 // Check localization key collisions
                 #[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator"))]
                 s.spawn(move |_| self.check_collisions(lang));
+                if lang != self.base_lang && self.mod_langs[self.base_lang.to_idx()] {
+                    if self.check_parity {
+                        s.spawn(move |_| self.check_parity(lang));
+                    }
+                    if let Some(snapshot) = &data.loca_snapshot {
+                        s.spawn(move |_| self.check_stale(lang, snapshot));
+                    }
+                }
+                #[cfg(feature = "jomini")]
+                if let Some(fonts) = &fonts
+                    && fonts.has_fonts(lang)
+                {
+                    s.spawn(move |_| self.check_glyphs(lang, fonts));
+                }
 
                 // Collect and sort the entries before looping, to create more stable output
                 let mut unvalidated_entries: Vec<&LocaEntry> =
@@ -691,6 +1327,81 @@ impl Localization {
         });
     }
 
+    /// Replace the entries from the files at `paths` with the current contents of those files.
+    /// `entries` are the current file entries under `paths`. A path without an entry means the
+    /// file has been removed.
+    ///
+    /// Keys that were defined in more than one file are resolved again in load order, so that
+    /// removing an overriding definition brings back the one it had overridden.
+    pub fn reload_files(
+        &mut self,
+        paths: &[PathBuf],
+        entries: &[&FileEntry],
+        parser: &ParserMemory,
+    ) {
+        let subpath = self.subpath();
+        let mut loaded = Vec::new();
+        for entry in entries.iter().filter(|entry| entry.path().starts_with(&subpath)) {
+            if let Some((filelang, vec)) = self.load_file(entry, parser) {
+                if entry.kind() == FileKind::Mod {
+                    self.mod_langs.set(filelang.to_idx(), true);
+                }
+                loaded.push((filelang, vec));
+            }
+        }
+
+        for lang in Language::iter() {
+            let hash = &mut self.locas[lang];
+            let shadowed = &mut self.shadowed[lang.to_idx()];
+            let is_reloaded = |loca: &LocaEntry| paths.iter().any(|p| p == loca.key.loc.pathname());
+
+            // Take out all the entries for the keys that are affected, and put them back in order.
+            let mut candidates: Vec<LocaEntry> = Vec::new();
+            let mut keys: TigerHashSet<&'static str> = TigerHashSet::default();
+            hash.retain(|key, loca| {
+                if is_reloaded(loca) {
+                    keys.insert(*key);
+                    false
+                } else {
+                    true
+                }
+            });
+            shadowed.retain(|loca| !is_reloaded(loca));
+            for (filelang, vec) in &mut loaded {
+                if *filelang == lang {
+                    keys.extend(vec.iter().map(|loca| loca.key.as_str()));
+                    candidates.append(vec);
+                }
+            }
+            for key in &keys {
+                if let Some(loca) = hash.remove(key) {
+                    candidates.push(loca);
+                }
+            }
+            let (affected, unaffected): (Vec<_>, Vec<_>) =
+                take(shadowed).into_iter().partition(|loca| keys.contains(loca.key.as_str()));
+            *shadowed = unaffected;
+            candidates.extend(affected);
+
+            candidates.sort_unstable();
+            for loca in candidates {
+                let replace = is_replace_path(loca.key.loc.pathname());
+                insert_loca(hash, shadowed, loca, replace);
+            }
+        }
+    }
+
+    /// Forget which entries have been used and validated, so that the next validation starts fresh.
+    pub fn clear_caches(&mut self) {
+        for lang in Language::iter() {
+            for loca in self.locas[lang].values_mut() {
+                *loca.used.get_mut() = false;
+                *loca.validated.get_mut() = false;
+            }
+            self.missing[lang.to_idx()].get_mut().unwrap().clear();
+        }
+    }
+
     pub fn mark_category_used(&self, prefix: &str) {
         let mut i = 0;
         loop {
@@ -770,6 +1481,17 @@ impl FileHandler<(Language, Vec<LocaEntry>)> for Localization {
                 }
             }
             self.check_langs = langs;
+
+            self.check_parity = block.get_field_bool("parity").unwrap_or(false);
+            if let Some(token) = block.get_field_value("base") {
+                if let Ok(lang) = Language::try_from(token.as_str()) {
+                    self.base_lang = lang;
+                } else {
+                    let msg = format!("unknown language `{token}`");
+                    let info = format!("expected one of {}", *LANG_LIST);
+                    warn(ErrorKey::Config).msg(msg).info(info).loc(token).push();
+                }
+            }
         }
     }
 
@@ -843,23 +1565,40 @@ impl FileHandler<(Language, Vec<LocaEntry>)> for Localization {
             self.mod_langs.set(filelang.to_idx(), true);
         }
 
+        let replace = is_replace_path(entry.path());
+        let shadowed = &mut self.shadowed[filelang.to_idx()];
         for loca in vec {
-            match hash.entry(loca.key.as_str()) {
-                Entry::Occupied(mut occupied_entry) => {
-                    let other = occupied_entry.get();
-                    // other.key and loca.key are in the other order than usual here,
-                    // because in loca the older definition overrides the later one.
-                    if is_replace_path(entry.path()) {
-                        occupied_entry.insert(loca);
-                    } else if other.key.loc.kind == entry.kind() && other.orig != loca.orig {
-                        dup_error(&other.key, &loca.key, "localization");
-                    }
-                }
-                Entry::Vacant(vacant_entry) => {
-                    vacant_entry.insert(loca);
+            insert_loca(hash, shadowed, loca, replace);
+        }
+    }
+}
+
+/// Add `loca` to `hash`, unless there is already an entry with the same key. In that case,
+/// the existing entry is kept unless `replace` is true. The entry that loses out is stored in
+/// `shadowed`.
+fn insert_loca(
+    hash: &mut TigerHashMap<&'static str, LocaEntry>,
+    shadowed: &mut Vec<LocaEntry>,
+    loca: LocaEntry,
+    replace: bool,
+) {
+    match hash.entry(loca.key.as_str()) {
+        Entry::Occupied(mut occupied_entry) => {
+            let other = occupied_entry.get();
+            // other.key and loca.key are in the other order than usual here,
+            // because in loca the older definition overrides the later one.
+            if replace {
+                shadowed.push(occupied_entry.insert(loca));
+            } else {
+                if other.key.loc.kind == loca.key.loc.kind && other.orig != loca.orig {
+                    dup_error(&other.key, &loca.key, "localization");
                 }
+                shadowed.push(loca);
             }
         }
+        Entry::Vacant(vacant_entry) => {
+            vacant_entry.insert(loca);
+        }
     }
 }
 
@@ -869,6 +1608,10 @@ impl Default for Localization {
             check_langs: bitarr![u16, Lsb0; 1; Language::COUNT],
             mod_langs: bitarr![u16, Lsb0; 0; Language::COUNT],
             locas: Languages(std::array::from_fn(|_| TigerHashMap::default())),
+            shadowed: std::array::from_fn(|_| Vec::new()),
+            base_lang: Language::English,
+            check_parity: false,
+            missing: std::array::from_fn(|_| Mutex::default()),
         }
     }
 }
@@ -976,4 +1719,20 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_merge_translations() {
+        let contents = "\u{feff}l_french:\r\n # Greetings\r\n  tr_hello:0 \"Salut\"\r\n\r\n tr_bye:0 \"Au revoir\"";
+        let translations = [
+            ImportedTranslation { key: "tr_hello", line: Some(3), version: 1, text: "Bonjour" },
+            ImportedTranslation { key: "tr_new", line: None, version: 0, text: "Nouveau" },
+        ];
+        let merged = merge_translations(Some(contents), Language::French, &translations);
+        assert_eq!(
+            merged,
+            "\u{feff}l_french:\r\n # Greetings\r\n  tr_hello:1 \"Bonjour\"\r\n\r\n tr_bye:0 \"Au revoir\"\n tr_new:0 \"Nouveau\"\n"
+        );
+        let new = merge_translations(None, Language::French, &translations[1..]);
+        assert_eq!(new, "\u{feff}l_french:\n tr_new:0 \"Nouveau\"\n");
+    }
 }
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
@@ -58,6 +58,15 @@ impl OnActions {
         self.on_actions.values().map(|item| &item.actions[0].0)
     }
 
+    /// Iterate over every definition of every on-action, including the ones that add to an
+    /// earlier definition.
+    #[cfg(feature = "jomini")]
+    pub fn iter_actions(&self) -> impl Iterator<Item = (&Token, &Block)> {
+        self.on_actions
+            .values()
+            .flat_map(|item| item.actions.iter().map(|(key, block)| (key, block)))
+    }
+
     pub fn validate(&self, data: &Everything) {
         self.on_actions.par_iter().for_each(|(_, v)| {
             v.validate(data);
@@ -65,6 +74,7 @@ impl OnActions {
     }
 
     pub fn validate_call(&self, key: &Token, data: &Everything, sc: &mut ScopeContext) {
+        data.explain.record_call(key, sc, data);
         if let Some(action) = self.on_actions.get(key.as_str()) {
             action.validate_call(data, sc);
         }
@@ -119,6 +129,26 @@ impl FileHandler<Block> for OnActions {
             }
         }
     }
+
+    fn defined_keys(&self, block: &Block) -> Vec<&'static str> {
+        let mut keys = Vec::new();
+        for (key, block) in block.iter_definitions() {
+            if Game::is_hoi4() && key.is("on_actions") {
+                keys.extend(block.iter_definitions().map(|(key, _)| key.as_str()));
+            } else {
+                keys.push(key.as_str());
+            }
+        }
+        keys
+    }
+
+    fn forget_files(&mut self, forget: &dyn Fn(&Path) -> bool) -> bool {
+        self.on_actions.retain(|_, on_action| {
+            on_action.actions.retain(|(key, _)| !forget(key.loc.pathname()));
+            !on_action.actions.is_empty()
+        });
+        true
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -140,17 +170,15 @@ impl OnAction {
     pub fn validate(&self, data: &Everything) {
         let mut seen_trigger = false;
         let mut seen_effect = false;
-        for (key, block) in self.actions.iter().rev() {
+        for (i, (key, block)) in self.actions.iter().enumerate().rev() {
             // Make an sc for each array entry, to make sure it uses the local `key`.
             // This is important to distinguish between vanilla errors and mod errors.
-            let mut sc = if let Some(builtin_sc) = on_action_scopecontext(key, data) {
-                builtin_sc
-            } else {
-                let mut generated_sc = ScopeContext::new(Scopes::non_primitive(), key);
-                generated_sc.set_strict_scopes(false);
-                generated_sc
-            };
+            let mut sc = new_scope_context(key, data);
             validate_on_action_internal(block, data, &mut sc, &mut seen_trigger, &mut seen_effect);
+            // The last definition is the one that overrides the others, usually the mod's.
+            if i + 1 == self.actions.len() {
+                data.explain.record_context("on-action", key, &sc);
+            }
         }
     }
 
@@ -167,6 +195,17 @@ impl OnAction {
     }
 }
 
+/// Make the scope context for an on-action that is fired by the game.
+fn new_scope_context(key: &Token, data: &Everything) -> ScopeContext {
+    if let Some(builtin_sc) = on_action_scopecontext(key, data) {
+        builtin_sc
+    } else {
+        let mut generated_sc = ScopeContext::new(Scopes::non_primitive(), key);
+        generated_sc.set_strict_scopes(false);
+        generated_sc
+    }
+}
+
 fn validate_on_action_internal(
     block: &Block,
     data: &Everything,
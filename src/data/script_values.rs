@@ -1,5 +1,7 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
 
 use crate::block::{BV, Block};
 use crate::context::ScopeContext;
@@ -7,11 +9,12 @@ use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 use crate::helpers::{
     BANNED_NAMES, PrefixShould, TigerHashMap, dup_error, exact_dup_error, item_prefix_should,
+    report_unused,
 };
 use crate::item::Item;
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
-use crate::report::{ErrorKey, err, warn};
+use crate::report::{Confidence, ErrorKey, err, warn};
 use crate::scopes::Scopes;
 use crate::script_value::{validate_non_dynamic_script_value, validate_script_value};
 use crate::token::{Loc, Token};
@@ -29,7 +32,13 @@ impl ScriptValues {
             && other.key.loc.kind >= key.loc.kind
         {
             if other.bv.equivalent(bv) {
-                exact_dup_error(key, &other.key, "script value");
+                exact_dup_error(
+                    key,
+                    bv.get_block(),
+                    &other.key,
+                    other.bv.get_block(),
+                    "script value",
+                );
             } else {
                 dup_error(key, &other.key, "script value");
             }
@@ -84,7 +93,11 @@ impl ScriptValues {
     }
 
     pub fn exists(&self, key: &str) -> bool {
-        self.script_values.contains_key(key)
+        self.get(key).is_some()
+    }
+
+    fn get(&self, key: &str) -> Option<&ScriptValue> {
+        self.script_values.get(key).inspect(|item| item.used.store(true, Relaxed))
     }
 
     pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
@@ -93,18 +106,40 @@ impl ScriptValues {
 
     pub fn validate(&self, data: &Everything) {
         for item in self.script_values.values() {
-            item.validate(data);
+            if data.should_validate(item.key.loc) {
+                item.validate(data);
+            }
+        }
+    }
+
+    pub fn check_unused(&self) {
+        let unused = self.script_values.values().filter(|item| !item.used.load(Relaxed));
+        let msg = "Unused script value";
+        report_unused(
+            ErrorKey::UnusedDefinition,
+            Confidence::Reasonable,
+            msg,
+            unused.map(|i| &i.key),
+        );
+    }
+
+    /// Forget the cached validation results, so that the next validation starts fresh.
+    pub fn clear_caches(&mut self) {
+        for item in self.script_values.values_mut() {
+            item.cache.get_mut().unwrap().clear();
         }
     }
 
     pub fn validate_call(&self, key: &Token, data: &Everything, sc: &mut ScopeContext) {
-        if let Some(item) = self.script_values.get(key.as_str()) {
+        if let Some(item) = self.get(key.as_str()) {
+            data.xref.record(Item::ScriptValue, key.as_str(), key);
             item.validate_call(key, data, sc);
         }
     }
 
     pub fn validate_non_dynamic_call(&self, key: &Token, data: &Everything) {
-        if let Some(item) = self.script_values.get(key.as_str()) {
+        if let Some(item) = self.get(key.as_str()) {
+            data.xref.record(Item::ScriptValue, key.as_str(), key);
             item.validate_non_dynamic_call(data);
         }
     }
@@ -149,6 +184,15 @@ impl FileHandler<Block> for ScriptValues {
             self.load_item(key, bv);
         }
     }
+
+    fn defined_keys(&self, block: &Block) -> Vec<&'static str> {
+        block.iter_assignments_and_definitions().map(|(key, _)| key.as_str()).collect()
+    }
+
+    fn forget_files(&mut self, forget: &dyn Fn(&Path) -> bool) -> bool {
+        self.script_values.retain(|_, value| !forget(value.key.loc.pathname()));
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -157,11 +201,19 @@ pub struct ScriptValue {
     bv: BV,
     cache: RwLock<TigerHashMap<Loc, ScopeContext>>,
     scope_override: Option<Scopes>,
+    /// Whether anything refers to this script value
+    used: AtomicBool,
 }
 
 impl ScriptValue {
     pub fn new(key: Token, bv: BV, scope_override: Option<Scopes>) -> Self {
-        Self { key, bv, cache: RwLock::new(TigerHashMap::default()), scope_override }
+        Self {
+            key,
+            bv,
+            cache: RwLock::new(TigerHashMap::default()),
+            scope_override,
+            used: AtomicBool::new(false),
+        }
     }
 
     pub fn cached_compat(&self, key: &Token, sc: &mut ScopeContext, data: &Everything) -> bool {
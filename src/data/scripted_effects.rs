@@ -1,20 +1,25 @@
 use std::fmt::Debug;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
 
 use crate::block::Block;
-use crate::context::ScopeContext;
+use crate::context::{ScopeContext, ScriptSignature};
+use crate::declared_signature::DeclaredSignature;
 use crate::effect::validate_effect_internal;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 #[cfg(feature = "hoi4")]
 use crate::game::Game;
-use crate::helpers::{BANNED_NAMES, TigerHashMap, limited_item_prefix_should_insert};
+use crate::helpers::{
+    BANNED_NAMES, TigerHashMap, limited_item_prefix_should_insert, report_unused,
+};
 use crate::item::Item;
 use crate::lowercase::Lowercase;
-use crate::macros::{MACRO_MAP, MacroCache};
+use crate::macros::{MacroCache, macro_map};
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
-use crate::report::{ErrorKey, err, warn};
+use crate::report::{Confidence, ErrorKey, err, warn};
 use crate::scopes::Scopes;
 use crate::special_tokens::SpecialTokens;
 use crate::token::Token;
@@ -24,8 +29,10 @@ use crate::validator::Validator;
 use crate::variables::Variables;
 
 #[derive(Debug, Default)]
+#[allow(clippy::struct_field_names)]
 pub struct Effects {
     scope_overrides: TigerHashMap<&'static str, Scopes>,
+    signatures: TigerHashMap<&'static str, DeclaredSignature>,
     effects: TigerHashMap<&'static str, Effect>,
 }
 
@@ -40,10 +47,16 @@ impl Effects {
             })
         {
             let scope_override = self.scope_overrides.get(name.as_str()).copied();
+            let declared = self
+                .signatures
+                .get(name.as_str())
+                .cloned()
+                .or_else(|| DeclaredSignature::from_comments(&name));
             if block.source.is_some() {
-                MACRO_MAP.insert_or_get_loc(name.loc);
+                macro_map().insert_or_get_loc(name.loc);
             }
-            self.effects.insert(name.as_str(), Effect::new(name, block, scope_override));
+            let effect = Effect::new(name, block, scope_override, declared);
+            self.effects.insert(effect.key.as_str(), effect);
         }
     }
 
@@ -54,7 +67,7 @@ impl Effects {
     }
 
     pub fn exists(&self, key: &str) -> bool {
-        self.effects.contains_key(key)
+        self.get(key).is_some()
     }
 
     pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
@@ -62,18 +75,44 @@ impl Effects {
     }
 
     pub fn get(&self, key: &str) -> Option<&Effect> {
+        self.effects.get(key).inspect(|item| item.mark_used())
+    }
+
+    /// Like [`Self::get`], but without marking the item as used.
+    pub fn peek(&self, key: &str) -> Option<&Effect> {
         self.effects.get(key)
     }
 
     pub fn validate(&self, data: &Everything) {
         for item in self.effects.values() {
-            item.validate(data);
+            if data.should_validate(item.key.loc) {
+                item.validate(data);
+            }
+        }
+    }
+
+    pub fn check_unused(&self) {
+        let unused = self.effects.values().filter(|item| !item.is_used());
+        let msg = "Unused scripted effect";
+        report_unused(
+            ErrorKey::UnusedDefinition,
+            Confidence::Reasonable,
+            msg,
+            unused.map(|i| &i.key),
+        );
+    }
+
+    /// Forget the cached validation results, so that the next validation starts fresh.
+    pub fn clear_caches(&mut self) {
+        for item in self.effects.values_mut() {
+            item.clear_cache();
         }
     }
 }
 
 impl FileHandler<Block> for Effects {
     fn config(&mut self, config: &Block) {
+        self.signatures = DeclaredSignature::from_config(config);
         if let Some(block) = config.get_field_block("scope_override") {
             for (key, token) in block.iter_assignments() {
                 let mut scopes = Scopes::empty();
@@ -115,6 +154,18 @@ impl FileHandler<Block> for Effects {
             self.load_item(key, block);
         }
     }
+
+    fn defined_keys(&self, block: &Block) -> Vec<&'static str> {
+        block
+            .iter_definitions()
+            .map(|(key, _)| key.as_str().split_once(':').map_or(key.as_str(), |(_, name)| name))
+            .collect()
+    }
+
+    fn forget_files(&mut self, forget: &dyn Fn(&Path) -> bool) -> bool {
+        self.effects.retain(|_, effect| !forget(effect.key.loc.pathname()));
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -123,14 +174,45 @@ pub struct Effect {
     pub block: Block,
     cache: MacroCache<(ScopeContext, SpecialTokens, bool)>,
     scope_override: Option<Scopes>,
+    /// The signature declared by the user, if any
+    declared: Option<DeclaredSignature>,
+    /// Whether anything refers to this effect
+    used: AtomicBool,
 }
 
 impl Effect {
-    pub fn new(key: Token, block: Block, scope_override: Option<Scopes>) -> Self {
-        Self { key, block, cache: MacroCache::default(), scope_override }
+    pub fn new(
+        key: Token,
+        block: Block,
+        scope_override: Option<Scopes>,
+        declared: Option<DeclaredSignature>,
+    ) -> Self {
+        Self {
+            key,
+            block,
+            cache: MacroCache::default(),
+            scope_override,
+            declared,
+            used: AtomicBool::new(false),
+        }
+    }
+
+    pub fn mark_used(&self) {
+        self.used.store(true, Relaxed);
+    }
+
+    pub fn is_used(&self) -> bool {
+        self.used.load(Relaxed)
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
     }
 
     pub fn validate(&self, data: &Everything) {
+        if let Some(declared) = &self.declared {
+            declared.check_parameters(&self.key, &self.macro_parms());
+        }
         if self.block.source.is_none() {
             let mut sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
             sc.set_strict_scopes(false);
@@ -147,6 +229,21 @@ impl Effect {
         }
     }
 
+    /// Make the scope context that this effect's body is validated in.
+    fn new_scope_context(&self) -> ScopeContext {
+        let mut sc = if let Some(declared) = &self.declared {
+            declared.scope_context(&self.key)
+        } else {
+            let mut sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
+            sc.set_strict_scopes(false);
+            sc
+        };
+        if self.scope_override.is_some() {
+            sc.set_no_warn(true);
+        }
+        sc
+    }
+
     pub fn validate_call(
         &self,
         key: &Token,
@@ -155,13 +252,10 @@ impl Effect {
         tooltipped: Tooltipped,
         special_tokens: &mut SpecialTokens,
     ) -> bool {
+        data.explain.record_call(key, sc, data);
         let mut has_tooltip = false;
         if !self.cached_compat(key, &[], tooltipped, sc, data, special_tokens, &mut has_tooltip) {
-            let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
-            our_sc.set_strict_scopes(false);
-            if self.scope_override.is_some() {
-                our_sc.set_no_warn(true);
-            }
+            let mut our_sc = self.new_scope_context();
             self.cache.insert(
                 key,
                 &[],
@@ -181,6 +275,9 @@ impl Effect {
                 tooltipped,
                 &mut our_st,
             );
+            if let Some(declared) = &self.declared {
+                declared.check_returns(&self.key, &our_sc, data);
+            }
             if let Some(scopes) = self.scope_override {
                 our_sc = ScopeContext::new_unrooted(scopes, key);
                 our_sc.set_strict_scopes(false);
@@ -192,6 +289,26 @@ impl Effect {
         has_tooltip && tooltipped.is_tooltipped()
     }
 
+    /// Return what validating this effect on its own found out about how it must be called.
+    /// Returns `None` for effects with macro parameters, because those are validated per call.
+    pub fn signature(&self, data: &Everything) -> Option<ScriptSignature> {
+        let mut signature = None;
+        self.cache.perform(&self.key, &[], Tooltipped::No, false, |(sc, _, _)| {
+            signature = Some(sc.script_signature(data));
+        });
+        signature
+    }
+
+    /// Return the scope context that validating this effect on its own ended up with.
+    /// Returns `None` for effects with macro parameters, because those are validated per call.
+    pub fn scope_context(&self) -> Option<ScopeContext> {
+        let mut result = None;
+        self.cache.perform(&self.key, &[], Tooltipped::No, false, |(sc, _, _)| {
+            result = Some(sc.clone());
+        });
+        result
+    }
+
     pub fn macro_parms(&self) -> Vec<&'static str> {
         self.block.macro_parms()
     }
@@ -223,17 +340,17 @@ impl Effect {
         tooltipped: Tooltipped,
         special_tokens: &mut SpecialTokens,
     ) -> bool {
+        data.explain.record_call(key, sc, data);
+        if let Some(declared) = &self.declared {
+            declared.validate_args(args, data, sc);
+        }
         let mut has_tooltip = false;
         // Every invocation is treated as different even if the args are the same,
         // because we want to point to the correct one when reporting errors.
         if !self.cached_compat(key, args, tooltipped, sc, data, special_tokens, &mut has_tooltip)
             && let Some(block) = self.block.expand_macro(args, key.loc, &data.parser.pdxfile)
         {
-            let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
-            our_sc.set_strict_scopes(false);
-            if self.scope_override.is_some() {
-                our_sc.set_no_warn(true);
-            }
+            let mut our_sc = self.new_scope_context();
             // Insert the dummy sc before continuing. That way, if we recurse, we'll hit
             // that dummy context instead of macro-expanding again.
             self.cache.insert(
@@ -255,6 +372,9 @@ impl Effect {
                 tooltipped,
                 &mut our_st,
             );
+            if let Some(declared) = &self.declared {
+                declared.check_returns(&self.key, &our_sc, data);
+            }
             if let Some(scopes) = self.scope_override {
                 our_sc = ScopeContext::new_unrooted(scopes, key);
                 our_sc.set_strict_scopes(false);
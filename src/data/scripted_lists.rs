@@ -1,14 +1,16 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
 
 use crate::block::Block;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::helpers::{TigerHashMap, dup_error};
+use crate::helpers::{TigerHashMap, dup_error, report_unused};
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
-use crate::report::{ErrorKey, err};
+use crate::report::{Confidence, ErrorKey, err};
 use crate::scopes::{Scopes, scope_iterator};
 use crate::token::{Loc, Token};
 use crate::tooltipped::Tooltipped;
@@ -38,7 +40,11 @@ impl ScriptedLists {
     }
 
     pub fn exists(&self, key: &str) -> bool {
-        self.lists.contains_key(key)
+        self.get(key).is_some()
+    }
+
+    fn get(&self, key: &str) -> Option<&List> {
+        self.lists.get(key).inspect(|item| item.used.store(true, Relaxed))
     }
 
     pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
@@ -46,11 +52,22 @@ impl ScriptedLists {
     }
 
     pub fn validate_call(&self, key: &Token, data: &Everything, sc: &mut ScopeContext) {
-        if let Some(item) = self.lists.get(key.as_str()) {
+        if let Some(item) = self.get(key.as_str()) {
             item.validate_call(key, data, sc);
         }
     }
 
+    pub fn check_unused(&self) {
+        let unused = self.lists.values().filter(|item| !item.used.load(Relaxed));
+        let msg = "Unused scripted list";
+        report_unused(
+            ErrorKey::UnusedDefinition,
+            Confidence::Reasonable,
+            msg,
+            unused.map(|i| &i.key),
+        );
+    }
+
     pub fn base(&self, item: &Token) -> Option<&Token> {
         self.lists.get(item.as_str()).and_then(|item| item.block.get_field_value("base"))
     }
@@ -60,6 +77,13 @@ impl ScriptedLists {
             item.validate(data);
         }
     }
+
+    /// Forget the cached validation results, so that the next validation starts fresh.
+    pub fn clear_caches(&mut self) {
+        for item in self.lists.values_mut() {
+            item.cache.get_mut().unwrap().clear();
+        }
+    }
 }
 
 impl FileHandler<Block> for ScriptedLists {
@@ -80,6 +104,15 @@ impl FileHandler<Block> for ScriptedLists {
             self.load_item(key, block);
         }
     }
+
+    fn defined_keys(&self, block: &Block) -> Vec<&'static str> {
+        block.iter_definitions().map(|(key, _)| key.as_str()).collect()
+    }
+
+    fn forget_files(&mut self, forget: &dyn Fn(&Path) -> bool) -> bool {
+        self.lists.retain(|_, list| !forget(list.key.loc.pathname()));
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -87,11 +120,18 @@ pub struct List {
     pub key: Token,
     block: Block,
     cache: RwLock<TigerHashMap<Loc, ScopeContext>>,
+    /// Whether anything refers to this list
+    used: AtomicBool,
 }
 
 impl List {
     pub fn new(key: Token, block: Block) -> Self {
-        Self { key, block, cache: RwLock::new(TigerHashMap::default()) }
+        Self {
+            key,
+            block,
+            cache: RwLock::new(TigerHashMap::default()),
+            used: AtomicBool::new(false),
+        }
     }
 
     fn cached_compat(&self, key: &Token, sc: &mut ScopeContext, data: &Everything) -> bool {
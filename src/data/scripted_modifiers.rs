@@ -1,15 +1,19 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
 
 use crate::block::Block;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::helpers::{BANNED_NAMES, TigerHashMap, limited_item_prefix_should_insert};
+use crate::helpers::{
+    BANNED_NAMES, TigerHashMap, limited_item_prefix_should_insert, report_unused,
+};
 use crate::item::Item;
-use crate::macros::{MACRO_MAP, MacroCache};
+use crate::macros::{MacroCache, macro_map};
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
-use crate::report::{ErrorKey, err};
+use crate::report::{Confidence, ErrorKey, err};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
@@ -34,7 +38,7 @@ impl ScriptedModifiers {
             })
         {
             if block.source.is_some() {
-                MACRO_MAP.insert_or_get_loc(name.loc);
+                macro_map().insert_or_get_loc(name.loc);
             }
             self.scripted_modifiers.insert(name.as_str(), ScriptedModifier::new(name, block));
         }
@@ -47,7 +51,7 @@ impl ScriptedModifiers {
     }
 
     pub fn exists(&self, key: &str) -> bool {
-        self.scripted_modifiers.contains_key(key)
+        self.get(key).is_some()
     }
 
     pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
@@ -55,7 +59,18 @@ impl ScriptedModifiers {
     }
 
     pub fn get(&self, key: &str) -> Option<&ScriptedModifier> {
-        self.scripted_modifiers.get(key)
+        self.scripted_modifiers.get(key).inspect(|item| item.used.store(true, Relaxed))
+    }
+
+    pub fn check_unused(&self) {
+        let unused = self.scripted_modifiers.values().filter(|item| !item.used.load(Relaxed));
+        let msg = "Unused scripted modifier";
+        report_unused(
+            ErrorKey::UnusedDefinition,
+            Confidence::Reasonable,
+            msg,
+            unused.map(|i| &i.key),
+        );
     }
 
     pub fn validate(&self, data: &Everything) {
@@ -63,6 +78,13 @@ impl ScriptedModifiers {
             item.validate(data);
         }
     }
+
+    /// Forget the cached validation results, so that the next validation starts fresh.
+    pub fn clear_caches(&mut self) {
+        for item in self.scripted_modifiers.values_mut() {
+            item.clear_cache();
+        }
+    }
 }
 
 impl FileHandler<Block> for ScriptedModifiers {
@@ -83,6 +105,18 @@ impl FileHandler<Block> for ScriptedModifiers {
             self.load_item(key, block);
         }
     }
+
+    fn defined_keys(&self, block: &Block) -> Vec<&'static str> {
+        block
+            .iter_definitions()
+            .map(|(key, _)| key.as_str().split_once(':').map_or(key.as_str(), |(_, name)| name))
+            .collect()
+    }
+
+    fn forget_files(&mut self, forget: &dyn Fn(&Path) -> bool) -> bool {
+        self.scripted_modifiers.retain(|_, modifier| !forget(modifier.key.loc.pathname()));
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -90,11 +124,17 @@ pub struct ScriptedModifier {
     pub key: Token,
     block: Block,
     cache: MacroCache<ScopeContext>,
+    /// Whether anything refers to this modifier
+    used: AtomicBool,
 }
 
 impl ScriptedModifier {
     pub fn new(key: Token, block: Block) -> Self {
-        Self { key, block, cache: MacroCache::default() }
+        Self { key, block, cache: MacroCache::default(), used: AtomicBool::new(false) }
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
     }
 
     pub fn validate(&self, data: &Everything) {
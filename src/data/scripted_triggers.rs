@@ -1,18 +1,23 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
 
 use crate::block::Block;
-use crate::context::ScopeContext;
+use crate::context::{ScopeContext, ScriptSignature};
+use crate::declared_signature::DeclaredSignature;
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
 #[cfg(feature = "hoi4")]
 use crate::game::Game;
-use crate::helpers::{BANNED_NAMES, TigerHashMap, limited_item_prefix_should_insert};
+use crate::helpers::{
+    BANNED_NAMES, TigerHashMap, limited_item_prefix_should_insert, report_unused,
+};
 use crate::item::Item;
 use crate::lowercase::Lowercase;
-use crate::macros::{MACRO_MAP, MacroCache};
+use crate::macros::{MacroCache, macro_map};
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
-use crate::report::{ErrorKey, err, warn};
+use crate::report::{Confidence, ErrorKey, err, warn};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::tooltipped::Tooltipped;
@@ -22,8 +27,10 @@ use crate::validator::Validator;
 use crate::variables::Variables;
 
 #[derive(Debug, Default)]
+#[allow(clippy::struct_field_names)]
 pub struct Triggers {
     scope_overrides: TigerHashMap<&'static str, Scopes>,
+    signatures: TigerHashMap<&'static str, DeclaredSignature>,
     triggers: TigerHashMap<&'static str, Trigger>,
 }
 
@@ -42,10 +49,16 @@ impl Triggers {
                 .get(name.as_str())
                 .copied()
                 .or_else(|| builtin_scope_overrides(&name));
+            let declared = self
+                .signatures
+                .get(name.as_str())
+                .cloned()
+                .or_else(|| DeclaredSignature::from_comments(&name));
             if block.source.is_some() {
-                MACRO_MAP.insert_or_get_loc(name.loc);
+                macro_map().insert_or_get_loc(name.loc);
             }
-            self.triggers.insert(name.as_str(), Trigger::new(name, block, scope_override));
+            let trigger = Trigger::new(name, block, scope_override, declared);
+            self.triggers.insert(trigger.key.as_str(), trigger);
         }
     }
 
@@ -56,7 +69,7 @@ impl Triggers {
     }
 
     pub fn exists(&self, key: &str) -> bool {
-        self.triggers.contains_key(key)
+        self.get(key).is_some()
     }
 
     pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
@@ -64,18 +77,44 @@ impl Triggers {
     }
 
     pub fn get(&self, key: &str) -> Option<&Trigger> {
+        self.triggers.get(key).inspect(|item| item.mark_used())
+    }
+
+    /// Like [`Self::get`], but without marking the item as used.
+    pub fn peek(&self, key: &str) -> Option<&Trigger> {
         self.triggers.get(key)
     }
 
     pub fn validate(&self, data: &Everything) {
         for item in self.triggers.values() {
-            item.validate(data);
+            if data.should_validate(item.key.loc) {
+                item.validate(data);
+            }
+        }
+    }
+
+    pub fn check_unused(&self) {
+        let unused = self.triggers.values().filter(|item| !item.is_used());
+        let msg = "Unused scripted trigger";
+        report_unused(
+            ErrorKey::UnusedDefinition,
+            Confidence::Reasonable,
+            msg,
+            unused.map(|i| &i.key),
+        );
+    }
+
+    /// Forget the cached validation results, so that the next validation starts fresh.
+    pub fn clear_caches(&mut self) {
+        for item in self.triggers.values_mut() {
+            item.clear_cache();
         }
     }
 }
 
 impl FileHandler<Block> for Triggers {
     fn config(&mut self, config: &Block) {
+        self.signatures = DeclaredSignature::from_config(config);
         if let Some(block) = config.get_field_block("scope_override") {
             for (key, token) in block.iter_assignments() {
                 let mut scopes = Scopes::empty();
@@ -117,6 +156,18 @@ impl FileHandler<Block> for Triggers {
             self.load_item(key, block);
         }
     }
+
+    fn defined_keys(&self, block: &Block) -> Vec<&'static str> {
+        block
+            .iter_definitions()
+            .map(|(key, _)| key.as_str().split_once(':').map_or(key.as_str(), |(_, name)| name))
+            .collect()
+    }
+
+    fn forget_files(&mut self, forget: &dyn Fn(&Path) -> bool) -> bool {
+        self.triggers.retain(|_, trigger| !forget(trigger.key.loc.pathname()));
+        true
+    }
 }
 
 #[derive(Debug)]
@@ -125,16 +176,48 @@ pub struct Trigger {
     pub block: Block,
     cache: MacroCache<ScopeContext>,
     scope_override: Option<Scopes>,
+    /// The signature declared by the user, if any
+    declared: Option<DeclaredSignature>,
+    /// Whether anything refers to this trigger
+    used: AtomicBool,
 }
 
 impl Trigger {
-    pub fn new(key: Token, block: Block, scope_override: Option<Scopes>) -> Self {
-        Self { key, block, cache: MacroCache::default(), scope_override }
+    pub fn new(
+        key: Token,
+        block: Block,
+        scope_override: Option<Scopes>,
+        declared: Option<DeclaredSignature>,
+    ) -> Self {
+        Self {
+            key,
+            block,
+            cache: MacroCache::default(),
+            scope_override,
+            declared,
+            used: AtomicBool::new(false),
+        }
+    }
+
+    pub fn mark_used(&self) {
+        self.used.store(true, Relaxed);
+    }
+
+    pub fn is_used(&self) -> bool {
+        self.used.load(Relaxed)
+    }
+
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
     }
 
     pub fn validate(&self, data: &Everything) {
         // We could let triggers get "naturally" validated by being called from other places,
         // but we want to also validate triggers that aren't called from anywhere yet.
+        if let Some(declared) = &self.declared {
+            declared.check_parameters(&self.key, &self.macro_parms());
+            declared.check_no_returns();
+        }
         if self.block.source.is_none() {
             let mut sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
             sc.set_strict_scopes(false);
@@ -145,6 +228,21 @@ impl Trigger {
         }
     }
 
+    /// Make the scope context that this trigger's body is validated in.
+    fn new_scope_context(&self) -> ScopeContext {
+        let mut sc = if let Some(declared) = &self.declared {
+            declared.scope_context(&self.key)
+        } else {
+            let mut sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
+            sc.set_strict_scopes(false);
+            sc
+        };
+        if self.scope_override.is_some() {
+            sc.set_no_warn(true);
+        }
+        sc
+    }
+
     pub fn validate_call(
         &self,
         key: &Token,
@@ -153,12 +251,9 @@ impl Trigger {
         tooltipped: Tooltipped,
         negated: bool,
     ) {
+        data.explain.record_call(key, sc, data);
         if !self.cached_compat(key, &[], tooltipped, negated, sc, data) {
-            let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
-            our_sc.set_strict_scopes(false);
-            if self.scope_override.is_some() {
-                our_sc.set_no_warn(true);
-            }
+            let mut our_sc = self.new_scope_context();
             self.cache.insert(key, &[], tooltipped, negated, our_sc.clone());
             let vd = Validator::new(&self.block, data);
             validate_trigger_internal(
@@ -180,6 +275,26 @@ impl Trigger {
         }
     }
 
+    /// Return what validating this trigger on its own found out about how it must be called.
+    /// Returns `None` for triggers with macro parameters, because those are validated per call.
+    pub fn signature(&self, data: &Everything) -> Option<ScriptSignature> {
+        let mut signature = None;
+        self.cache.perform(&self.key, &[], Tooltipped::No, false, |sc| {
+            signature = Some(sc.script_signature(data));
+        });
+        signature
+    }
+
+    /// Return the scope context that validating this trigger on its own ended up with.
+    /// Returns `None` for triggers with macro parameters, because those are validated per call.
+    pub fn scope_context(&self) -> Option<ScopeContext> {
+        let mut result = None;
+        self.cache.perform(&self.key, &[], Tooltipped::No, false, |sc| {
+            result = Some(sc.clone());
+        });
+        result
+    }
+
     pub fn macro_parms(&self) -> Vec<&'static str> {
         self.block.macro_parms()
     }
@@ -207,16 +322,16 @@ impl Trigger {
         tooltipped: Tooltipped,
         negated: bool,
     ) {
+        data.explain.record_call(key, sc, data);
+        if let Some(declared) = &self.declared {
+            declared.validate_args(args, data, sc);
+        }
         // Every invocation is treated as different even if the args are the same,
         // because we want to point to the correct one when reporting errors.
         if !self.cached_compat(key, args, tooltipped, negated, sc, data)
             && let Some(block) = self.block.expand_macro(args, key.loc, &data.parser.pdxfile)
         {
-            let mut our_sc = ScopeContext::new_unrooted(Scopes::all(), &self.key);
-            our_sc.set_strict_scopes(false);
-            if self.scope_override.is_some() {
-                our_sc.set_no_warn(true);
-            }
+            let mut our_sc = self.new_scope_context();
             // Insert the dummy sc before continuing. That way, if we recurse, we'll hit
             // that dummy context instead of macro-expanding again.
             self.cache.insert(key, args, tooltipped, negated, our_sc.clone());
@@ -4,25 +4,31 @@
 
 use std::any::Any;
 use std::fmt::Debug;
-use std::mem::take;
+use std::mem::{replace, take};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
 
 use as_any::AsAny;
 use itertools::Itertools;
 use rayon::prelude::*;
-use strum::EnumCount;
+use strum::{EnumCount, IntoEnumIterator};
 
 use crate::block::Block;
 use crate::context::ScopeContext;
 use crate::everything::Everything;
 use crate::game::Game;
-use crate::helpers::{TigerHashMap, TigerHashSet, dup_error, exact_dup_advice, exact_dup_error};
+use crate::helpers::{
+    TigerHashMap, TigerHashSet, dup_error, exact_dup_advice, exact_dup_error, report_unused,
+};
 use crate::item::Item;
 #[cfg(any(feature = "vic3", feature = "eu5"))]
 use crate::item::ItemExt;
 use crate::lowercase::Lowercase;
 #[cfg(any(feature = "vic3", feature = "eu5"))]
-use crate::report::{ErrorKey, err};
-use crate::token::Token;
+use crate::report::err;
+use crate::report::{Confidence, ErrorKey};
+use crate::token::{Loc, Token};
 use crate::variables::Variables;
 
 pub type FlagValidator = fn(&Token, &Everything);
@@ -41,6 +47,21 @@ pub struct Db {
     anonymous: Vec<DbEntry>,
     /// Lowercased registry of database items and flags, for case insensitive lookups
     items_lc: Vec<TigerHashMap<Lowercase<'static>, &'static str>>,
+    /// Items that were overridden by later items with the same key. They are kept so that they
+    /// can take the place of the overriding item again if its file is reloaded without it.
+    /// The `Vec` is indexed with an `Item` discriminant.
+    shadowed: Vec<Vec<DbEntry>>,
+    /// Bookkeeping between [`Db::start_reload`] and [`Db::finish_reload`].
+    reload: Option<ReloadState>,
+}
+
+/// What happened to the database items while some of their files are being reloaded.
+#[derive(Debug, Default)]
+struct ReloadState {
+    /// The keys of the items that were removed or added, whose duplicates have to be checked again.
+    touched: TigerHashSet<(Item, &'static str)>,
+    /// The overridden items that were put back because the items overriding them were removed.
+    restored: TigerHashSet<Loc>,
 }
 
 impl Default for Db {
@@ -50,6 +71,8 @@ impl Default for Db {
             flags: (0..Item::COUNT).map(|_| (TigerHashSet::default(), None)).collect(),
             anonymous: Vec::new(),
             items_lc: (0..Item::COUNT).map(|_| TigerHashMap::default()).collect(),
+            shadowed: (0..Item::COUNT).map(|_| Vec::new()).collect(),
+            reload: None,
         }
     }
 }
@@ -97,7 +120,7 @@ impl Db {
                     }
                     "REPLACE" => {
                         if self.database[item as usize].contains_key(name.as_str()) {
-                            self.add_inner2(item, name, block, kind);
+                            self.add_inner2(item, name, block, kind, false);
                         } else {
                             let msg = "replacing a non-existing item";
                             err(ErrorKey::Prefixes).msg(msg).loc(name).push();
@@ -110,17 +133,17 @@ impl Db {
                     }
                     "TRY_REPLACE" => {
                         if self.database[item as usize].contains_key(name.as_str()) {
-                            self.add_inner2(item, name, block, kind);
+                            self.add_inner2(item, name, block, kind, false);
                         }
                     }
                     "REPLACE_OR_CREATE" => {
-                        self.add_inner2(item, name, block, kind);
+                        self.add_inner2(item, name, block, kind, false);
                     }
                     "INJECT_OR_CREATE" => {
                         if let Some(other) = self.database[item as usize].get_mut(name.as_str()) {
                             other.inject(block, kind);
                         } else {
-                            self.add_inner2(item, name, block, kind);
+                            self.add_inner2(item, name, block, kind, false);
                         }
                     }
                     _ => {
@@ -140,34 +163,76 @@ impl Db {
                             .loc_msg(&other.key, "original here")
                             .push();
                     } else {
-                        self.add_inner2(item, key, block, kind);
+                        self.add_inner2(item, key, block, kind, false);
                     }
                 } else {
-                    self.add_inner2(item, key, block, kind);
+                    self.add_inner2(item, key, block, kind, false);
                 }
             }
         } else {
-            if let Some(other) = self.database[item as usize].get(key.as_str())
+            // While reloading, the items are not added in load order, so the duplicates are
+            // checked afterward by `finish_reload`.
+            if self.reload.is_none()
+                && let Some(other) = self.database[item as usize].get(key.as_str())
                 && other.key.loc.kind >= key.loc.kind
             {
                 if other.block.equivalent(&block) {
                     if exact_dup_ok {
                         exact_dup_advice(&key, &other.key, &item.to_string());
                     } else {
-                        exact_dup_error(&key, &other.key, &item.to_string());
+                        exact_dup_error(
+                            &key,
+                            Some(&block),
+                            &other.key,
+                            Some(&other.block),
+                            &item.to_string(),
+                        );
                     }
                 } else {
                     dup_error(&key, &other.key, &item.to_string());
                 }
             }
-            self.add_inner2(item, key, block, kind);
+            self.add_inner2(item, key, block, kind, exact_dup_ok);
         }
     }
 
-    /// Actually add the item to the database, replacing any of the same name that were there before.
-    fn add_inner2(&mut self, item: Item, key: Token, block: Block, kind: Box<dyn DbKind>) {
-        self.items_lc[item as usize].insert(Lowercase::new(key.as_str()), key.as_str());
-        self.database[item as usize].insert(key.as_str(), DbEntry { key, block, kind });
+    /// Actually add the item to the database, replacing any of the same name that were loaded
+    /// before it.
+    fn add_inner2(
+        &mut self,
+        item: Item,
+        key: Token,
+        block: Block,
+        kind: Box<dyn DbKind>,
+        exact_dup_ok: bool,
+    ) {
+        let reloading = if let Some(state) = &mut self.reload {
+            state.touched.insert((item, key.as_str()));
+            true
+        } else {
+            false
+        };
+        let entry = DbEntry::new(key, block, kind, exact_dup_ok);
+        if let Some(other) = self.database[item as usize].get_mut(entry.key.as_str()) {
+            // Items are added in load order, except when some of their files are reloaded.
+            if reloading && load_order(entry.key.loc) < load_order(other.key.loc) {
+                self.shadowed[item as usize].push(entry);
+                return;
+            }
+            self.items_lc[item as usize]
+                .insert(Lowercase::new(entry.key.as_str()), entry.key.as_str());
+            if other.key.loc == entry.key.loc {
+                // The same item added again, by `add_subitems` of a restored item.
+                *other = entry;
+            } else {
+                let other = replace(other, entry);
+                self.shadowed[item as usize].push(other);
+            }
+        } else {
+            self.items_lc[item as usize]
+                .insert(Lowercase::new(entry.key.as_str()), entry.key.as_str());
+            self.database[item as usize].insert(entry.key.as_str(), entry);
+        }
     }
 
     #[cfg(feature = "hoi4")]
@@ -182,13 +247,22 @@ impl Db {
 
     #[cfg(feature = "hoi4")]
     pub fn add_anonymous(&mut self, ident: Token, block: Block, kind: Box<dyn DbKind>) {
-        self.anonymous.push(DbEntry { key: ident, block, kind });
+        self.anonymous.push(DbEntry::new(ident, block, kind, false));
     }
 
     pub fn add_subitems(&mut self) {
+        self.add_subitems_filtered(|_| true);
+    }
+
+    /// Like [`Db::add_subitems`], but only for the items defined in files under one of the `paths`.
+    pub fn add_subitems_under(&mut self, paths: &[PathBuf]) {
+        self.add_subitems_filtered(|entry| is_under(&entry.key, paths));
+    }
+
+    fn add_subitems_filtered<F: Fn(&DbEntry) -> bool>(&mut self, f: F) {
         for itype in 0..Item::COUNT {
             let queue = take(&mut self.database[itype]);
-            for entry in queue.values().sorted_by_key(|e| e.key.loc) {
+            for entry in queue.values().filter(|e| f(e)).sorted_by_key(|e| e.key.loc) {
                 entry.kind.add_subitems(&entry.key, &entry.block, self);
             }
             if self.database[itype].is_empty() {
@@ -201,6 +275,155 @@ impl Db {
         }
     }
 
+    /// Remove all items, flags and anonymous items that were defined in files under one of the
+    /// `paths`, so that those files can be loaded again.
+    ///
+    /// Items that were overridden by items from these files are not restored, so the caller should
+    /// reload all the files under `paths` and not just the changed ones.
+    pub fn remove_under(&mut self, paths: &[PathBuf]) {
+        for map in &mut self.database {
+            map.retain(|_, entry| !is_under(&entry.key, paths));
+        }
+        for (set, _) in &mut self.flags {
+            set.retain(|flag| !is_under(flag, paths));
+        }
+        self.anonymous.retain(|entry| !is_under(&entry.key, paths));
+        for shadowed in &mut self.shadowed {
+            shadowed.retain(|entry| !is_under(&entry.key, paths));
+        }
+        self.retain_items_lc();
+    }
+
+    /// Remove all items, flags and anonymous items that were defined in `files`, so that those
+    /// files can be loaded again. Items that they had overridden take their place again.
+    ///
+    /// `files` are paths relative to their roots, and must be sorted. After loading the files,
+    /// [`Db::finish_reload`] must be called with the same `files`.
+    pub fn start_reload(&mut self, files: &[PathBuf]) {
+        let mut state = ReloadState::default();
+        for itype in Item::iter() {
+            let shadowed = &mut self.shadowed[itype as usize];
+            shadowed.retain(|entry| {
+                let keep = !is_in(&entry.key, files);
+                if !keep {
+                    state.touched.insert((itype, entry.key.as_str()));
+                }
+                keep
+            });
+
+            let map = &mut self.database[itype as usize];
+            let removed: Vec<&'static str> = map
+                .values()
+                .filter(|entry| is_in(&entry.key, files))
+                .map(|entry| entry.key.as_str())
+                .collect();
+            for key in removed {
+                map.remove(key);
+                state.touched.insert((itype, key));
+                let latest = shadowed
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.key.is(key))
+                    .max_by_key(|(_, entry)| load_order(entry.key.loc))
+                    .map(|(idx, _)| idx);
+                if let Some(idx) = latest {
+                    let entry = shadowed.swap_remove(idx);
+                    state.restored.insert(entry.key.loc);
+                    map.insert(entry.key.as_str(), entry);
+                }
+            }
+        }
+        for (set, _) in &mut self.flags {
+            set.retain(|flag| !is_in(flag, files));
+        }
+        self.anonymous.retain(|entry| !is_in(&entry.key, files));
+        self.retain_items_lc();
+        self.reload = Some(state);
+    }
+
+    /// Finish what [`Db::start_reload`] started, after the `files` have been loaded again.
+    ///
+    /// This adds the subitems of the items from `files` and of the items that were put back, and
+    /// checks the duplicates of all items whose keys were removed or added.
+    ///
+    /// The subitems of items that are now overridden by items from `files` are not removed.
+    pub fn finish_reload(&mut self, files: &[PathBuf]) {
+        let restored = self.reload.as_mut().map(|state| take(&mut state.restored));
+        let restored = restored.unwrap_or_default();
+        self.add_subitems_filtered(|entry| {
+            is_in(&entry.key, files) || restored.contains(&entry.key.loc)
+        });
+        let Some(state) = self.reload.take() else {
+            return;
+        };
+        if !Game::is_vic3() && !Game::is_eu5() {
+            for (itype, key) in state.touched {
+                self.check_duplicates(itype, key);
+            }
+        }
+    }
+
+    /// Report the duplicates of `key` the way [`Db::add`] would have, if all its definitions had
+    /// been added in load order.
+    fn check_duplicates(&self, itype: Item, key: &str) {
+        let mut entries: Vec<&DbEntry> = self.shadowed[itype as usize]
+            .iter()
+            .filter(|entry| entry.key.is(key))
+            .chain(self.database[itype as usize].get(key))
+            .collect();
+        entries.sort_by_key(|entry| load_order(entry.key.loc));
+        for (other, entry) in entries.iter().tuple_windows() {
+            if other.key.loc.kind >= entry.key.loc.kind {
+                if other.block.equivalent(&entry.block) {
+                    if entry.exact_dup_ok {
+                        exact_dup_advice(&entry.key, &other.key, &itype.to_string());
+                    } else {
+                        exact_dup_error(
+                            &entry.key,
+                            Some(&entry.block),
+                            &other.key,
+                            Some(&other.block),
+                            &itype.to_string(),
+                        );
+                    }
+                } else {
+                    dup_error(&entry.key, &other.key, &itype.to_string());
+                }
+            }
+        }
+    }
+
+    /// Return the files that define any of the items in `keys`, including the items that are
+    /// overridden.
+    pub fn files_defining(&self, keys: &TigerHashSet<(Item, &str)>) -> TigerHashSet<&'static Path> {
+        let mut files = TigerHashSet::default();
+        for (itype, key) in keys {
+            let shadowed = self.shadowed[*itype as usize].iter().filter(|entry| entry.key.is(key));
+            for entry in shadowed.chain(self.database[*itype as usize].get(key)) {
+                files.insert(entry.key.loc.pathname());
+            }
+        }
+        files
+    }
+
+    /// Drop the lowercased keys of items and flags that no longer exist.
+    fn retain_items_lc(&mut self) {
+        for (itype, items_lc) in self.items_lc.iter_mut().enumerate() {
+            let database = &self.database[itype];
+            let flags = &self.flags[itype].0;
+            items_lc.retain(|_, key| database.contains_key(key) || flags.contains(*key));
+        }
+    }
+
+    /// Forget the cached validation results of all items, so that the next validation starts fresh.
+    pub fn clear_caches(&mut self) {
+        for map in &mut self.database {
+            for entry in map.values_mut() {
+                entry.kind.clear_cache();
+            }
+        }
+    }
+
     pub fn scan_variables(&self, registry: &mut Variables) {
         for map in &self.database {
             for entry in map.values() {
@@ -212,32 +435,60 @@ impl Db {
     pub fn validate(&self, data: &Everything) {
         self.database.par_iter().for_each(|map| {
             map.par_iter().for_each(|(_, entry)| {
-                entry.kind.validate(&entry.key, &entry.block, data);
+                if data.should_validate(entry.key.loc) {
+                    entry.kind.validate(&entry.key, &entry.block, data);
+                }
             });
         });
         self.flags.par_iter().for_each(|(map, fv)| {
             if let Some(fv) = fv {
                 map.par_iter().for_each(|flag| {
-                    fv(flag, data);
+                    if data.should_validate(flag.loc) {
+                        fv(flag, data);
+                    }
                 });
             }
         });
         self.anonymous.par_iter().for_each(|entry| {
-            entry.kind.validate(&entry.key, &entry.block, data);
+            if data.should_validate(entry.key.loc) {
+                entry.kind.validate(&entry.key, &entry.block, data);
+            }
         });
     }
 
+    /// Look up a database entry, and record that it is used.
+    fn get_entry(&self, item: Item, key: &str) -> Option<&DbEntry> {
+        self.database[item as usize].get(key).inspect(|entry| entry.used.store(true, Relaxed))
+    }
+
     pub fn exists(&self, item: Item, key: &str) -> bool {
-        self.database[item as usize].contains_key(key) || self.flags[item as usize].0.contains(key)
+        self.get_entry(item, key).is_some() || self.flags[item as usize].0.contains(key)
     }
 
     pub fn exists_lc(&self, item: Item, key: &Lowercase) -> bool {
-        self.items_lc[item as usize].contains_key(key)
+        if let Some(real_key) = self.items_lc[item as usize].get(key) {
+            self.get_entry(item, real_key);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Report the database items that were never referenced.
+    ///
+    /// Many items are used by the game engine directly, without being named anywhere in script, so
+    /// these reports are less reliable than the others about unused definitions.
+    pub fn check_unused(&self) {
+        for itype in Item::iter() {
+            let unused = self.database[itype as usize].values().filter(|e| !e.used.load(Relaxed));
+            let msg = format!("Unused {itype}");
+            report_unused(ErrorKey::UnusedItem, Confidence::Weak, &msg, unused.map(|e| &e.key));
+        }
     }
 
     #[allow(dead_code)]
     pub fn get_item<T: DbKind + Any>(&self, item: Item, key: &str) -> Option<(&Token, &Block, &T)> {
-        if let Some(entry) = self.database[item as usize].get(key)
+        if let Some(entry) = self.get_entry(item, key)
             && let Some(kind) = (*entry.kind).as_any().downcast_ref::<T>()
         {
             return Some((&entry.key, &entry.block, kind));
@@ -245,13 +496,23 @@ impl Db {
         None
     }
 
-    pub fn get_key_block(&self, item: Item, key: &str) -> Option<(&Token, &Block)> {
+    /// Return whether the database has any items of type `item` that are defined by a block.
+    pub fn has_blocks(&self, item: Item) -> bool {
+        !self.database[item as usize].is_empty()
+    }
+
+    /// Like [`Db::get_key_block`], but without marking the item as used.
+    pub fn peek_key_block(&self, item: Item, key: &str) -> Option<(&Token, &Block)> {
         self.database[item as usize].get(key).map(|entry| (&entry.key, &entry.block))
     }
 
+    pub fn get_key_block(&self, item: Item, key: &str) -> Option<(&Token, &Block)> {
+        self.get_entry(item, key).map(|entry| (&entry.key, &entry.block))
+    }
+
     #[allow(dead_code)]
     pub fn has_property(&self, item: Item, key: &str, property: &str, data: &Everything) -> bool {
-        if let Some(entry) = self.database[item as usize].get(key) {
+        if let Some(entry) = self.get_entry(item, key) {
             entry.kind.has_property(&entry.key, &entry.block, property, data)
         } else {
             false
@@ -267,7 +528,7 @@ impl Db {
         data: &Everything,
     ) -> bool {
         let real_key = self.items_lc[item as usize].get(key);
-        if let Some(entry) = real_key.and_then(|key| self.database[item as usize].get(key)) {
+        if let Some(entry) = real_key.and_then(|key| self.get_entry(item, key)) {
             entry.kind.has_property(&entry.key, &entry.block, property, data)
         } else {
             false
@@ -290,14 +551,14 @@ impl Db {
         data: &Everything,
         sc: &mut ScopeContext,
     ) {
-        if let Some(entry) = self.database[item as usize].get(key.as_str()) {
+        if let Some(entry) = self.get_entry(item, key.as_str()) {
             entry.kind.validate_call(&entry.key, &entry.block, key, block, data, sc);
         }
     }
 
     #[allow(dead_code)]
     pub fn validate_use(&self, item: Item, key: &Token, block: &Block, data: &Everything) {
-        if let Some(entry) = self.database[item as usize].get(key.as_str()) {
+        if let Some(entry) = self.get_entry(item, key.as_str()) {
             entry.kind.validate_use(&entry.key, &entry.block, data, key, block);
         }
     }
@@ -311,7 +572,7 @@ impl Db {
         property: &Token,
         caller: &str,
     ) {
-        if let Some(entry) = self.database[item as usize].get(key.as_str()) {
+        if let Some(entry) = self.get_entry(item, key.as_str()) {
             entry.kind.validate_property_use(&entry.key, &entry.block, property, caller, data);
         }
     }
@@ -334,9 +595,17 @@ pub struct DbEntry {
     key: Token,
     block: Block,
     kind: Box<dyn DbKind>,
+    /// Whether anything refers to this item
+    used: AtomicBool,
+    /// Whether an exact duplicate of an earlier item is only worth an advice report.
+    exact_dup_ok: bool,
 }
 
 impl DbEntry {
+    fn new(key: Token, block: Block, kind: Box<dyn DbKind>, exact_dup_ok: bool) -> Self {
+        Self { key, block, kind, used: AtomicBool::new(false), exact_dup_ok }
+    }
+
     #[cfg(any(feature = "vic3", feature = "eu5"))]
     fn inject(&mut self, mut block: Block, kind: Box<dyn DbKind>) {
         self.block.append(&mut block);
@@ -395,4 +664,24 @@ pub trait DbKind: Debug + AsAny + Sync + Send {
     fn set_property(&mut self, _key: &Token, _block: &Block, _property: &str) {}
 
     fn merge_in(&mut self, _other: Box<dyn DbKind>) {}
+
+    /// Forget anything remembered from an earlier validation.
+    fn clear_cache(&mut self) {}
+}
+
+/// Whether `key` was defined in a file under one of the `paths`.
+fn is_under(key: &Token, paths: &[PathBuf]) -> bool {
+    let pathname = key.loc.pathname();
+    paths.iter().any(|path| pathname.starts_with(path))
+}
+
+/// Whether `key` was defined in one of the `files`, which must be sorted.
+fn is_in(key: &Token, files: &[PathBuf]) -> bool {
+    let pathname = key.loc.pathname();
+    files.binary_search_by(|file| file.as_path().cmp(pathname)).is_ok()
+}
+
+/// The order in which items are loaded, so that later ones override earlier ones.
+fn load_order(loc: Loc) -> (&'static Path, u32, u32) {
+    (loc.pathname(), loc.line, loc.column)
 }
@@ -2,7 +2,7 @@
 
 use std::fs::{File, metadata};
 use std::io::{Read, Result};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::fileset::{FileEntry, FileHandler};
 use crate::helpers::TigerHashMap;
@@ -105,6 +105,11 @@ impl FileHandler<DdsInfo> for DdsFiles {
     fn handle_file(&mut self, entry: &FileEntry, info: DdsInfo) {
         self.handle_dds(entry, info);
     }
+
+    fn forget_files(&mut self, forget: &dyn Fn(&Path) -> bool) -> bool {
+        self.dds_files.retain(|_, info| !forget(info.entry.path()));
+        true
+    }
 }
 
 #[derive(Clone, Debug)]
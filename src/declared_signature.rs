@@ -0,0 +1,363 @@
+//! Signatures of scripted triggers and effects as declared by the user, rather than deduced by
+//! validating their bodies.
+//!
+//! A signature can be declared in the config file, in a `signature` block next to
+//! `scope_override`, or in `# tiger:` comments right above the definition. Both use the same
+//! fields:
+//!
+//! ```text
+//! # tiger: scope = character root = character
+//! # tiger: scopes = { target = character } args = { AMOUNT = value TRAIT = trait }
+//! # tiger: returns = { new_title = landed_title }
+//! my_effect = { ... }
+//! ```
+//!
+//! The body is then validated against the declaration, and so is every call.
+
+use std::path::{Path, PathBuf};
+use std::slice;
+
+use strum::IntoEnumIterator;
+
+use crate::block::{BV, Block};
+use crate::context::ScopeContext;
+use crate::everything::Everything;
+use crate::helpers::TigerHashMap;
+use crate::item::Item;
+use crate::parse::pdxfile::{PdxfileMemory, parse_pdx_macro};
+use crate::report::{ErrorKey, warn};
+use crate::scopes::Scopes;
+#[cfg(feature = "jomini")]
+use crate::script_value::validate_script_value;
+use crate::session::SessionState;
+use crate::token::{Loc, Token};
+use crate::trigger::validate_target_ok_this;
+
+/// What a macro argument must be.
+#[derive(Clone, Copy, Debug)]
+enum ArgKind {
+    /// A target of one of these scope types.
+    Scope(Scopes),
+    /// The key of an item of this type.
+    Item(Item),
+    /// A script value.
+    #[cfg(feature = "jomini")]
+    Value,
+    /// `yes` or `no`.
+    Bool,
+}
+
+impl ArgKind {
+    /// Parse an argument type. Scope types are preferred over item types of the same name;
+    /// those items can be asked for with `item:` in front.
+    fn from_token(token: &Token) -> Option<Self> {
+        #[cfg(feature = "jomini")]
+        if token.is("value") {
+            return Some(ArgKind::Value);
+        }
+        if token.is("bool") {
+            return Some(ArgKind::Bool);
+        }
+        if let Some(name) = token.as_str().strip_prefix("item:") {
+            return item_from_name(name).map(ArgKind::Item);
+        }
+        if token.lowercase_is("all")
+            || token.split('|').iter().all(|part| Scopes::from_snake_case(part.as_str()).is_some())
+        {
+            return Some(ArgKind::Scope(scopes_from_token(token)));
+        }
+        item_from_name(token.as_str()).map(ArgKind::Item)
+    }
+
+    fn validate(self, arg: &Token, data: &Everything, sc: &mut ScopeContext) {
+        match self {
+            ArgKind::Scope(scopes) => {
+                validate_target_ok_this(arg, data, sc, scopes);
+            }
+            ArgKind::Item(itype) => data.verify_exists(itype, arg),
+            #[cfg(feature = "jomini")]
+            ArgKind::Value => validate_script_value(&BV::Value(arg.clone()), data, sc),
+            ArgKind::Bool => {
+                if !arg.is("yes") && !arg.is("no") {
+                    let msg = "expected yes or no";
+                    warn(ErrorKey::Validation).msg(msg).loc(arg).push();
+                }
+            }
+        }
+    }
+}
+
+fn item_from_name(name: &str) -> Option<Item> {
+    Item::iter().find(|itype| <&str>::from(*itype) == name)
+}
+
+/// Parse a list of scope types like `scope_override` does: ALL, or a list separated by `|`.
+fn scopes_from_token(token: &Token) -> Scopes {
+    if token.lowercase_is("all") {
+        return Scopes::all();
+    }
+    let mut scopes = Scopes::empty();
+    for part in token.split('|') {
+        if let Some(scope) = Scopes::from_snake_case(part.as_str()) {
+            scopes |= scope;
+        } else {
+            let msg = format!("unknown scope type `{part}`");
+            warn(ErrorKey::Config).msg(msg).loc(part).push();
+        }
+    }
+    scopes
+}
+
+/// The declared signature of a scripted trigger or effect.
+#[derive(Clone, Debug, Default)]
+pub struct DeclaredSignature {
+    /// The scope types of `this`, and the token they were declared with.
+    this: Option<(Scopes, Token)>,
+    /// The scope types of `root`, and the token they were declared with.
+    root: Option<(Scopes, Token)>,
+    /// The named scopes that the caller must supply.
+    scopes: Vec<(&'static str, Scopes, Token)>,
+    /// The macro parameters, keyed by the token that names them. `None` if no `args` were declared.
+    args: Option<Vec<(Token, ArgKind)>>,
+    /// The named scopes that are saved for the caller.
+    returns: Vec<(&'static str, Scopes, Token)>,
+}
+
+impl DeclaredSignature {
+    /// Read the `signature` block from the config file.
+    pub fn from_config(config: &Block) -> TigerHashMap<&'static str, Self> {
+        let mut signatures = TigerHashMap::default();
+        if let Some(block) = config.get_field_block("signature") {
+            for (key, block) in block.iter_definitions_warn() {
+                let mut signature = Self::default();
+                signature.add_block(block);
+                signatures.insert(key.as_str(), signature);
+            }
+        }
+        signatures
+    }
+
+    /// Read the signature from the `# tiger:` comments right above the definition of `key`.
+    /// They may be mixed with other comments, but not with blank lines.
+    pub fn from_comments(key: &Token) -> Option<Self> {
+        let tokens = SessionState::current()
+            .signature_comments
+            .lock()
+            .unwrap()
+            .get(&(key.loc.pathname(), key.loc.line))
+            .cloned()?;
+        let mut signature = Self::default();
+        let memory = PdxfileMemory::default();
+        for token in &tokens {
+            signature.add_block(&parse_pdx_macro(slice::from_ref(token), &memory, &memory));
+        }
+        Some(signature)
+    }
+
+    fn add_block(&mut self, block: &Block) {
+        fn named_scopes(block: &Block) -> impl Iterator<Item = (&'static str, Scopes, Token)> {
+            block
+                .iter_assignments_warn()
+                .map(|(name, token)| (name.as_str(), scopes_from_token(token), token.clone()))
+        }
+
+        for (key, bv) in block.iter_assignments_and_definitions_warn() {
+            match (key.as_str(), bv) {
+                ("scope", BV::Value(token)) => {
+                    self.this = Some((scopes_from_token(token), token.clone()));
+                }
+                ("root", BV::Value(token)) => {
+                    self.root = Some((scopes_from_token(token), token.clone()));
+                }
+                ("scopes", BV::Block(block)) => self.scopes.extend(named_scopes(block)),
+                ("args", BV::Block(block)) => {
+                    let args = self.args.get_or_insert_default();
+                    for (name, token) in block.iter_assignments_warn() {
+                        if let Some(kind) = ArgKind::from_token(token) {
+                            args.push((name.clone(), kind));
+                        } else {
+                            let msg = format!("unknown argument type `{token}`");
+                            let info = "expected a scope type, an item type, value, or bool";
+                            warn(ErrorKey::Config).msg(msg).info(info).loc(token).push();
+                        }
+                    }
+                }
+                ("returns", BV::Block(block)) => self.returns.extend(named_scopes(block)),
+                _ => {
+                    let msg = format!("unknown signature field `{key}`");
+                    let info = "expected scope, root, scopes, args, or returns";
+                    warn(ErrorKey::Config).msg(msg).info(info).loc(key).push();
+                }
+            }
+        }
+    }
+
+    /// Make the scope context that the body of the trigger or effect `key` is validated in.
+    /// Named scopes that aren't declared are not available in it.
+    pub fn scope_context(&self, key: &Token) -> ScopeContext {
+        let mut sc = ScopeContext::new_unrooted(Scopes::all(), key);
+        if let Some((scopes, token)) = &self.this {
+            sc.declare_this(*scopes, token);
+        }
+        if let Some((scopes, token)) = &self.root {
+            sc.declare_root(*scopes, token);
+        }
+        for (name, scopes, token) in &self.scopes {
+            sc.declare_input_name(name, *scopes, token);
+        }
+        sc
+    }
+
+    /// Check the declared `args` against the macro parameters of the definition of `key`.
+    pub fn check_parameters(&self, key: &Token, parms: &[&str]) {
+        let Some(args) = &self.args else { return };
+        for (name, _) in args {
+            if !parms.contains(&name.as_str()) {
+                let msg = format!("`{key}` has no parameter ${name}$");
+                warn(ErrorKey::Macro).msg(msg).loc(name).push();
+            }
+        }
+        for parm in parms {
+            if !args.iter().any(|(name, _)| name.is(parm)) {
+                let msg = format!("parameter ${parm}$ is not declared in the signature of `{key}`");
+                warn(ErrorKey::Macro).msg(msg).loc(key).push();
+            }
+        }
+    }
+
+    /// Check the arguments of a call against the declared `args`. `sc` is the caller's scope
+    /// context.
+    pub fn validate_args(
+        &self,
+        args: &[(&'static str, Token)],
+        data: &Everything,
+        sc: &mut ScopeContext,
+    ) {
+        let Some(declared) = &self.args else { return };
+        for (name, kind) in declared {
+            if let Some((_, arg)) = args.iter().find(|(parm, _)| name.is(parm)) {
+                kind.validate(arg, data, sc);
+            }
+        }
+    }
+
+    /// Warn about declared return scopes on a scripted trigger, which can't save any.
+    pub fn check_no_returns(&self) {
+        for (name, _, token) in &self.returns {
+            let msg = format!("a scripted trigger can't save scope:{name}");
+            warn(ErrorKey::Scopes).msg(msg).loc(token).push();
+        }
+    }
+
+    /// Check that the body of the scripted effect `key`, validated in `sc`, saves the named
+    /// scopes that it's declared to return.
+    pub fn check_returns(&self, key: &Token, sc: &ScopeContext, data: &Everything) {
+        for (name, scopes, token) in &self.returns {
+            match sc.saved_name_scopes(name, data) {
+                None => {
+                    let msg =
+                        format!("`{key}` is declared to return scope:{name} but never saves it");
+                    warn(ErrorKey::Scopes).msg(msg).loc(token).push();
+                }
+                Some(saved) if !saved.intersects(*scopes) => {
+                    let msg = format!(
+                        "`{key}` is declared to return scope:{name} as {scopes} but saves it as {saved}"
+                    );
+                    warn(ErrorKey::Scopes).msg(msg).loc(token).push();
+                }
+                Some(_) => (),
+            }
+        }
+    }
+}
+
+/// The `# tiger:` comments found by the parser, by the file and line of the item right below
+/// them.
+pub(crate) type SignatureComments = TigerHashMap<(&'static Path, u32), Vec<Token>>;
+
+/// Remember the `# tiger:` comments that are right above the item at `loc`.
+pub(crate) fn register_signature_comments(loc: Loc, comments: Vec<Token>) {
+    let mut registry = SessionState::current().signature_comments.lock().unwrap();
+    // The same file may be parsed again when it's reloaded.
+    registry.insert((loc.pathname(), loc.line), comments);
+}
+
+/// Forget the `# tiger:` comments of these files, before they are parsed again.
+pub(crate) fn forget_signature_comments(pathnames: &[PathBuf]) {
+    let mut registry = SessionState::current().signature_comments.lock().unwrap();
+    registry.retain(|(pathname, _), _| !pathnames.iter().any(|p| p == pathname));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::pdxfile::parse_pdx_internal;
+    use crate::report::take_reports;
+    use crate::session::Session;
+
+    fn messages() -> Vec<String> {
+        let mut messages: Vec<String> =
+            take_reports().into_keys().map(|report| report.msg).collect();
+        messages.sort();
+        messages
+    }
+
+    #[test]
+    fn test_from_config() {
+        Session::new().enter(|| {
+            let config = parse_pdx_internal(
+                "signature = {\n\
+                \tmy_effect = {\n\
+                \t\tscope = character root = all\n\
+                \t\tscopes = { target = character }\n\
+                \t\targs = { WHO = character FLAG = bool KEY = item:localization WHAT = nonsense }\n\
+                \t\treturns = { friend = character }\n\
+                \t\tcolor = red\n\
+                \t}\n\
+                }\n",
+                "signature",
+            );
+            let signatures = DeclaredSignature::from_config(&config);
+            let signature = &signatures["my_effect"];
+            assert_eq!(signature.this.as_ref().unwrap().0, Scopes::Character);
+            assert_eq!(signature.root.as_ref().unwrap().0, Scopes::all());
+            assert_eq!(signature.scopes[0].0, "target");
+            assert_eq!(signature.returns[0].0, "friend");
+
+            let args = signature.args.as_ref().unwrap();
+            assert_eq!(args.len(), 3);
+            assert!(matches!(args[0], (ref name, ArgKind::Scope(Scopes::Character)) if name.is("WHO")));
+            assert!(matches!(args[1].1, ArgKind::Bool));
+            assert!(matches!(args[2].1, ArgKind::Item(Item::Localization)));
+            assert_eq!(
+                messages(),
+                ["unknown argument type `nonsense`", "unknown signature field `color`"]
+            );
+        });
+    }
+
+    #[test]
+    fn test_check_parameters() {
+        Session::new().enter(|| {
+            let config = parse_pdx_internal(
+                "signature = { my_effect = { args = { A = bool B = bool } } }",
+                "signature",
+            );
+            let signature = &DeclaredSignature::from_config(&config)["my_effect"];
+            let (key, _) =
+                config.get_field_block("signature").unwrap().iter_definitions().next().unwrap();
+            signature.check_parameters(key, &["A", "C"]);
+            assert_eq!(
+                messages(),
+                [
+                    "`my_effect` has no parameter $B$",
+                    "parameter $C$ is not declared in the signature of `my_effect`",
+                ]
+            );
+
+            // Without declared args, the parameters are not checked.
+            DeclaredSignature::default().check_parameters(key, &["A"]);
+            assert!(messages().is_empty());
+        });
+    }
+}
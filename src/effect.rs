@@ -38,7 +38,15 @@ use crate::validator::{Validator, ValueValidator};
 
 /// Look up an effect name token in the effects table.
 /// `name` is the token. `data` is used in special cases to verify the name dynamically.
+/// Effects that are only known from the game's `script_docs` logs are not checked further.
 pub fn scope_effect(name: &Token, data: &Everything) -> Option<(Scopes, Effect)> {
+    builtin_scope_effect(name, data).or_else(|| {
+        data.script_docs.effect(name.as_str()).map(|scopes| (scopes, Effect::Unchecked))
+    })
+}
+
+/// Like [`scope_effect`], but only looks in the built-in tables.
+pub(crate) fn builtin_scope_effect(name: &Token, data: &Everything) -> Option<(Scopes, Effect)> {
     let scope_effect = match Game::game() {
         #[cfg(feature = "ck3")]
         Game::Ck3 => crate::ck3::tables::effects::scope_effect,
@@ -54,6 +62,24 @@ pub fn scope_effect(name: &Token, data: &Everything) -> Option<(Scopes, Effect)>
     scope_effect(name, data)
 }
 
+/// Return the names of all the builtin effects for the game being validated.
+///
+/// This does not include scripted effects, or effects whose names are constructed from game items.
+pub fn builtin_effect_names() -> Box<dyn Iterator<Item = &'static str>> {
+    match Game::game() {
+        #[cfg(feature = "ck3")]
+        Game::Ck3 => Box::new(crate::ck3::tables::effects::iter_effect_names()),
+        #[cfg(feature = "vic3")]
+        Game::Vic3 => Box::new(crate::vic3::tables::effects::iter_effect_names()),
+        #[cfg(feature = "imperator")]
+        Game::Imperator => Box::new(crate::imperator::tables::effects::iter_effect_names()),
+        #[cfg(feature = "eu5")]
+        Game::Eu5 => Box::new(crate::eu5::tables::effects::iter_effect_names()),
+        #[cfg(feature = "hoi4")]
+        Game::Hoi4 => Box::new(crate::hoi4::tables::effects::iter_effect_names()),
+    }
+}
+
 /// The standard interface to effect validation. Validates an effect in the given [`ScopeContext`].
 ///
 /// `tooltipped` determines what warnings are emitted related to tooltippability of the effects
@@ -0,0 +1,882 @@
+//! The graph of which events, on-actions and scripted effects fire which others.
+//!
+//! The graph is built from the loaded script by [`Everything::event_graph`]. It can be written
+//! out in DOT format, for drawing with Graphviz, or in JSON format for other tools.
+//! It is also used to find the events that can't be reached from anything the game fires.
+
+use std::collections::VecDeque;
+use std::io::Write;
+
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use crate::block::{BV, Block, BlockItem, Field};
+use crate::data::events::Event;
+use crate::everything::Everything;
+use crate::fileset::FileKind;
+use crate::helpers::{TigerHashMap, TigerHashSet};
+use crate::item::Item;
+use crate::on_action::on_action_scopecontext;
+use crate::report::{ErrorKey, Errors, err, game_id, location_json, warn};
+use crate::scopes::Scopes;
+use crate::token::{Loc, Token};
+
+/// The version of the JSON event graph format. It is increased whenever a field is removed or
+/// changes meaning. Adding fields does not change the version.
+pub const EVENT_GRAPH_SCHEMA_VERSION: u32 = 1;
+
+/// The kinds of script item that can be nodes in the [`EventGraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    Event,
+    OnAction,
+    ScriptedEffect,
+}
+
+impl NodeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            NodeKind::Event => "event",
+            NodeKind::OnAction => "on_action",
+            NodeKind::ScriptedEffect => "scripted_effect",
+        }
+    }
+}
+
+/// How one node of the [`EventGraph`] fires another. The names are those of the script fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    TriggerEvent,
+    Events,
+    RandomEvents,
+    FirstValid,
+    OnActions,
+    RandomOnAction,
+    FirstValidOnAction,
+    Fallback,
+    /// A call to a scripted effect.
+    ScriptedEffect,
+}
+
+impl EdgeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            EdgeKind::TriggerEvent => "trigger_event",
+            EdgeKind::Events => "events",
+            EdgeKind::RandomEvents => "random_events",
+            EdgeKind::FirstValid => "first_valid",
+            EdgeKind::OnActions => "on_actions",
+            EdgeKind::RandomOnAction => "random_on_action",
+            EdgeKind::FirstValidOnAction => "first_valid_on_action",
+            EdgeKind::Fallback => "fallback",
+            EdgeKind::ScriptedEffect => "scripted_effect",
+        }
+    }
+}
+
+/// An event, on-action or scripted effect in the [`EventGraph`].
+#[derive(Clone, Copy, Debug)]
+pub struct GraphNode {
+    pub kind: NodeKind,
+    pub key: &'static str,
+    /// Where the item is defined, or `None` if it is fired but not defined.
+    pub definition: Option<Loc>,
+    /// The scope types the item runs in, if known.
+    pub scopes: Option<Scopes>,
+    /// Whether the item has a `trigger` that is checked before it runs.
+    pub has_trigger: bool,
+}
+
+/// The delay before a fired event or on-action happens.
+#[derive(Clone, Debug)]
+pub struct Delay {
+    /// The delay as written in the script, such as `3 days`.
+    pub description: String,
+    /// Whether the delay can be zero, for example because it's a range starting at 0.
+    /// Delays given as script values are assumed not to be zero.
+    pub may_be_zero: bool,
+}
+
+/// One node of the [`EventGraph`] firing another.
+#[derive(Clone, Debug)]
+pub struct GraphEdge {
+    /// The index of the firing node in [`EventGraph::nodes`].
+    pub from: usize,
+    /// The index of the fired node in [`EventGraph::nodes`].
+    pub to: usize,
+    pub kind: EdgeKind,
+    /// The delay, or `None` if the target is fired right away.
+    pub delay: Option<Delay>,
+    /// Whether the firing depends on a condition, such as being inside an `if` or a block with a
+    /// `limit`, or being picked at random.
+    pub conditional: bool,
+    /// Where the firing is written.
+    pub loc: Loc,
+}
+
+/// The graph of which events, on-actions and scripted effects fire which others.
+///
+/// Scripted effects are only included if they lead to firing an event or on-action.
+#[derive(Clone, Debug, Default)]
+pub struct EventGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl EventGraph {
+    /// Return whether node `idx` is defined in the mod being validated, as opposed to the base
+    /// game or other loaded mods.
+    pub fn is_mod_node(&self, idx: usize) -> bool {
+        self.nodes[idx].definition.is_some_and(|loc| loc.kind == FileKind::Mod)
+    }
+
+    /// Return the indexes of the edges that belong to the mod: the ones written in the mod, or
+    /// firing something that is defined in the mod.
+    fn mod_edges(&self) -> impl Iterator<Item = usize> {
+        (0..self.edges.len()).filter(|&i| {
+            let edge = &self.edges[i];
+            edge.loc.kind == FileKind::Mod || self.is_mod_node(edge.to)
+        })
+    }
+
+    /// Return the indexes of the nodes that belong to the mod: the ones defined in the mod, and
+    /// the ones connected to them by an edge that belongs to the mod.
+    fn mod_nodes(&self) -> Vec<usize> {
+        let mut included = vec![false; self.nodes.len()];
+        for (i, flag) in included.iter_mut().enumerate() {
+            *flag = self.is_mod_node(i);
+        }
+        for i in self.mod_edges() {
+            included[self.edges[i].from] = true;
+            included[self.edges[i].to] = true;
+        }
+        (0..self.nodes.len()).filter(|&i| included[i]).collect()
+    }
+
+    fn node_id(&self, idx: usize) -> String {
+        let node = &self.nodes[idx];
+        format!("{}:{}", node.kind.as_str(), node.key)
+    }
+
+    /// Write the part of the graph that belongs to the mod in DOT format.
+    ///
+    /// Nodes that are not defined in the mod are drawn dashed.
+    ///
+    /// # Errors
+    /// Returns an error if writing to `output` fails.
+    pub fn write_dot<O: Write>(&self, output: &mut O) -> Result<()> {
+        writeln!(output, "digraph events {{")?;
+        writeln!(output, "    rankdir=LR;")?;
+        for idx in self.mod_nodes() {
+            let node = &self.nodes[idx];
+            let label = match node.scopes {
+                Some(scopes) => format!("{}\n{scopes}", node.key),
+                None => node.key.to_owned(),
+            };
+            let shape = match node.kind {
+                NodeKind::Event => "box",
+                NodeKind::OnAction => "ellipse",
+                NodeKind::ScriptedEffect => "note",
+            };
+            let style = if self.is_mod_node(idx) { "solid" } else { "dashed" };
+            writeln!(
+                output,
+                "    {} [label={} shape={shape} style={style}];",
+                dot_string(&self.node_id(idx)),
+                dot_string(&label)
+            )?;
+        }
+        for i in self.mod_edges() {
+            let edge = &self.edges[i];
+            let label = match &edge.delay {
+                Some(delay) => format!("{}\n{}", edge.kind.as_str(), delay.description),
+                None => edge.kind.as_str().to_owned(),
+            };
+            writeln!(
+                output,
+                "    {} -> {} [label={}];",
+                dot_string(&self.node_id(edge.from)),
+                dot_string(&self.node_id(edge.to)),
+                dot_string(&label)
+            )?;
+        }
+        writeln!(output, "}}")?;
+        Ok(())
+    }
+
+    /// Write the part of the graph that belongs to the mod in JSON format.
+    ///
+    /// # Errors
+    /// Returns an error if writing to `output` fails.
+    ///
+    /// # Panics
+    /// May panic when the report mutex has been poisoned by another thread.
+    pub fn write_json<O: Write>(&self, output: &mut O) -> Result<()> {
+        let errors = Errors::get();
+        let nodes: Vec<Value> = self
+            .mod_nodes()
+            .into_iter()
+            .map(|idx| {
+                let node = &self.nodes[idx];
+                json!({
+                    "id": self.node_id(idx),
+                    "kind": node.kind.as_str(),
+                    "key": node.key,
+                    "scopes": node.scopes.map(|scopes| scopes.to_string()),
+                    "definition": node.definition.map(|loc| location_json(&errors, loc)),
+                })
+            })
+            .collect();
+        let edges: Vec<Value> = self
+            .mod_edges()
+            .map(|i| {
+                let edge = &self.edges[i];
+                json!({
+                    "from": self.node_id(edge.from),
+                    "to": self.node_id(edge.to),
+                    "kind": edge.kind.as_str(),
+                    "delay": edge.delay.as_ref().map(|delay| &delay.description),
+                    "conditional": edge.conditional,
+                    "location": location_json(&errors, edge.loc),
+                })
+            })
+            .collect();
+        let document = json!({
+            "schema_version": EVENT_GRAPH_SCHEMA_VERSION,
+            "tiger_version": env!("CARGO_PKG_VERSION"),
+            "game": game_id(),
+            "nodes": nodes,
+            "edges": edges,
+        });
+        drop(errors);
+        serde_json::to_writer_pretty(&mut *output, &document)?;
+        writeln!(output)?;
+        Ok(())
+    }
+
+    /// Report loops of events and on-actions that fire each other right away, without any
+    /// condition that could end the loop. These freeze or crash the game.
+    pub(crate) fn check_loops(&self) {
+        let mut successors = vec![Vec::new(); self.nodes.len()];
+        for (i, edge) in self.edges.iter().enumerate() {
+            if !edge.conditional
+                && edge.delay.as_ref().is_none_or(|delay| delay.may_be_zero)
+                && !self.nodes[edge.from].has_trigger
+                && !self.nodes[edge.to].has_trigger
+            {
+                successors[edge.from].push(i);
+            }
+        }
+
+        // Look for a path from each node back to itself. Nodes that are part of a reported loop
+        // are skipped, so that each loop is reported once.
+        let mut in_loop = vec![false; self.nodes.len()];
+        let mut starts: Vec<usize> = (0..self.nodes.len()).collect();
+        starts.sort_unstable_by_key(|&idx| self.nodes[idx].definition);
+        for start in starts {
+            if in_loop[start] || successors[start].is_empty() {
+                continue;
+            }
+            let Some(cycle) = find_cycle(self, &successors, start) else { continue };
+            for &i in &cycle {
+                in_loop[self.edges[i].to] = true;
+            }
+
+            let mut keys = vec![self.nodes[start].key];
+            keys.extend(cycle.iter().map(|&i| self.nodes[self.edges[i].to].key));
+            let msg = format!("`{}` fire each other in a loop without delay", keys.join("` -> `"));
+            let info = "this will freeze or crash the game; add a delay, a trigger, or a condition that ends the loop";
+            let node = &self.nodes[start];
+            let mut builder = err(ErrorKey::Loop)
+                .msg(msg)
+                .info(info)
+                .loc(node.definition.unwrap_or(self.edges[cycle[0]].loc));
+            for &i in &cycle {
+                let edge = &self.edges[i];
+                builder = builder.loc_msg(edge.loc, format!("fires `{}`", self.nodes[edge.to].key));
+            }
+            builder.push();
+        }
+    }
+}
+
+/// Quote a string for use as an id or label in a DOT file.
+fn dot_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// Collects the nodes and edges while walking the script.
+struct Builder<'a> {
+    data: &'a Everything,
+    graph: EventGraph,
+    index: TigerHashMap<(NodeKind, &'static str), usize>,
+    /// Whether the block being walked only runs under some condition.
+    conditional: bool,
+}
+
+impl Builder<'_> {
+    fn node(&mut self, kind: NodeKind, key: &Token) -> usize {
+        if let Some(&idx) = self.index.get(&(kind, key.as_str())) {
+            return idx;
+        }
+        let idx = self.graph.nodes.len();
+        self.graph.nodes.push(GraphNode {
+            kind,
+            key: key.as_str(),
+            definition: None,
+            scopes: None,
+            has_trigger: false,
+        });
+        self.index.insert((kind, key.as_str()), idx);
+        idx
+    }
+
+    fn define(
+        &mut self,
+        kind: NodeKind,
+        key: &Token,
+        block: &Block,
+        scopes: Option<Scopes>,
+    ) -> usize {
+        let idx = self.node(kind, key);
+        let node = &mut self.graph.nodes[idx];
+        node.definition.get_or_insert(key.loc);
+        node.scopes = node.scopes.or(scopes);
+        node.has_trigger |= kind != NodeKind::ScriptedEffect && block.has_key("trigger");
+        idx
+    }
+
+    fn edge(
+        &mut self,
+        from: usize,
+        kind: NodeKind,
+        to: &Token,
+        edge_kind: EdgeKind,
+        delay: Option<Delay>,
+    ) {
+        let to_idx = self.node(kind, to);
+        self.graph.edges.push(GraphEdge {
+            from,
+            to: to_idx,
+            kind: edge_kind,
+            delay,
+            conditional: self.conditional
+                || matches!(
+                    edge_kind,
+                    EdgeKind::Fallback | EdgeKind::RandomEvents | EdgeKind::RandomOnAction
+                ),
+            loc: to.loc,
+        });
+    }
+
+    /// Find the events, on-actions and scripted effects fired from an event's effect blocks.
+    ///
+    /// The options are conditional, because which one runs is up to the player or the AI.
+    fn walk_event(&mut self, from: usize, block: &Block) {
+        for Field(key, _, bv) in block.iter_fields() {
+            let BV::Block(block) = bv else { continue };
+            let conditional = if key.is("immediate") || key.is("after") {
+                false
+            } else if key.is("option") || key.is("on_trigger_fail") {
+                true
+            } else {
+                continue;
+            };
+            let outer = self.conditional;
+            self.conditional |= conditional;
+            self.walk_effect(from, block);
+            self.conditional = outer;
+        }
+    }
+
+    /// Find the events, on-actions and scripted effects fired from an effect block.
+    fn walk_effect(&mut self, from: usize, block: &Block) {
+        for item in block.iter_items() {
+            match item {
+                BlockItem::Field(Field(key, _, bv)) => {
+                    if key.lowercase_is("trigger_event") {
+                        self.trigger_event(from, bv);
+                    } else if self.data.effects.peek(key.as_str()).is_some() {
+                        self.edge(
+                            from,
+                            NodeKind::ScriptedEffect,
+                            key,
+                            EdgeKind::ScriptedEffect,
+                            None,
+                        );
+                    } else if let BV::Block(block) = bv
+                        && !TRIGGER_FIELDS.contains(&key.as_str())
+                    {
+                        let outer = self.conditional;
+                        self.conditional |= is_conditional_effect(key) || block.has_key("limit");
+                        self.walk_effect(from, block);
+                        self.conditional = outer;
+                    }
+                }
+                BlockItem::Block(block) => self.walk_effect(from, block),
+                BlockItem::Value(_) => (),
+            }
+        }
+    }
+
+    fn trigger_event(&mut self, from: usize, bv: &BV) {
+        match bv {
+            BV::Value(token) => {
+                self.edge(from, NodeKind::Event, token, EdgeKind::TriggerEvent, None);
+            }
+            BV::Block(block) => {
+                let delay = delay(block);
+                if let Some(token) = block.get_field_value("id") {
+                    self.edge(from, NodeKind::Event, token, EdgeKind::TriggerEvent, delay.clone());
+                }
+                if let Some(token) = block.get_field_value("on_action") {
+                    self.edge(from, NodeKind::OnAction, token, EdgeKind::TriggerEvent, delay);
+                }
+            }
+        }
+    }
+
+    /// Find the events, on-actions and scripted effects fired from an on-action.
+    fn walk_on_action(&mut self, from: usize, block: &Block) {
+        for Field(key, _, bv) in block.iter_fields() {
+            let Some(kind) = on_action_edge_kind(key) else {
+                if key.is("effect")
+                    && let BV::Block(block) = bv
+                {
+                    self.walk_effect(from, block);
+                }
+                continue;
+            };
+            let target_kind = match kind {
+                EdgeKind::Events | EdgeKind::RandomEvents | EdgeKind::FirstValid => NodeKind::Event,
+                _ => NodeKind::OnAction,
+            };
+            match bv {
+                BV::Value(token) => self.edge(from, target_kind, token, kind, None),
+                BV::Block(block) => {
+                    let delay = block.get_field_block("delay").and_then(delay);
+                    for item in block.iter_items() {
+                        let token = match item {
+                            BlockItem::Value(token) => token,
+                            // The weighted lists of random_events and random_on_action
+                            BlockItem::Field(Field(weight, _, BV::Value(token)))
+                                if weight.is_number() && !weight.is("0") =>
+                            {
+                                token
+                            }
+                            _ => continue,
+                        };
+                        self.edge(from, target_kind, token, kind, delay.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remove the scripted effects that don't lead to firing anything, and the edges to them.
+    fn prune(&mut self) {
+        let graph = &mut self.graph;
+        let mut removed = vec![false; graph.nodes.len()];
+        loop {
+            let mut has_out = vec![false; graph.nodes.len()];
+            for edge in &graph.edges {
+                if !removed[edge.to] {
+                    has_out[edge.from] = true;
+                }
+            }
+            let mut changed = false;
+            for (idx, node) in graph.nodes.iter().enumerate() {
+                if node.kind == NodeKind::ScriptedEffect && !removed[idx] && !has_out[idx] {
+                    removed[idx] = true;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let mut new_index = vec![usize::MAX; graph.nodes.len()];
+        let mut nodes = Vec::new();
+        for (idx, node) in graph.nodes.drain(..).enumerate() {
+            if !removed[idx] {
+                new_index[idx] = nodes.len();
+                nodes.push(node);
+            }
+        }
+        graph.nodes = nodes;
+        graph.edges.retain(|edge| !removed[edge.from] && !removed[edge.to]);
+        for edge in &mut graph.edges {
+            edge.from = new_index[edge.from];
+            edge.to = new_index[edge.to];
+        }
+    }
+}
+
+/// The effects that run their contents only under some condition, or only some of the time.
+/// Any effect with a `limit` is conditional too, and so is any `random_` effect, such as
+/// `random_list` or the `random_` iterators, which may find nothing to pick.
+const CONDITIONAL_EFFECTS: &[&str] = &["if", "else_if", "else", "while", "switch", "duel"];
+
+fn is_conditional_effect(key: &Token) -> bool {
+    CONDITIONAL_EFFECTS.contains(&key.as_str()) || key.as_str().starts_with("random")
+}
+
+/// The fields inside effect blocks that hold triggers or weights rather than effects.
+const TRIGGER_FIELDS: &[&str] =
+    &["limit", "trigger", "show_as_unavailable", "is_shown", "is_valid", "ai_chance", "modifier"];
+
+fn on_action_edge_kind(key: &Token) -> Option<EdgeKind> {
+    match key.as_str() {
+        "events" => Some(EdgeKind::Events),
+        "random_events" => Some(EdgeKind::RandomEvents),
+        "first_valid" => Some(EdgeKind::FirstValid),
+        "on_actions" => Some(EdgeKind::OnActions),
+        "random_on_action" => Some(EdgeKind::RandomOnAction),
+        "first_valid_on_action" => Some(EdgeKind::FirstValidOnAction),
+        "fallback" => Some(EdgeKind::Fallback),
+        _ => None,
+    }
+}
+
+/// Find a loop from `start` back to itself, following only the edges listed in `successors`.
+/// Return the indexes of the edges that make up the loop, in order.
+fn find_cycle(graph: &EventGraph, successors: &[Vec<usize>], start: usize) -> Option<Vec<usize>> {
+    // Breadth-first search, so that the shortest loop is found.
+    let mut via: Vec<Option<usize>> = vec![None; graph.nodes.len()];
+    let mut queue = VecDeque::from([start]);
+    while let Some(idx) = queue.pop_front() {
+        for &i in &successors[idx] {
+            let next = graph.edges[i].to;
+            if next == start {
+                let mut cycle = vec![i];
+                let mut at = idx;
+                while at != start {
+                    let edge = via[at]?;
+                    cycle.push(edge);
+                    at = graph.edges[edge].from;
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if via[next].is_none() {
+                via[next] = Some(i);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Describe the delay given by the duration fields in `block`, if there are any.
+fn delay(block: &Block) -> Option<Delay> {
+    let mut parts = Vec::new();
+    let mut may_be_zero = true;
+    for unit in ["days", "weeks", "months", "years"] {
+        match block.get_field(unit) {
+            Some(BV::Value(token)) => {
+                may_be_zero &= token.get_number() == Some(0.0);
+                parts.push(format!("{token} {unit}"));
+            }
+            Some(BV::Block(range)) => {
+                let values: Vec<_> = range.iter_values().collect();
+                if let [min, max] = values[..] {
+                    may_be_zero &= min.get_number().is_some_and(|n| n <= 0.0);
+                    parts.push(format!("{min}-{max} {unit}"));
+                } else {
+                    may_be_zero = false;
+                    parts.push(format!("{unit} from script"));
+                }
+            }
+            None => (),
+        }
+    }
+    if block.get_field_bool("delayed") == Some(true) {
+        // The event happens on a later tick, which is enough to keep the game responsive.
+        may_be_zero = false;
+        parts.push("delayed".to_owned());
+    }
+    if let Some(date) = block.get_field_value("trigger_on_next_date") {
+        may_be_zero = false;
+        parts.push(format!("on {date}"));
+    }
+    (!parts.is_empty()).then(|| Delay { description: parts.join(", "), may_be_zero })
+}
+
+/// The events and on-actions that the config file says are fired in ways that tiger can't see,
+/// such as from the console.
+#[derive(Debug, Default)]
+pub(crate) struct EntryPoints {
+    /// Event ids and whole event namespaces.
+    events: TigerHashSet<&'static str>,
+    on_actions: TigerHashSet<&'static str>,
+}
+
+impl EntryPoints {
+    pub(crate) fn from_config(config: &Block) -> Self {
+        let mut entry_points = Self::default();
+        if let Some(block) = config.get_field_block("entry_points") {
+            for events in block.get_field_blocks("events") {
+                entry_points.events.extend(events.iter_values().map(Token::as_str));
+            }
+            for on_actions in block.get_field_blocks("on_actions") {
+                entry_points.on_actions.extend(on_actions.iter_values().map(Token::as_str));
+            }
+        }
+        entry_points
+    }
+
+    pub(crate) fn has_event(&self, key: &str) -> bool {
+        self.events.contains(key)
+            || key.split_once('.').is_some_and(|(namespace, _)| self.events.contains(namespace))
+    }
+
+    pub(crate) fn has_on_action(&self, key: &str) -> bool {
+        self.on_actions.contains(key)
+    }
+}
+
+impl Everything {
+    /// Build the graph of which events, on-actions and scripted effects fire which others.
+    ///
+    /// The scope types of scripted effects are only known after [`Everything::validate_all`].
+    ///
+    /// # Panics
+    /// May panic when a mutex has been poisoned by another thread.
+    pub fn event_graph(&self) -> EventGraph {
+        if let Some(graph) = &*self.validated_event_graph.lock().unwrap() {
+            return graph.clone();
+        }
+        self.session.enter(|| self.build_event_graph())
+    }
+
+    pub(crate) fn build_event_graph(&self) -> EventGraph {
+        let mut builder = Builder {
+            data: self,
+            graph: EventGraph::default(),
+            index: TigerHashMap::default(),
+            conditional: false,
+        };
+
+        let mut events = Vec::new();
+        for key in self.events.iter_keys() {
+            if let Some(event) = self.events.get_event(key.as_str()) {
+                let scopes = Some(event.expects_scope());
+                let idx = builder.define(NodeKind::Event, key, &event.block, scopes);
+                events.push((idx, &event.block));
+            }
+        }
+        let mut on_actions = Vec::new();
+        for (key, block) in self.on_actions.iter_actions() {
+            let scopes = on_action_scopecontext(key, self).map(|sc| sc.scopes(self));
+            let idx = builder.define(NodeKind::OnAction, key, block, scopes);
+            on_actions.push((idx, block));
+        }
+        let mut effects = Vec::new();
+        for key in self.effects.iter_keys() {
+            if let Some(effect) = self.effects.peek(key.as_str()) {
+                let scopes = self.scripted_effect_signature(key.as_str()).map(|sig| sig.scopes);
+                let idx = builder.define(NodeKind::ScriptedEffect, key, &effect.block, scopes);
+                effects.push((idx, &effect.block));
+            }
+        }
+
+        for (idx, block) in events {
+            builder.walk_event(idx, block);
+        }
+        for (idx, block) in on_actions {
+            builder.walk_on_action(idx, block);
+        }
+        for (idx, block) in effects {
+            builder.walk_effect(idx, block);
+        }
+        builder.prune();
+        builder.graph
+    }
+
+    /// Warn about the mod's events that are fired, but only from events, on-actions or scripted
+    /// effects that can't be reached themselves.
+    ///
+    /// Events that are never fired at all are reported by `Events::check_unused` instead.
+    pub(crate) fn check_unreachable_events(&self, graph: &EventGraph, entry_points: &EntryPoints) {
+        let index: TigerHashMap<(NodeKind, &str), usize> = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(idx, node)| ((node.kind, node.key), idx))
+            .collect();
+        let mut successors = vec![Vec::new(); graph.nodes.len()];
+        for edge in &graph.edges {
+            successors[edge.from].push(edge.to);
+        }
+
+        // The starting points are the items that are fired from outside the graph, such as from
+        // decisions or interactions or by the game engine itself.
+        let mut reached = vec![false; graph.nodes.len()];
+        for (idx, node) in graph.nodes.iter().enumerate() {
+            reached[idx] = !graph.is_mod_node(idx)
+                || match node.kind {
+                    NodeKind::Event => entry_points.has_event(node.key),
+                    // Only the on-actions that the game itself fires have known scopes.
+                    NodeKind::OnAction => {
+                        node.scopes.is_some() || entry_points.has_on_action(node.key)
+                    }
+                    NodeKind::ScriptedEffect => false,
+                };
+        }
+        let edge_locs: TigerHashSet<Loc> = graph.edges.iter().map(|edge| edge.loc).collect();
+        for (itype, kind) in [
+            (Item::Event, NodeKind::Event),
+            (Item::OnAction, NodeKind::OnAction),
+            (Item::ScriptedEffect, NodeKind::ScriptedEffect),
+        ] {
+            for (key, loc) in self.xref.references_to(itype) {
+                if !edge_locs.contains(&loc)
+                    && let Some(&idx) = index.get(&(kind, key.as_ref()))
+                {
+                    reached[idx] = true;
+                }
+            }
+        }
+
+        let mut queue: Vec<usize> = (0..graph.nodes.len()).filter(|&idx| reached[idx]).collect();
+        while let Some(idx) = queue.pop() {
+            for &next in &successors[idx] {
+                if !reached[next] {
+                    reached[next] = true;
+                    queue.push(next);
+                }
+            }
+        }
+
+        let mut unreachable = Vec::new();
+        for (idx, node) in graph.nodes.iter().enumerate() {
+            if node.kind == NodeKind::Event
+                && !reached[idx]
+                && self.events.get_event(node.key).is_some_and(Event::is_used)
+            {
+                unreachable.push(idx);
+            }
+        }
+        unreachable.sort_unstable_by_key(|&idx| graph.nodes[idx].definition);
+        for idx in unreachable {
+            let node = &graph.nodes[idx];
+            let Some(definition) = node.definition else { continue };
+            let msg = format!("event `{}` is fired, but it can't be reached", node.key);
+            let info = "it is only fired from events, on-actions or scripted effects that are never fired themselves";
+            let mut builder = warn(ErrorKey::UnreachableEvent).msg(msg).info(info).loc(definition);
+            for edge in graph.edges.iter().filter(|edge| edge.to == idx) {
+                builder = builder.loc_msg(edge.loc, "fired here");
+            }
+            builder.push();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::fileset::FileStage;
+    use crate::parse::pdxfile::parse_pdx_internal;
+    use crate::report::take_reports;
+    use crate::session::Session;
+
+    fn test_loc(line: u32) -> Loc {
+        let path = PathBuf::from("events/test.txt");
+        let mut loc = Loc::for_file(path.clone(), FileStage::NoStage, FileKind::Mod, path);
+        loc.line = line;
+        loc
+    }
+
+    fn event(key: &'static str, definition: Option<Loc>) -> GraphNode {
+        GraphNode { kind: NodeKind::Event, key, definition, scopes: None, has_trigger: false }
+    }
+
+    fn edge(from: usize, to: usize, delay: Option<&str>, conditional: bool) -> GraphEdge {
+        GraphEdge {
+            from,
+            to,
+            kind: EdgeKind::TriggerEvent,
+            delay: delay.map(|description| Delay {
+                description: description.to_owned(),
+                may_be_zero: false,
+            }),
+            conditional,
+            loc: test_loc(10 + u32::try_from(from).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_delay() {
+        let describe = |input: &'static str| {
+            delay(&parse_pdx_internal(input, "delay")).map(|d| (d.description, d.may_be_zero))
+        };
+        assert_eq!(describe("id = a.1"), None);
+        assert_eq!(describe("days = 3"), Some(("3 days".to_owned(), false)));
+        assert_eq!(describe("days = 0"), Some(("0 days".to_owned(), true)));
+        assert_eq!(describe("days = { 0 5 }"), Some(("0-5 days".to_owned(), true)));
+        assert_eq!(describe("months = { 1 2 }"), Some(("1-2 months".to_owned(), false)));
+        assert_eq!(describe("days = { value = 3 }"), Some(("days from script".to_owned(), false)));
+        assert_eq!(describe("delayed = yes"), Some(("delayed".to_owned(), false)));
+    }
+
+    #[test]
+    fn test_check_loops() {
+        let reports = Session::new().enter(|| {
+            let graph = EventGraph {
+                nodes: vec![
+                    event("a.1", Some(test_loc(1))),
+                    event("a.2", Some(test_loc(2))),
+                    event("a.3", Some(test_loc(3))),
+                    event("a.4", Some(test_loc(4))),
+                    event("a.5", Some(test_loc(5))),
+                ],
+                edges: vec![
+                    edge(0, 1, None, false),
+                    edge(1, 0, None, false),
+                    // A delay ends the loop.
+                    edge(2, 3, Some("1 days"), false),
+                    edge(3, 2, None, false),
+                    // So does a condition.
+                    edge(4, 4, None, true),
+                ],
+            };
+            graph.check_loops();
+            take_reports()
+        });
+        assert_eq!(reports.len(), 1);
+        let (report, occurrences) = reports.into_iter().next().unwrap();
+        assert_eq!(report.msg, "`a.1` -> `a.2` -> `a.1` fire each other in a loop without delay");
+        let pointers = occurrences.into_iter().next().unwrap();
+        let lines: Vec<u32> = pointers.iter().map(|p| p.loc.line).collect();
+        assert_eq!(lines, [1, 10, 11]);
+    }
+
+    #[test]
+    fn test_write_dot() {
+        let dot = Session::new().enter(|| {
+            let graph = EventGraph {
+                nodes: vec![
+                    event("a.1", Some(test_loc(1))),
+                    event("a.2", None),
+                    event("vanilla.1", None),
+                ],
+                edges: vec![edge(0, 1, Some("3 days"), false)],
+            };
+            let mut output = Vec::new();
+            graph.write_dot(&mut output).unwrap();
+            String::from_utf8(output).unwrap()
+        });
+        assert!(dot.starts_with("digraph events {\n"));
+        assert!(dot.contains("    \"event:a.1\" [label=\"a.1\" shape=box style=solid];\n"));
+        assert!(dot.contains("    \"event:a.2\" [label=\"a.2\" shape=box style=dashed];\n"));
+        assert!(!dot.contains("vanilla.1"));
+        assert!(
+            dot.contains(
+                "    \"event:a.1\" -> \"event:a.2\" [label=\"trigger_event\\n3 days\"];\n"
+            )
+        );
+    }
+}
@@ -5,7 +5,11 @@
 
 use std::borrow::Cow;
 use std::fmt::Debug;
+use std::mem::take;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+#[cfg(feature = "jomini")]
+use std::sync::OnceLock;
 #[cfg(any(feature = "ck3", feature = "vic3"))]
 use std::sync::RwLock;
 
@@ -38,6 +42,8 @@ use crate::config_load::{check_for_legacy_ignore, load_filter};
 use crate::context::ScopeContext;
 #[cfg(any(feature = "ck3", feature = "vic3"))]
 use crate::data::data_binding::DataBindings;
+#[cfg(feature = "jomini")]
+use crate::data::fonts::FontCoverage;
 use crate::data::{
     assets::Assets,
     defines::Defines,
@@ -54,14 +60,17 @@ use crate::data::{
 };
 use crate::db::{Db, DbKind};
 use crate::dds::DdsFiles;
+use crate::declared_signature::forget_signature_comments;
 #[cfg(feature = "eu5")]
 use crate::eu5::data::provinces::Eu5Provinces;
 #[cfg(feature = "eu5")]
 use crate::eu5::tables::misc::*;
-use crate::fileset::{FileEntry, FileKind, FileStage, Fileset};
+#[cfg(feature = "jomini")]
+use crate::event_graph::{EntryPoints, EventGraph};
+use crate::explain::Explain;
+use crate::fileset::{FileEntry, FileHandler, FileKind, FileStage, Fileset, LoadedKeys};
 use crate::game::Game;
-#[cfg(any(feature = "ck3", feature = "vic3"))]
-use crate::helpers::TigerHashSet;
+use crate::helpers::{TigerHashMap, TigerHashSet};
 #[cfg(feature = "hoi4")]
 use crate::hoi4::data::{
     events::Hoi4Events, gfx::Gfx, music::Hoi4Musics, provinces::Hoi4Provinces,
@@ -73,8 +82,8 @@ use crate::imperator::data::{decisions::Decisions, provinces::ImperatorProvinces
 #[cfg(feature = "imperator")]
 use crate::imperator::tables::misc::*;
 use crate::item::{Item, ItemExt, ItemLoader};
+use crate::loca_snapshot::LocaSnapshot;
 use crate::lowercase::Lowercase;
-use crate::macros::MACRO_MAP;
 use crate::parse::ParserMemory;
 #[cfg(feature = "vic3")]
 use crate::parse::json::parse_json_file;
@@ -83,10 +92,15 @@ use crate::pdxfile::PdxFile;
 use crate::report::err;
 #[cfg(feature = "jomini")]
 use crate::report::warn;
-use crate::report::{ErrorKey, OutputStyle, Severity, report, set_output_style};
+use crate::report::{
+    ErrorKey, LogReportMetadata, LogReportPointers, OutputStyle, Severity, copy_reports,
+    forget_files, report, restore_reports, set_output_style, take_reports,
+};
 use crate::rivers::Rivers;
 #[cfg(feature = "jomini")]
 use crate::scopes::Scopes;
+use crate::script_docs::ScriptDocs;
+use crate::session::{Session, SessionState};
 use crate::token::{Loc, Token};
 #[cfg(feature = "jomini")]
 use crate::variable_scopes::VariableScopes;
@@ -98,6 +112,7 @@ use crate::vic3::data::{
 };
 #[cfg(feature = "vic3")]
 use crate::vic3::tables::misc::*;
+use crate::xref::Xref;
 
 #[derive(Debug, Error)]
 #[allow(clippy::enum_variant_names)]
@@ -108,6 +123,8 @@ pub enum FilesError {
     ModUnreadable { path: PathBuf, source: walkdir::Error },
     #[error("Could not read config file at {path}")]
     ConfigUnreadable { path: PathBuf },
+    #[error("Could not find any script_docs logs in {path}")]
+    ScriptDocsMissing { path: PathBuf },
 }
 
 /// A record of everything known about the game and mod being validated.
@@ -121,8 +138,14 @@ pub enum FilesError {
 /// * During validation, `Everything` is immutable and cross-checking between item types can be done safely.
 #[derive(Debug)]
 pub struct Everything {
+    /// The state of the session that this validation belongs to. Reports go there.
+    pub(crate) session: &'static SessionState,
+    /// Keeps the session's state from being used by another session while this exists. It's
+    /// `None` in the default session.
+    _session_handle: Option<Session>,
+
     /// Config from file
-    config: Block,
+    pub(crate) config: Block,
 
     /// The global parser state, carrying information between files.
     /// Currently only used by the pdxfile parser, to handle the `reader_export` directory,
@@ -134,6 +157,30 @@ pub struct Everything {
     #[cfg(any(feature = "ck3", feature = "vic3"))]
     warned_defines: RwLock<TigerHashSet<String>>,
 
+    /// The reports that were stored while loading the files. They are kept so that the ones for
+    /// unchanged files can be restored after [`Everything::reload_files`].
+    load_reports: TigerHashMap<LogReportMetadata, TigerHashSet<LogReportPointers>>,
+
+    /// Whether [`Everything::enable_reload`] was called.
+    reload_enabled: bool,
+
+    /// Whether [`Everything::enable_check_unused`] was called.
+    check_unused_enabled: bool,
+
+    /// The keys that each file handler loaded from its files, keyed by the handler's field name.
+    /// They are kept so that [`Everything::reload_files`] knows which other files to parse again
+    /// together with the changed ones.
+    loaded_keys: Mutex<TigerHashMap<&'static str, LoadedKeys>>,
+
+    /// The reports that were stored after the last validation, if reloading is enabled. They are
+    /// kept so that the ones about files that aren't validated again can be restored after
+    /// [`Everything::reload_files`].
+    validation_reports: Mutex<TigerHashMap<LogReportMetadata, TigerHashSet<LogReportPointers>>>,
+
+    /// The files that [`Everything::validate_all`] has to validate, or `None` if it has to
+    /// validate all of them.
+    revalidate: Option<TigerHashSet<PathBuf>>,
+
     /// Tracks all the files (vanilla and mods) that are relevant to the current validation.
     pub(crate) fileset: Fileset,
 
@@ -230,6 +277,26 @@ pub struct Everything {
 
     pub(crate) variables: Variables,
 
+    /// The triggers, effects and so on from the game's `script_docs` logs, if they were loaded.
+    pub(crate) script_docs: ScriptDocs,
+
+    /// The references found during validation, if they are being recorded.
+    pub(crate) xref: Xref,
+
+    /// The calls to the item named by `--explain`, if any.
+    pub(crate) explain: Explain,
+
+    /// The event graph built by the last validation, shared by the checks that need it.
+    #[cfg(feature = "jomini")]
+    pub(crate) validated_event_graph: Mutex<Option<EventGraph>>,
+
+    /// The glyphs of the fonts, read by the first validation that needed them.
+    #[cfg(feature = "jomini")]
+    pub(crate) font_coverage: OnceLock<FontCoverage>,
+
+    /// The localization snapshot to compare the translations with, if one was loaded.
+    pub(crate) loca_snapshot: Option<LocaSnapshot>,
+
     #[cfg(feature = "jomini")]
     pub(crate) global_scopes: VariableScopes,
     #[cfg(feature = "jomini")]
@@ -240,101 +307,166 @@ pub struct Everything {
     pub(crate) variable_list_scopes: VariableScopes,
 }
 
-macro_rules! load_all_generic {
-    ($s: ident, $t: ident) => {
-        $s.spawn(|_| $t.fileset.handle(&mut $t.dds, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.defines, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.triggers, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.effects, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.assets, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.gui, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.on_actions, &$t.parser));
+/// The item types that are validated again at each place they are used. The files that use them
+/// have to be validated again whenever the files that define them are.
+const VALIDATED_WHERE_USED: &[Item] = &[
+    Item::ScriptedTrigger,
+    Item::ScriptedEffect,
+    #[cfg(feature = "jomini")]
+    Item::ScriptValue,
+];
+
+/// Keep only the occurrences of `reports` that don't point into a file for which `keep` returns
+/// false.
+fn retain_reports<F>(
+    reports: &mut TigerHashMap<LogReportMetadata, TigerHashSet<LogReportPointers>>,
+    keep: F,
+) where
+    F: Fn(&Path) -> bool,
+{
+    for occurrences in reports.values_mut() {
+        occurrences.retain(|pointers| pointers.iter().all(|pointer| keep(pointer.loc.pathname())));
+    }
+    reports.retain(|_, occurrences| !occurrences.is_empty());
+}
+
+/// Invoke the macro `$m` for each of the special file handlers that all games have.
+macro_rules! handlers_generic {
+    ($m: ident, $t: ident $(, $arg: ident)*) => {
+        $m!($t, dds $(, $arg)*);
+        $m!($t, defines $(, $arg)*);
+        $m!($t, triggers $(, $arg)*);
+        $m!($t, effects $(, $arg)*);
+        $m!($t, assets $(, $arg)*);
+        $m!($t, gui $(, $arg)*);
+        $m!($t, on_actions $(, $arg)*);
     };
 }
 
+/// Invoke the macro `$m` for each of the special file handlers that only CK3 has.
 #[cfg(feature = "ck3")]
-macro_rules! load_all_ck3 {
-    ($s: ident, $t: ident) => {
-        $s.spawn(|_| $t.fileset.handle(&mut $t.events, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.interaction_cats, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.province_histories, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.province_properties, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.province_terrains, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.gameconcepts, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.titles, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.characters, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.traits, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.title_history, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.menatarmstypes, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.music, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.data_bindings, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.provinces_ck3, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.scripted_lists, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.wars, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.coas, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.scripted_modifiers, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.script_values, &$t.parser));
-        $s.spawn(|_| crate::ck3::data::buildings::Building::finalize(&mut $t.database));
+macro_rules! handlers_ck3 {
+    ($m: ident, $t: ident $(, $arg: ident)*) => {
+        $m!($t, events $(, $arg)*);
+        $m!($t, interaction_cats $(, $arg)*);
+        $m!($t, province_histories $(, $arg)*);
+        $m!($t, province_properties $(, $arg)*);
+        $m!($t, province_terrains $(, $arg)*);
+        $m!($t, gameconcepts $(, $arg)*);
+        $m!($t, titles $(, $arg)*);
+        $m!($t, characters $(, $arg)*);
+        $m!($t, traits $(, $arg)*);
+        $m!($t, title_history $(, $arg)*);
+        $m!($t, menatarmstypes $(, $arg)*);
+        $m!($t, music $(, $arg)*);
+        $m!($t, data_bindings $(, $arg)*);
+        $m!($t, provinces_ck3 $(, $arg)*);
+        $m!($t, scripted_lists $(, $arg)*);
+        $m!($t, wars $(, $arg)*);
+        $m!($t, coas $(, $arg)*);
+        $m!($t, scripted_modifiers $(, $arg)*);
+        $m!($t, script_values $(, $arg)*);
     };
 }
 
+/// Invoke the macro `$m` for each of the special file handlers that only Vic3 has.
 #[cfg(feature = "vic3")]
-macro_rules! load_all_vic3 {
-    ($s: ident, $t: ident) => {
-        $s.spawn(|_| $t.fileset.handle(&mut $t.events, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.history, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.provinces_vic3, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.data_bindings, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.coas, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.scripted_lists, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.scripted_modifiers, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.script_values, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.music, &$t.parser));
-        $s.spawn(|_| {
-            Everything::load_json(
-                &$t.fileset,
-                &mut $t.database,
-                Item::TerrainMask,
-                TerrainMask::add_json,
-            );
-        });
+macro_rules! handlers_vic3 {
+    ($m: ident, $t: ident $(, $arg: ident)*) => {
+        $m!($t, events $(, $arg)*);
+        $m!($t, history $(, $arg)*);
+        $m!($t, provinces_vic3 $(, $arg)*);
+        $m!($t, data_bindings $(, $arg)*);
+        $m!($t, coas $(, $arg)*);
+        $m!($t, scripted_lists $(, $arg)*);
+        $m!($t, scripted_modifiers $(, $arg)*);
+        $m!($t, script_values $(, $arg)*);
+        $m!($t, music $(, $arg)*);
     };
 }
 
+/// Invoke the macro `$m` for each of the special file handlers that only Imperator has.
 #[cfg(feature = "imperator")]
-macro_rules! load_all_imperator {
-    ($s: ident, $t: ident) => {
-        $s.spawn(|_| $t.fileset.handle(&mut $t.events, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.decisions_imperator, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.provinces_imperator, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.coas, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.scripted_lists, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.scripted_modifiers, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.script_values, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.music, &$t.parser));
+macro_rules! handlers_imperator {
+    ($m: ident, $t: ident $(, $arg: ident)*) => {
+        $m!($t, events $(, $arg)*);
+        $m!($t, decisions_imperator $(, $arg)*);
+        $m!($t, provinces_imperator $(, $arg)*);
+        $m!($t, coas $(, $arg)*);
+        $m!($t, scripted_lists $(, $arg)*);
+        $m!($t, scripted_modifiers $(, $arg)*);
+        $m!($t, script_values $(, $arg)*);
+        $m!($t, music $(, $arg)*);
     };
 }
 
+/// Invoke the macro `$m` for each of the special file handlers that only EU5 has.
 #[cfg(feature = "eu5")]
-macro_rules! load_all_eu5 {
-    ($s: ident, $t: ident) => {
-        $s.spawn(|_| $t.fileset.handle(&mut $t.events, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.coas, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.provinces_eu5, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.scripted_lists, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.scripted_modifiers, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.script_values, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.music, &$t.parser));
+macro_rules! handlers_eu5 {
+    ($m: ident, $t: ident $(, $arg: ident)*) => {
+        $m!($t, events $(, $arg)*);
+        $m!($t, coas $(, $arg)*);
+        $m!($t, provinces_eu5 $(, $arg)*);
+        $m!($t, scripted_lists $(, $arg)*);
+        $m!($t, scripted_modifiers $(, $arg)*);
+        $m!($t, script_values $(, $arg)*);
+        $m!($t, music $(, $arg)*);
     };
 }
 
+/// Invoke the macro `$m` for each of the special file handlers that only Hoi4 has.
 #[cfg(feature = "hoi4")]
-macro_rules! load_all_hoi4 {
-    ($s: ident, $t: ident) => {
-        $s.spawn(|_| $t.fileset.handle(&mut $t.events_hoi4, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.gfx, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.provinces_hoi4, &$t.parser));
-        $s.spawn(|_| $t.fileset.handle(&mut $t.music_hoi4, &$t.parser));
+macro_rules! handlers_hoi4 {
+    ($m: ident, $t: ident $(, $arg: ident)*) => {
+        $m!($t, events_hoi4 $(, $arg)*);
+        $m!($t, gfx $(, $arg)*);
+        $m!($t, provinces_hoi4 $(, $arg)*);
+        $m!($t, music_hoi4 $(, $arg)*);
+    };
+}
+
+macro_rules! load_handler {
+    ($t: ident, $field: ident, $s: ident) => {
+        $s.spawn(|_| {
+            if $t.reload_enabled {
+                let keys = $t.fileset.handle_keep(&mut $t.$field, &$t.parser);
+                $t.loaded_keys.lock().unwrap().insert(stringify!($field), keys);
+            } else {
+                $t.fileset.handle(&mut $t.$field, &$t.parser);
+            }
+        });
+    };
+}
+
+/// Update the handler `$field` if any of its files are in `$reload`, and add the files that were
+/// parsed again to `$reparsed`. If the handler can't forget files, or its keys weren't kept, it is
+/// filled again from all its files and its whole `subpath` is added instead.
+macro_rules! reload_handler {
+    ($t: ident, $field: ident, $reload: ident, $reparsed: ident) => {
+        let subpath = $t.$field.subpath();
+        if $reload.iter().any(|path| path.starts_with(&subpath)) {
+            let loaded_keys = $t.loaded_keys.get_mut().unwrap();
+            let again = loaded_keys.get_mut(stringify!($field)).and_then(|keys| {
+                $t.fileset.handle_again(&mut $t.$field, &$t.parser, keys, &$reload)
+            });
+            if let Some(paths) = again {
+                $reparsed.extend(paths);
+            } else {
+                $t.$field = Default::default();
+                let keys = $t.fileset.handle_keep(&mut $t.$field, &$t.parser);
+                loaded_keys.insert(stringify!($field), keys);
+                $reparsed.push(subpath);
+            }
+        }
+    };
+}
+
+/// Validate the handler `$field`, unless none of its files have to be validated again.
+macro_rules! validate_handler {
+    ($t: ident, $field: ident, $s: ident) => {
+        if $t.should_validate_under(&$t.$field.subpath()) {
+            $s.spawn(|_| $t.$field.validate($t));
+        }
     };
 }
 
@@ -410,6 +542,36 @@ macro_rules! scan_all_hoi4 {
     };
 }
 
+/// Fill the registry of variable names from all the loaded items.
+macro_rules! scan_all {
+    ($s: ident) => {
+        scan_all_generic!($s);
+        match Game::game() {
+            #[cfg(feature = "ck3")]
+            Game::Ck3 => {
+                scan_all_ck3!($s);
+            }
+            #[cfg(feature = "vic3")]
+            Game::Vic3 => {
+                scan_all_vic3!($s);
+            }
+            #[cfg(feature = "imperator")]
+            Game::Imperator => {
+                scan_all_imperator!($s);
+            }
+            #[cfg(feature = "eu5")]
+            Game::Eu5 => {
+                scan_all_eu5!($s);
+            }
+            #[cfg(feature = "hoi4")]
+            Game::Hoi4 => {
+                scan_all_hoi4!($s);
+            }
+        }
+        $s.database.scan_variables(&mut $s.variables);
+    };
+}
+
 impl Everything {
     /// Create a new `Everything` instance, ready for validating a mod.
     ///
@@ -428,6 +590,7 @@ impl Everything {
         mod_root: &Path,
         replace_paths: Vec<PathBuf>,
     ) -> Result<Self> {
+        let session = SessionState::current();
         let mut fileset = Fileset::new(vanilla_dir, mod_root.to_path_buf(), replace_paths);
 
         let config_file_name = match Game::game() {
@@ -516,7 +679,15 @@ impl Everything {
         }
 
         Ok(Everything {
+            session,
+            _session_handle: session.session(),
             parser: ParserMemory::default(),
+            load_reports: TigerHashMap::default(),
+            reload_enabled: false,
+            check_unused_enabled: false,
+            loaded_keys: Mutex::default(),
+            validation_reports: Mutex::default(),
+            revalidate: None,
             fileset,
             dds: DdsFiles::default(),
             config,
@@ -595,6 +766,14 @@ impl Everything {
             variable_scopes,
             #[cfg(feature = "jomini")]
             variable_list_scopes,
+            script_docs: ScriptDocs::default(),
+            xref: Xref::default(),
+            explain: Explain::default(),
+            #[cfg(feature = "jomini")]
+            validated_event_graph: Mutex::new(None),
+            #[cfg(feature = "jomini")]
+            font_coverage: OnceLock::new(),
+            loca_snapshot: None,
         })
     }
 
@@ -604,9 +783,16 @@ impl Everything {
         PdxFile::read_optional_bom(&entry, &ParserMemory::default())
     }
 
+    /// The root directory of the mod being validated.
+    pub fn mod_root(&self) -> &Path {
+        self.fileset.mod_root()
+    }
+
     pub fn load_config_filtering_rules(&self) {
-        check_for_legacy_ignore(&self.config);
-        load_filter(&self.config);
+        self.session.enter(|| {
+            check_for_legacy_ignore(&self.config);
+            load_filter(&self.config);
+        });
     }
 
     /// Load the `OutputStyle` settings from the config.
@@ -634,16 +820,19 @@ impl Everything {
     }
 
     pub fn load_output_settings(&self, default_colors: bool) {
-        set_output_style(self.load_output_styles(default_colors));
+        self.session.enter(|| {
+            set_output_style(self.load_output_styles(default_colors));
+        });
     }
 
     #[cfg(feature = "vic3")]
-    fn load_json<F>(fileset: &Fileset, db: &mut Db, itype: Item, add_json: F)
+    fn load_json<F, P>(fileset: &Fileset, db: &mut Db, itype: Item, add_json: F, filter: P)
     where
         F: Fn(&mut Db, Block) + Sync + Send,
+        P: Fn(&FileEntry) -> bool + Sync + Send,
     {
         for block in fileset.filter_map_under(&PathBuf::from(itype.path()), |entry| {
-            if entry.filename().to_string_lossy().ends_with(".json") {
+            if filter(entry) && entry.filename().to_string_lossy().ends_with(".json") {
                 parse_json_file(entry)
             } else {
                 None
@@ -664,12 +853,21 @@ impl Everything {
     }
 
     fn load_pdx_files(&mut self, loader: &ItemLoader) {
+        self.load_pdx_files_filtered(loader, |_| true);
+    }
+
+    /// Like [`Everything::load_pdx_files`], but only for the files that `filter` accepts.
+    fn load_pdx_files_filtered<F>(&mut self, loader: &ItemLoader, filter: F)
+    where
+        F: Fn(&FileEntry) -> bool + Sync + Send,
+    {
         let path = PathBuf::from(loader.itype().path());
         let recursive = loader.recursive();
         let expect_count = path.components().count() + 1;
         for mut block in self.fileset.filter_map_under(&path, |entry| {
             // It's <= expect_count because some loader paths are files not directories
-            if (recursive || entry.path().components().count() <= expect_count)
+            if filter(entry)
+                && (recursive || entry.path().components().count() <= expect_count)
                 && entry.filename().to_string_lossy().ends_with(loader.extension())
             {
                 PdxFile::read_encoded(entry, loader.encoding(), &self.parser)
@@ -699,100 +897,409 @@ impl Everything {
         }
     }
 
+    /// Read the logs that the game writes with its `script_docs` console command, from `dir`.
+    ///
+    /// Triggers, effects, iterators, event targets and modifiers that are in the logs but not in
+    /// tiger's built-in tables are then accepted. This way a mod can be validated against a game
+    /// version that is newer than tiger. The entries whose scopes disagree with the built-in
+    /// tables are reported by [`Everything::validate_all`].
+    ///
+    /// `dir` should contain at least one of `triggers.log`, `effects.log`, `event_targets.log`
+    /// and `modifiers.log`.
+    pub fn load_script_docs(&mut self, dir: &Path) -> Result<()> {
+        let session = self.session;
+        let found = session.enter(|| self.script_docs.load(dir))?;
+        if found == 0 {
+            return Err(FilesError::ScriptDocsMissing { path: dir.to_path_buf() }.into());
+        }
+        Ok(())
+    }
+
     pub fn load_all(&mut self) {
-        #[cfg(feature = "ck3")]
-        self.load_reader_export();
-        self.load_all_normal_pdx_files();
+        let session = self.session;
+        session.enter(|| {
+            #[cfg(feature = "ck3")]
+            self.load_reader_export();
+            self.load_all_normal_pdx_files();
+
+            std::thread::scope(|s| {
+                s.spawn(|| {
+                    session.enter(|| self.fileset.handle(&mut self.localization, &self.parser));
+                });
+
+                scope(|s| {
+                    handlers_generic!(load_handler, self, s);
+                    match Game::game() {
+                        #[cfg(feature = "ck3")]
+                        Game::Ck3 => {
+                            handlers_ck3!(load_handler, self, s);
+                            s.spawn(|_| {
+                                crate::ck3::data::buildings::Building::finalize(&mut self.database);
+                            });
+                        }
+                        #[cfg(feature = "vic3")]
+                        Game::Vic3 => {
+                            handlers_vic3!(load_handler, self, s);
+                            s.spawn(|_| {
+                                Everything::load_json(
+                                    &self.fileset,
+                                    &mut self.database,
+                                    Item::TerrainMask,
+                                    TerrainMask::add_json,
+                                    |_| true,
+                                );
+                            });
+                        }
+                        #[cfg(feature = "imperator")]
+                        Game::Imperator => {
+                            handlers_imperator!(load_handler, self, s);
+                        }
+                        #[cfg(feature = "eu5")]
+                        Game::Eu5 => {
+                            handlers_eu5!(load_handler, self, s);
+                        }
+                        #[cfg(feature = "hoi4")]
+                        Game::Hoi4 => {
+                            handlers_hoi4!(load_handler, self, s);
+                        }
+                    }
+                });
 
-        std::thread::scope(|s| {
-            s.spawn(|| self.fileset.handle(&mut self.localization, &self.parser));
+                self.database.add_subitems();
+                scan_all!(self);
+            });
+            self.load_reports = copy_reports();
+        });
+    }
 
-            scope(|s| {
-                load_all_generic!(s, self);
-                match Game::game() {
-                    #[cfg(feature = "ck3")]
-                    Game::Ck3 => {
-                        load_all_ck3!(s, self);
-                    }
-                    #[cfg(feature = "vic3")]
-                    Game::Vic3 => {
-                        load_all_vic3!(s, self);
-                    }
-                    #[cfg(feature = "imperator")]
-                    Game::Imperator => {
-                        load_all_imperator!(s, self);
-                    }
-                    #[cfg(feature = "eu5")]
-                    Game::Eu5 => {
-                        load_all_eu5!(s, self);
+    /// Keep what is needed to make [`Everything::reload_files`] fast: the keys that the file
+    /// handlers loaded from each file, the references between files, and the reports from the
+    /// last validation.
+    ///
+    /// This must be called before [`Everything::load_all`]. Without it, the first reload of a file
+    /// handler parses all of its files again, and every validation after a reload covers all items.
+    pub fn enable_reload(&mut self) {
+        self.reload_enabled = true;
+        self.xref.enable_dependencies();
+    }
+
+    /// Update the loaded files after some of the mod's files were changed, added, or removed,
+    /// without loading everything again. `changed` are the full filesystem paths of those files.
+    ///
+    /// Only the changed files are parsed again, together with the files that were uncovered or
+    /// hidden by the changes, and for scripted items the other files that define the same keys.
+    /// Their items replace the ones they had before, and items that they no longer override take
+    /// their place again. In Vic3 and EU5, the item types that allow `INJECT:` are the exception:
+    /// all the files of such an item type are parsed again if any of them changed. Parsed files
+    /// are kept in memory for good, so each reload adds only the files it parsed to it.
+    ///
+    /// This must be called after [`Everything::load_all`], and should be followed by
+    /// [`Everything::validate_all`]. If [`Everything::enable_reload`] was called, and
+    /// [`Everything::enable_check_unused`] wasn't, that validation only covers the changed files
+    /// and the files that refer to the items defined in them. The reports about the other files
+    /// are restored from the last validation. This is approximate: a report about an unchanged
+    /// file that is caused by how a changed file uses it may linger until the next full
+    /// validation. Otherwise, the validation covers all items again, and the reports will be the
+    /// same as after a full load.
+    ///
+    /// Reports that are still stored from before are discarded, and the reports from loading the
+    /// unchanged files are stored again.
+    ///
+    /// # Panics
+    /// May panic when a mutex has been poisoned by another thread.
+    pub fn reload_files(&mut self, changed: &[PathBuf]) -> Result<()> {
+        let session = self.session;
+        session.enter(|| {
+            drop(take_reports());
+            let reload = self.fileset.rescan_mod(changed)?;
+            forget_files(&reload, changed);
+            forget_signature_comments(&reload);
+            #[cfg(feature = "jomini")]
+            if self.font_coverage.get().is_some_and(|fonts| fonts.depends_on(&reload)) {
+                self.font_coverage = OnceLock::new();
+            }
+            let partial = self.reload_enabled && !self.check_unused_enabled;
+            let defined_before: TigerHashSet<(Item, &'static str)> = if partial {
+                self.iter_definitions()
+                    .filter(|(_, key)| {
+                        reload
+                            .binary_search_by(|path| path.as_path().cmp(key.loc.pathname()))
+                            .is_ok()
+                    })
+                    .map(|(itype, key)| (itype, key.as_str()))
+                    .collect()
+            } else {
+                TigerHashSet::default()
+            };
+            let mut reparsed = reload.clone();
+
+            #[cfg(feature = "ck3")]
+            if reload.iter().any(|path| path.starts_with("reader_export")) {
+                self.parser = ParserMemory::default();
+                self.load_reader_export();
+            }
+
+            let loaders: Vec<&ItemLoader> = inventory::iter::<ItemLoader>
+                .into_iter()
+                .filter(|loader| loader.for_game(Game::game()))
+                .filter(|loader| reload.iter().any(|path| path.starts_with(loader.itype().path())))
+                .collect();
+            // Injected items can't be taken apart again, so the item types that allow injection
+            // are reloaded whole, together with the other item types stored under their
+            // directories.
+            #[allow(unused_mut)]
+            let mut db_paths: Vec<PathBuf> = Vec::new();
+            #[cfg(any(feature = "vic3", feature = "eu5"))]
+            if Game::is_vic3() || Game::is_eu5() {
+                db_paths.extend(
+                    loaders
+                        .iter()
+                        .filter(|loader| loader.itype().injectable())
+                        .map(|loader| PathBuf::from(loader.itype().path())),
+                );
+                db_paths.sort();
+                db_paths.dedup();
+            }
+            let db_files: Vec<PathBuf> = reload
+                .iter()
+                .filter(|path| !db_paths.iter().any(|p| path.starts_with(p)))
+                .cloned()
+                .collect();
+            #[cfg(feature = "vic3")]
+            let reload_terrain_masks = Game::is_vic3()
+                && db_files.iter().any(|path| path.starts_with(Item::TerrainMask.path()));
+            #[cfg(not(feature = "vic3"))]
+            let reload_terrain_masks = false;
+
+            if !loaders.is_empty() || reload_terrain_masks {
+                if !db_paths.is_empty() {
+                    self.database.remove_under(&db_paths);
+                }
+                self.database.start_reload(&db_files);
+                for loader in inventory::iter::<ItemLoader> {
+                    if !loader.for_game(Game::game()) {
+                        continue;
                     }
-                    #[cfg(feature = "hoi4")]
-                    Game::Hoi4 => {
-                        load_all_hoi4!(s, self);
+                    let itype_path = Path::new(loader.itype().path());
+                    if db_paths.iter().any(|path| itype_path.starts_with(path)) {
+                        self.load_pdx_files(loader);
+                    } else if db_files.iter().any(|path| path.starts_with(itype_path)) {
+                        self.load_pdx_files_filtered(loader, |entry| {
+                            db_files
+                                .binary_search_by(|path| path.as_path().cmp(entry.path()))
+                                .is_ok()
+                        });
                     }
                 }
-            });
+                #[cfg(feature = "vic3")]
+                if reload_terrain_masks {
+                    Everything::load_json(
+                        &self.fileset,
+                        &mut self.database,
+                        Item::TerrainMask,
+                        TerrainMask::add_json,
+                        |entry| {
+                            db_files
+                                .binary_search_by(|path| path.as_path().cmp(entry.path()))
+                                .is_ok()
+                        },
+                    );
+                }
+                self.database.add_subitems_under(&db_paths);
+                self.database.finish_reload(&db_files);
+                #[cfg(feature = "ck3")]
+                if Game::is_ck3() {
+                    crate::ck3::data::buildings::Building::finalize(&mut self.database);
+                }
+                reparsed.extend(db_paths);
+            }
 
-            self.database.add_subitems();
-            scan_all_generic!(self);
+            let subpath = self.localization.subpath();
+            let loca_paths: Vec<PathBuf> =
+                reload.iter().filter(|path| path.starts_with(&subpath)).cloned().collect();
+            if !loca_paths.is_empty() {
+                let entries: Vec<&FileEntry> =
+                    loca_paths.iter().filter_map(|path| self.fileset.get_entry(path)).collect();
+                self.localization.reload_files(&loca_paths, &entries, &self.parser);
+            }
+
+            handlers_generic!(reload_handler, self, reload, reparsed);
             match Game::game() {
                 #[cfg(feature = "ck3")]
                 Game::Ck3 => {
-                    scan_all_ck3!(self);
+                    handlers_ck3!(reload_handler, self, reload, reparsed);
                 }
                 #[cfg(feature = "vic3")]
                 Game::Vic3 => {
-                    scan_all_vic3!(self);
+                    handlers_vic3!(reload_handler, self, reload, reparsed);
                 }
                 #[cfg(feature = "imperator")]
                 Game::Imperator => {
-                    scan_all_imperator!(self);
+                    handlers_imperator!(reload_handler, self, reload, reparsed);
                 }
                 #[cfg(feature = "eu5")]
                 Game::Eu5 => {
-                    scan_all_eu5!(self);
+                    handlers_eu5!(reload_handler, self, reload, reparsed);
                 }
                 #[cfg(feature = "hoi4")]
                 Game::Hoi4 => {
-                    scan_all_hoi4!(self);
+                    handlers_hoi4!(reload_handler, self, reload, reparsed);
                 }
             }
-            self.database.scan_variables(&mut self.variables);
-        });
+
+            self.variables = Variables::new();
+            scan_all!(self);
+
+            // The files that were parsed again have emitted their reports again.
+            let mut load_reports = take(&mut self.load_reports);
+            retain_reports(&mut load_reports, |pathname| {
+                !reparsed.iter().any(|path| pathname.starts_with(path))
+            });
+            restore_reports(load_reports);
+            self.load_reports = copy_reports();
+
+            self.revalidate = None;
+            if partial {
+                let mut files = TigerHashSet::default();
+                for path in &reparsed {
+                    files.insert(path.clone());
+                    files.extend(
+                        self.fileset.get_files_under(path).iter().map(|e| e.path().to_path_buf()),
+                    );
+                }
+                let revalidate = self.files_to_revalidate(files, defined_before);
+                let mut validation_reports = take(self.validation_reports.get_mut().unwrap());
+                retain_reports(&mut validation_reports, |pathname| !revalidate.contains(pathname));
+                restore_reports(validation_reports);
+                self.revalidate = Some(revalidate);
+            }
+            self.clear_caches();
+            Ok(())
+        })
+    }
+
+    /// Return the `files`, together with the files that refer to items defined in them or to the
+    /// items in `defined_before`, and the files with other definitions of those items. Scripted
+    /// triggers, scripted effects and script values are validated again where they are used, so
+    /// the files that refer to the ones defined in those files are added too, and so on.
+    fn files_to_revalidate(
+        &self,
+        mut files: TigerHashSet<PathBuf>,
+        defined_before: TigerHashSet<(Item, &'static str)>,
+    ) -> TigerHashSet<PathBuf> {
+        let definitions: Vec<(Item, &Token)> = self.iter_definitions().collect();
+        let mut keys = defined_before;
+        let mut new_files: Vec<PathBuf> = files.iter().cloned().collect();
+        let mut first = true;
+        while !new_files.is_empty() {
+            let new_files_set: TigerHashSet<&Path> =
+                new_files.iter().map(PathBuf::as_path).collect();
+            for (itype, key) in &definitions {
+                if (first || VALIDATED_WHERE_USED.contains(itype))
+                    && new_files_set.contains(key.loc.pathname())
+                {
+                    keys.insert((*itype, key.as_str()));
+                }
+            }
+            first = false;
+            let mut found = self.xref.files_referring_to(&keys);
+            found.extend(self.database.files_defining(&keys));
+            new_files = found
+                .into_iter()
+                .filter(|path| !files.contains(*path))
+                .map(Path::to_path_buf)
+                .collect();
+            files.extend(new_files.iter().cloned());
+            keys.clear();
+        }
+        files
+    }
+
+    /// Return whether [`Everything::validate_all`] has to validate the item at `loc`.
+    pub(crate) fn should_validate(&self, loc: Loc) -> bool {
+        self.revalidate.as_ref().is_none_or(|files| files.contains(loc.pathname()))
+    }
+
+    /// Return whether [`Everything::validate_all`] has to validate any of the files under
+    /// `subpath`.
+    fn should_validate_under(&self, subpath: &Path) -> bool {
+        self.revalidate
+            .as_ref()
+            .is_none_or(|files| files.iter().any(|path| path.starts_with(subpath)))
+    }
+
+    /// Forget everything that was remembered during the last validation, so that the next one
+    /// starts fresh.
+    ///
+    /// If only some files will be validated again, what was learned from the other files is kept:
+    /// their references, which localization keys they use, and the scope types they imply.
+    fn clear_caches(&mut self) {
+        self.database.clear_caches();
+        self.triggers.clear_caches();
+        self.effects.clear_caches();
+        if let Some(files) = &self.revalidate {
+            self.xref.forget(|pathname| files.contains(pathname));
+            self.explain.forget(|loc| files.contains(loc.pathname()));
+        } else {
+            self.fileset.clear_caches();
+            self.localization.clear_caches();
+            self.xref.clear();
+            self.explain.clear();
+        }
+        #[cfg(any(feature = "ck3", feature = "vic3"))]
+        self.warned_defines.get_mut().unwrap().clear();
+        #[cfg(feature = "jomini")]
+        {
+            self.events.clear_caches();
+            self.scripted_lists.clear_caches();
+            self.scripted_modifiers.clear_caches();
+            self.script_values.clear_caches();
+            if self.revalidate.is_none() {
+                self.global_scopes.clear_deduced();
+                self.global_list_scopes.clear_deduced();
+                self.variable_scopes.clear_deduced();
+                self.variable_list_scopes.clear_deduced();
+            }
+        }
+        #[cfg(feature = "hoi4")]
+        self.events_hoi4.clear_caches();
+        #[cfg(feature = "ck3")]
+        self.characters.clear_caches();
     }
 
     fn validate_all_generic<'a>(&'a self, s: &Scope<'a>) {
         s.spawn(|_| self.fileset.validate(self));
-        s.spawn(|_| self.defines.validate(self));
+        validate_handler!(self, defines, s);
         s.spawn(|_| self.triggers.validate(self));
         s.spawn(|_| self.effects.validate(self));
-        s.spawn(|_| self.assets.validate(self));
-        s.spawn(|_| self.gui.validate(self));
-        s.spawn(|_| self.on_actions.validate(self));
+        validate_handler!(self, assets, s);
+        validate_handler!(self, gui, s);
+        validate_handler!(self, on_actions, s);
         s.spawn(|_| self.dds.validate());
+        s.spawn(|_| self.script_docs.validate(self));
     }
 
     #[cfg(feature = "ck3")]
     fn validate_all_ck3<'a>(&'a self, s: &Scope<'a>) {
         s.spawn(|_| self.events.validate(self));
-        s.spawn(|_| self.interaction_cats.validate(self));
-        s.spawn(|_| self.province_histories.validate(self));
-        s.spawn(|_| self.province_properties.validate(self));
-        s.spawn(|_| self.province_terrains.validate(self));
-        s.spawn(|_| self.gameconcepts.validate(self));
-        s.spawn(|_| self.titles.validate(self));
-        s.spawn(|_| self.characters.validate(self));
-        s.spawn(|_| self.traits.validate(self));
-        s.spawn(|_| self.title_history.validate(self));
-        s.spawn(|_| self.menatarmstypes.validate(self));
-        s.spawn(|_| self.data_bindings.validate(self));
-        s.spawn(|_| self.provinces_ck3.validate(self));
-        s.spawn(|_| self.wars.validate(self));
-        s.spawn(|_| self.coas.validate(self));
-        s.spawn(|_| self.scripted_lists.validate(self));
-        s.spawn(|_| self.scripted_modifiers.validate(self));
+        validate_handler!(self, interaction_cats, s);
+        validate_handler!(self, province_histories, s);
+        validate_handler!(self, province_properties, s);
+        validate_handler!(self, province_terrains, s);
+        validate_handler!(self, gameconcepts, s);
+        validate_handler!(self, titles, s);
+        validate_handler!(self, characters, s);
+        validate_handler!(self, traits, s);
+        validate_handler!(self, title_history, s);
+        validate_handler!(self, menatarmstypes, s);
+        validate_handler!(self, data_bindings, s);
+        validate_handler!(self, provinces_ck3, s);
+        validate_handler!(self, wars, s);
+        validate_handler!(self, coas, s);
+        validate_handler!(self, scripted_lists, s);
+        validate_handler!(self, scripted_modifiers, s);
         s.spawn(|_| self.script_values.validate(self));
-        s.spawn(|_| self.music.validate(self));
+        validate_handler!(self, music, s);
         s.spawn(|_| Climate::validate_all(&self.database, self));
     }
 
@@ -822,14 +1329,14 @@ impl Everything {
             }
         }
         s.spawn(|_| self.events.validate(self));
-        s.spawn(|_| self.history.validate(self));
-        s.spawn(|_| self.provinces_vic3.validate(self));
-        s.spawn(|_| self.data_bindings.validate(self));
-        s.spawn(|_| self.coas.validate(self));
-        s.spawn(|_| self.scripted_lists.validate(self));
-        s.spawn(|_| self.scripted_modifiers.validate(self));
+        validate_handler!(self, history, s);
+        validate_handler!(self, provinces_vic3, s);
+        validate_handler!(self, data_bindings, s);
+        validate_handler!(self, coas, s);
+        validate_handler!(self, scripted_lists, s);
+        validate_handler!(self, scripted_modifiers, s);
         s.spawn(|_| self.script_values.validate(self));
-        s.spawn(|_| self.music.validate(self));
+        validate_handler!(self, music, s);
         s.spawn(|_| StrategicRegion::crosscheck(self));
         s.spawn(|_| BuyPackage::crosscheck(self));
     }
@@ -837,70 +1344,126 @@ impl Everything {
     #[cfg(feature = "imperator")]
     fn validate_all_imperator<'a>(&'a self, s: &Scope<'a>) {
         s.spawn(|_| self.events.validate(self));
-        s.spawn(|_| self.decisions_imperator.validate(self));
-        s.spawn(|_| self.provinces_imperator.validate(self));
-        s.spawn(|_| self.coas.validate(self));
-        s.spawn(|_| self.scripted_lists.validate(self));
-        s.spawn(|_| self.scripted_modifiers.validate(self));
+        validate_handler!(self, decisions_imperator, s);
+        validate_handler!(self, provinces_imperator, s);
+        validate_handler!(self, coas, s);
+        validate_handler!(self, scripted_lists, s);
+        validate_handler!(self, scripted_modifiers, s);
         s.spawn(|_| self.script_values.validate(self));
-        s.spawn(|_| self.music.validate(self));
+        validate_handler!(self, music, s);
     }
 
     #[cfg(feature = "eu5")]
     fn validate_all_eu5<'a>(&'a self, s: &Scope<'a>) {
         s.spawn(|_| self.events.validate(self));
-        s.spawn(|_| self.coas.validate(self));
-        s.spawn(|_| self.provinces_eu5.validate(self));
-        s.spawn(|_| self.scripted_lists.validate(self));
-        s.spawn(|_| self.scripted_modifiers.validate(self));
+        validate_handler!(self, coas, s);
+        validate_handler!(self, provinces_eu5, s);
+        validate_handler!(self, scripted_lists, s);
+        validate_handler!(self, scripted_modifiers, s);
         s.spawn(|_| self.script_values.validate(self));
-        s.spawn(|_| self.music.validate(self));
+        validate_handler!(self, music, s);
     }
 
     #[cfg(feature = "hoi4")]
     fn validate_all_hoi4<'a>(&'a self, s: &Scope<'a>) {
-        s.spawn(|_| self.events_hoi4.validate(self));
-        s.spawn(|_| self.provinces_hoi4.validate(self));
-        s.spawn(|_| self.gfx.validate(self));
-        s.spawn(|_| self.music_hoi4.validate(self));
+        validate_handler!(self, events_hoi4, s);
+        validate_handler!(self, provinces_hoi4, s);
+        validate_handler!(self, gfx, s);
+        validate_handler!(self, music_hoi4, s);
     }
 
+    /// Validate the loaded items. After [`Everything::reload_files`], this may cover only some of
+    /// them; see there.
+    ///
+    /// # Panics
+    /// May panic when a mutex has been poisoned by another thread.
     pub fn validate_all(&self) {
-        scope(|s| {
-            self.validate_all_generic(s);
-            match Game::game() {
-                #[cfg(feature = "ck3")]
-                Game::Ck3 => self.validate_all_ck3(s),
-                #[cfg(feature = "vic3")]
-                Game::Vic3 => self.validate_all_vic3(s),
-                #[cfg(feature = "imperator")]
-                Game::Imperator => self.validate_all_imperator(s),
-                #[cfg(feature = "eu5")]
-                Game::Eu5 => self.validate_all_eu5(s),
-                #[cfg(feature = "hoi4")]
-                Game::Hoi4 => self.validate_all_hoi4(s),
+        self.session.enter(|| {
+            scope(|s| {
+                self.validate_all_generic(s);
+                match Game::game() {
+                    #[cfg(feature = "ck3")]
+                    Game::Ck3 => self.validate_all_ck3(s),
+                    #[cfg(feature = "vic3")]
+                    Game::Vic3 => self.validate_all_vic3(s),
+                    #[cfg(feature = "imperator")]
+                    Game::Imperator => self.validate_all_imperator(s),
+                    #[cfg(feature = "eu5")]
+                    Game::Eu5 => self.validate_all_eu5(s),
+                    #[cfg(feature = "hoi4")]
+                    Game::Hoi4 => self.validate_all_hoi4(s),
+                }
+                s.spawn(|_| self.database.validate(self));
+            });
+            self.localization.validate_pass2(self);
+            #[cfg(feature = "jomini")]
+            if Game::is_jomini() {
+                let graph = self.build_event_graph();
+                graph.check_loops();
+                *self.validated_event_graph.lock().unwrap() = Some(graph);
+            }
+            if self.reload_enabled {
+                *self.validation_reports.lock().unwrap() = copy_reports();
             }
-            s.spawn(|_| self.database.validate(self));
         });
-        self.localization.validate_pass2(self);
     }
 
     pub fn check_rivers(&mut self) {
-        let mut rivers = Rivers::default();
-        self.fileset.handle(&mut rivers, &self.parser);
-        rivers.validate(self);
+        let session = self.session;
+        session.enter(|| {
+            let mut rivers = Rivers::default();
+            self.fileset.handle(&mut rivers, &self.parser);
+            rivers.validate(self);
+        });
     }
 
     #[cfg(feature = "ck3")]
     pub fn check_pod(&mut self) {
-        self.province_histories.check_pod_faiths(self, &self.titles);
-        self.characters.check_pod_flags(self);
-        self.localization.check_pod_loca(self);
+        let session = self.session;
+        session.enter(|| {
+            self.province_histories.check_pod_faiths(self, &self.titles);
+            self.characters.check_pod_flags(self);
+            self.localization.check_pod_loca(self);
+        });
+    }
+
+    /// Prepare for calling [`Everything::check_unused`] after reloads.
+    ///
+    /// This only matters together with [`Everything::enable_reload`]. It makes the validation
+    /// after [`Everything::reload_files`] cover all items again, because `check_unused` needs to
+    /// know what every item uses.
+    pub fn enable_check_unused(&mut self) {
+        self.check_unused_enabled = true;
     }
 
+    /// Warn about the definitions that nothing uses.
+    ///
+    /// # Panics
+    /// May panic when a mutex has been poisoned by another thread.
     pub fn check_unused(&mut self) {
-        self.localization.check_unused(self);
-        self.fileset.check_unused_dds(self);
+        let session = self.session;
+        session.enter(|| {
+            self.localization.check_unused(self);
+            self.fileset.check_unused_dds(self);
+            self.triggers.check_unused();
+            self.effects.check_unused();
+            #[cfg(feature = "jomini")]
+            if Game::is_jomini() {
+                self.script_values.check_unused();
+                self.scripted_modifiers.check_unused();
+                self.scripted_lists.check_unused();
+                let entry_points = EntryPoints::from_config(&self.config);
+                self.events.check_unused(&entry_points);
+                if let Some(graph) = &*self.validated_event_graph.lock().unwrap() {
+                    self.check_unreachable_events(graph, &entry_points);
+                }
+            }
+            #[cfg(feature = "hoi4")]
+            if Game::is_hoi4() {
+                self.events_hoi4.check_unused();
+            }
+            self.database.check_unused();
+        });
     }
 
     #[allow(dead_code)]
@@ -1181,6 +1744,7 @@ impl Everything {
         token: &Token,
         max_sev: Severity,
     ) {
+        self.xref.record(itype, key, token);
         match itype {
             Item::Entry => self.fileset.verify_entry_exists(key, token, max_sev),
             Item::File => self.fileset.verify_exists_implied(key, token, max_sev),
@@ -1327,6 +1891,14 @@ impl Everything {
     }
 
     pub(crate) fn get_trigger(&self, key: &Token) -> Option<&Trigger> {
+        let trigger = self.lookup_trigger(key);
+        if trigger.is_some() {
+            self.xref.record(Item::ScriptedTrigger, key.as_str(), key);
+        }
+        trigger
+    }
+
+    fn lookup_trigger(&self, key: &Token) -> Option<&Trigger> {
         #[cfg(any(feature = "ck3", feature = "eu5"))]
         if Game::is_ck3() || Game::is_eu5() {
             if let Some(trigger) = self.triggers.get(key.as_str()) {
@@ -1341,6 +1913,14 @@ impl Everything {
     }
 
     pub(crate) fn get_effect(&self, key: &Token) -> Option<&Effect> {
+        let effect = self.lookup_effect(key);
+        if effect.is_some() {
+            self.xref.record(Item::ScriptedEffect, key.as_str(), key);
+        }
+        effect
+    }
+
+    fn lookup_effect(&self, key: &Token) -> Option<&Effect> {
         #[cfg(any(feature = "ck3", feature = "eu5"))]
         if Game::is_ck3() || Game::is_eu5() {
             if let Some(effect) = self.effects.get(key.as_str()) {
@@ -1521,6 +2101,13 @@ impl Everything {
         }
     }
 
+    /// Iterate over the keys of all defined items of all item types, together with their types.
+    ///
+    /// The locations of the keys point to where the items were defined.
+    pub fn iter_definitions(&self) -> impl Iterator<Item = (Item, &Token)> {
+        Item::iter().flat_map(move |itype| self.iter_keys(itype).map(move |key| (itype, key)))
+    }
+
     #[cfg(feature = "jomini")]
     fn valid_sound(&self, name: &str) -> bool {
         // TODO: verify that file:/ values work
@@ -1555,6 +2142,8 @@ impl Everything {
     }
 
     pub(crate) fn event_check_scope(&self, id: &Token, sc: &mut ScopeContext) {
+        self.xref.record(Item::Event, id.as_str(), id);
+        self.explain.record_call(id, sc, self);
         if Game::is_hoi4() {
             #[cfg(feature = "hoi4")]
             self.events_hoi4.check_scope(id, sc, self);
@@ -1565,6 +2154,7 @@ impl Everything {
     }
 
     pub(crate) fn event_validate_call(&self, id: &Token, sc: &mut ScopeContext) {
+        self.xref.record(Item::Event, id.as_str(), id);
         if Game::is_hoi4() {
             #[cfg(feature = "hoi4")]
             self.events_hoi4.validate_call(id, self, sc);
@@ -1577,8 +2167,8 @@ impl Everything {
 
 impl Drop for Everything {
     fn drop(&mut self) {
-        // For the sake of the benchmark code, restore MACRO_MAP to a clean slate
-        MACRO_MAP.clear();
+        // For the sake of the benchmark code, restore the macro map to a clean slate
+        self.session.macro_map.clear();
     }
 }
 
@@ -0,0 +1,168 @@
+//! Explanations of the scope types that tiger deduced for a scripted trigger, scripted effect,
+//! event or on-action, for users who want to know why tiger thinks what it does.
+//!
+//! The item to explain is set with [`Everything::set_explain`] before validation, so that the
+//! calls to it and the scope contexts of events and on-actions can be recorded along the way.
+//! [`Everything::write_explanation`] then shows the scope context that validation arrived at,
+//! with the reason for each scope type.
+
+use std::io::Write;
+use std::sync::Mutex;
+
+use anyhow::Result;
+
+use crate::context::ScopeContext;
+use crate::everything::Everything;
+use crate::report::{Errors, kind_tag};
+use crate::scopes::Scopes;
+use crate::token::{Loc, Token};
+
+/// The calls recorded during validation.
+#[derive(Debug, Default)]
+pub(crate) struct Explain {
+    /// The name of the item to explain. Nothing is recorded if this is `None`.
+    key: Option<String>,
+    /// Where the item is called from, with the scope types of `this` at each call.
+    calls: Mutex<Vec<(Loc, Scopes)>>,
+    /// The scope contexts that the events and on-actions with this name were validated with,
+    /// with the kind of item and its key.
+    contexts: Mutex<Vec<(&'static str, Token, ScopeContext)>>,
+}
+
+impl Explain {
+    /// Record that `key` is called with the scope context `sc`, if it's the item being explained.
+    pub(crate) fn record_call(&self, key: &Token, sc: &ScopeContext, data: &Everything) {
+        if self.key.as_deref() == Some(key.as_str()) {
+            self.calls.lock().unwrap().push((key.loc, sc.scopes(data)));
+        }
+    }
+
+    /// Record that the `kind` item `key` was validated with the scope context `sc`, if it's the
+    /// item being explained.
+    pub(crate) fn record_context(&self, kind: &'static str, key: &Token, sc: &ScopeContext) {
+        if self.key.as_deref() == Some(key.as_str()) {
+            self.contexts.lock().unwrap().push((kind, key.clone(), sc.clone()));
+        }
+    }
+
+    /// Forget the recorded calls and scope contexts, so that the next validation starts fresh.
+    pub(crate) fn clear(&mut self) {
+        self.calls.get_mut().unwrap().clear();
+        self.contexts.get_mut().unwrap().clear();
+    }
+
+    /// Forget the recorded calls from the locations for which `f` returns true, and the scope
+    /// contexts of the items defined there, because they will be validated again.
+    pub(crate) fn forget<F: Fn(Loc) -> bool>(&mut self, f: F) {
+        self.calls.get_mut().unwrap().retain(|(loc, _)| !f(*loc));
+        self.contexts.get_mut().unwrap().retain(|(_, key, _)| !f(key.loc));
+    }
+}
+
+/// One of the items that has the name being explained.
+struct Explained {
+    kind: &'static str,
+    key: Token,
+    /// `None` if the item has macro parameters.
+    sc: Option<ScopeContext>,
+}
+
+/// Describe a location the way reports do, with the tag for where the file comes from.
+fn describe_loc(loc: Loc) -> String {
+    let errors = Errors::get();
+    let tag = kind_tag(&errors, loc.kind);
+    format!("[{tag}] {}:{}:{}", loc.pathname().display(), loc.line, loc.column)
+}
+
+impl Everything {
+    /// Start recording the calls to the scripted trigger, scripted effect, event or on-action
+    /// named `key`, for [`Everything::write_explanation`].
+    ///
+    /// This must be called before [`Everything::validate_all`].
+    pub fn set_explain(&mut self, key: &str) {
+        self.explain.key = Some(key.to_owned());
+    }
+
+    /// Write an explanation of the scope types that validation deduced for the scripted trigger,
+    /// scripted effect, event or on-action named `key`: those of `root`, `this`, `prev`, and the
+    /// named scopes, with the reason for each, and the places that call it.
+    ///
+    /// If several kinds of item have this name, all of them are explained.
+    /// Returns false if there is no such item.
+    ///
+    /// # Errors
+    /// Returns an error if writing to `output` fails.
+    ///
+    /// # Panics
+    /// May panic when a mutex has been poisoned by another thread.
+    pub fn write_explanation<O: Write + Send>(&self, key: &str, output: &mut O) -> Result<bool> {
+        self.session.enter(|| {
+            let mut explained = Vec::new();
+            if let Some(item) = self.triggers.peek(key) {
+                let key = item.key.clone();
+                explained.push(Explained {
+                    kind: "scripted trigger",
+                    key,
+                    sc: item.scope_context(),
+                });
+            }
+            if let Some(item) = self.effects.peek(key) {
+                let key = item.key.clone();
+                explained.push(Explained {
+                    kind: "scripted effect",
+                    key,
+                    sc: item.scope_context(),
+                });
+            }
+            let mut contexts = self.explain.contexts.lock().unwrap().clone();
+            contexts.sort_by_key(|(kind, key, _)| (*kind, key.loc));
+            contexts.dedup_by_key(|(kind, key, _)| (*kind, key.loc));
+            for (kind, key, sc) in contexts {
+                explained.push(Explained { kind, key, sc: Some(sc) });
+            }
+            if explained.is_empty() {
+                return Ok(false);
+            }
+
+            let mut calls = self.explain.calls.lock().unwrap().clone();
+            calls.sort_unstable_by_key(|(loc, scopes)| (*loc, scopes.bits()));
+            calls.dedup();
+
+            for item in explained {
+                writeln!(output, "{} `{}` at {}", item.kind, item.key, describe_loc(item.key.loc))?;
+                if let Some(sc) = &item.sc {
+                    for (name, scopes, reason, input) in sc.explain(self) {
+                        let input = if input { " (expected from the caller)" } else { "" };
+                        writeln!(output, "  {name}: {scopes}{input}")?;
+                        let loc = describe_loc(reason.token().loc);
+                        writeln!(output, "    {}, at {loc}", reason.msg())?;
+                    }
+                    let mut origin = sc.origin().into_iter();
+                    if let Some(source) = origin.next() {
+                        writeln!(output, "  scopes initialized at {}", describe_loc(source.loc))?;
+                    }
+                    for token in origin {
+                        writeln!(output, "  triggered from {}", describe_loc(token.loc))?;
+                    }
+                } else {
+                    writeln!(
+                        output,
+                        "  it has macro parameters, so it is validated separately for each call"
+                    )?;
+                }
+
+                // The item's own validation counts as a call from its definition.
+                let mut callers = calls.iter().filter(|(loc, _)| *loc != item.key.loc).peekable();
+                if callers.peek().is_none() {
+                    writeln!(output, "  not called from anywhere")?;
+                } else {
+                    writeln!(output, "  called from:")?;
+                    for (loc, scopes) in callers {
+                        writeln!(output, "    {}, where this is {scopes}", describe_loc(*loc))?;
+                    }
+                }
+            }
+            Ok(true)
+        })
+    }
+}
@@ -2,8 +2,10 @@
 
 use std::borrow::ToOwned;
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use std::ffi::OsStr;
 use std::fmt::{Display, Formatter};
+use std::mem::take;
 use std::path::{Path, PathBuf};
 use std::string::ToString;
 use std::sync::RwLock;
@@ -15,7 +17,7 @@ use walkdir::WalkDir;
 use crate::block::Block;
 use crate::everything::{Everything, FilesError};
 use crate::game::Game;
-use crate::helpers::TigerHashSet;
+use crate::helpers::{TigerHashMap, TigerHashSet};
 use crate::item::{Item, ItemExt};
 #[cfg(any(feature = "vic3", feature = "eu5"))]
 use crate::mod_metadata::ModMetadata;
@@ -24,7 +26,7 @@ use crate::modfile::ModFile;
 use crate::parse::ParserMemory;
 use crate::pathtable::{PathTable, PathTableIndex};
 use crate::report::{
-    ErrorKey, Severity, add_loaded_dlc_root, add_loaded_mod_root, err, fatal, report,
+    ErrorKey, Severity, add_loaded_dlc_root, add_loaded_mod_root, err, fatal, report, set_mod_root,
 };
 use crate::token::Token;
 use crate::util::fix_slashes_for_target_platform;
@@ -205,6 +207,26 @@ pub trait FileHandler<T: Send>: Sync + Send {
     /// This is called after all files have been handled.
     /// The `FileHandler` can generate indexes, perform full-data checks, etc.
     fn finalize(&mut self) {}
+
+    /// The keys of the items that `loaded` will define when it is handled.
+    /// Files that define the same keys are handled again together by [`Fileset::handle_again`].
+    fn defined_keys(&self, _loaded: &T) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Remove everything that was handled from the files for which `forget` returns true, so that
+    /// they can be handled again. Return false if the `FileHandler` can't do that.
+    fn forget_files(&mut self, _forget: &dyn Fn(&Path) -> bool) -> bool {
+        false
+    }
+}
+
+/// The keys that a [`FileHandler`] loaded from each of its files, by path relative to their roots.
+/// They are kept so that [`Fileset::handle_again`] can find the other files that define the same
+/// items as the files that changed.
+#[derive(Debug, Default)]
+pub struct LoadedKeys {
+    files: TigerHashMap<PathBuf, Vec<&'static str>>,
 }
 
 #[derive(Clone, Debug)]
@@ -270,7 +292,8 @@ pub struct Fileset {
     /// The ck3-tiger config.
     config: Option<Block>,
 
-    /// The CK3 and mod files in arbitrary order (will be empty after `finalize`).
+    /// All the CK3 and mod files that were found, including the ones that are overridden by later
+    /// files. They are in arbitrary order until `finalize` sorts them.
     files: Vec<FileEntry>,
 
     /// The CK3 and mod files in the order the game would load them.
@@ -302,6 +325,7 @@ impl Fileset {
         #[cfg(feature = "jomini")]
         let jomini_root = vanilla_dir.map(|dir| dir.join("jomini"));
 
+        set_mod_root(mod_root.clone());
         Fileset {
             vanilla_root,
             #[cfg(feature = "jomini")]
@@ -492,6 +516,11 @@ impl Fileset {
                     .map_err(|e| FilesError::ModUnreadable { path: path.clone(), source: e })?;
             }
         }
+        self.scan_mod_stage(stage)
+    }
+
+    #[allow(clippy::nonminimal_bool)] // The expression as written is clearer
+    fn scan_mod_stage(&mut self, stage: FileStage) -> Result<(), FilesError> {
         let path = stage.with_dir(self.the_mod.root());
         if !(Game::is_eu5() && !path.exists()) {
             self.scan(&path, stage, FileKind::Mod)
@@ -538,31 +567,92 @@ impl Fileset {
     }
 
     pub fn finalize(&mut self) {
+        self.finalize_with(&TigerHashMap::default());
+    }
+
+    /// Build `ordered_files` from `files`. Entries whose full path is in `known` reuse that
+    /// `PathTableIndex` instead of storing their path in the `PathTable` again.
+    fn finalize_with(&mut self, known: &TigerHashMap<PathBuf, PathTableIndex>) {
         // This sorts by pathname but where pathnames are equal it places `Mod` entries after `Vanilla` entries
         // and `LoadedMod` entries between them in order
         self.files.sort();
 
         // When there are identical paths, only keep the last entry of them.
-        for entry in self.files.drain(..) {
+        for entry in &self.files {
             if let Some(prev) = self.ordered_files.last_mut() {
                 if entry.path == prev.path {
-                    *prev = entry;
+                    *prev = entry.clone();
                 } else {
-                    self.ordered_files.push(entry);
+                    self.ordered_files.push(entry.clone());
                 }
             } else {
-                self.ordered_files.push(entry);
+                self.ordered_files.push(entry.clone());
             }
         }
 
         for entry in &mut self.ordered_files {
+            if let Some(idx) = known.get(&entry.fullpath) {
+                entry.idx = Some(*idx);
+            } else {
+                entry.store_in_pathtable();
+            }
             let token = Token::new(&entry.filename().to_string_lossy(), (&*entry).into());
             self.filename_tokens.push(token);
-            entry.store_in_pathtable();
             self.filenames.insert(entry.path.clone());
         }
     }
 
+    /// Scan the mod directory again, to pick up added and removed files.
+    ///
+    /// `changed` is a list of full filesystem paths of files that were changed, added, or removed.
+    /// Returns the paths, relative to their roots, of the files that need to be loaded again.
+    /// This includes files that were not in `changed` but were uncovered or hidden by the changes,
+    /// such as a vanilla file that is no longer overridden by a removed mod file.
+    pub fn rescan_mod(&mut self, changed: &[PathBuf]) -> Result<Vec<PathBuf>, FilesError> {
+        self.files.retain(|entry| entry.kind != FileKind::Mod);
+        if Game::is_eu5() {
+            #[cfg(feature = "eu5")]
+            self.scan_mod_stage(FileStage::LoadingScreen)?;
+            #[cfg(feature = "eu5")]
+            self.scan_mod_stage(FileStage::MainMenu)?;
+            #[cfg(feature = "eu5")]
+            self.scan_mod_stage(FileStage::InGame)?;
+        } else {
+            self.scan_mod_stage(FileStage::NoStage)?;
+        }
+
+        let old_files = take(&mut self.ordered_files);
+        let known: TigerHashMap<PathBuf, PathTableIndex> = old_files
+            .iter()
+            .filter_map(|entry| entry.idx.map(|idx| (entry.fullpath.clone(), idx)))
+            .collect();
+        self.filename_tokens.clear();
+        self.filenames.clear();
+        self.directories.get_mut().unwrap().clear();
+        self.finalize_with(&known);
+
+        let old: TigerHashMap<&Path, &Path> =
+            old_files.iter().map(|entry| (entry.path(), entry.fullpath())).collect();
+        let new: TigerHashMap<&Path, &Path> =
+            self.ordered_files.iter().map(|entry| (entry.path(), entry.fullpath())).collect();
+        let mut reload: Vec<PathBuf> = old
+            .iter()
+            .chain(new.iter())
+            .filter(|(path, fullpath)| {
+                changed.iter().any(|p| p == *fullpath) || old.get(*path) != new.get(*path)
+            })
+            .map(|(path, _)| path.to_path_buf())
+            .collect();
+        reload.sort();
+        reload.dedup();
+        Ok(reload)
+    }
+
+    /// Forget which files have been looked up during validation.
+    pub fn clear_caches(&mut self) {
+        self.used.get_mut().unwrap().clear();
+    }
+
     pub fn get_files_under<'a>(&'a self, subpath: &'a Path) -> &'a [FileEntry] {
         let start = self.ordered_files.partition_point(|entry| entry.path < subpath);
         let end = start
@@ -570,6 +660,16 @@ impl Fileset {
         &self.ordered_files[start..end]
     }
 
+    pub fn mod_root(&self) -> &Path {
+        self.the_mod.root()
+    }
+
+    /// Return the entry for the file at `path`, relative to its root.
+    pub fn get_entry(&self, path: &Path) -> Option<&FileEntry> {
+        let idx = self.ordered_files.binary_search_by_key(&path, |entry| entry.path.as_path());
+        idx.ok().map(|idx| &self.ordered_files[idx])
+    }
+
     pub fn filter_map_under<F, T>(&self, subpath: &Path, f: F) -> Vec<T>
     where
         F: Fn(&FileEntry) -> Option<T> + Sync + Send,
@@ -592,6 +692,93 @@ impl Fileset {
         handler.finalize();
     }
 
+    /// Like [`Fileset::handle`], but also return the keys that were loaded from each file, so
+    /// that they can be given to [`Fileset::handle_again`] later.
+    pub fn handle_keep<T: Send, H: FileHandler<T>>(
+        &self,
+        handler: &mut H,
+        parser: &ParserMemory,
+    ) -> LoadedKeys {
+        if let Some(config) = &self.config {
+            handler.config(config);
+        }
+        let subpath = handler.subpath();
+        let entries = self.filter_map_under(&subpath, |entry| {
+            handler.load_file(entry, parser).map(|loaded| (entry.clone(), loaded))
+        });
+        let mut keys = LoadedKeys::default();
+        for (entry, loaded) in entries {
+            keys.files.insert(entry.path.clone(), handler.defined_keys(&loaded));
+            handler.handle_file(&entry, loaded);
+        }
+        handler.finalize();
+        keys
+    }
+
+    /// Update a `handler` that was filled by [`Fileset::handle_keep`] after the files in `reload`
+    /// changed. Those files are forgotten and handled again, together with the other files that
+    /// define any of the same keys, so that overridden items come back in load order.
+    ///
+    /// Return the paths of the files that were parsed again, or `None` if the handler can't forget
+    /// files and has to be filled from scratch.
+    ///
+    /// `reload` are paths relative to their roots, and must be sorted.
+    pub fn handle_again<T: Send, H: FileHandler<T>>(
+        &self,
+        handler: &mut H,
+        parser: &ParserMemory,
+        loaded: &mut LoadedKeys,
+        reload: &[PathBuf],
+    ) -> Option<Vec<PathBuf>> {
+        let subpath = handler.subpath();
+        let mut affected: BTreeSet<PathBuf> =
+            reload.iter().filter(|path| path.starts_with(&subpath)).cloned().collect();
+        if !handler.forget_files(&|path| affected.contains(path)) {
+            return None;
+        }
+        let mut keys: TigerHashSet<&'static str> =
+            affected.iter().filter_map(|path| loaded.files.get(path)).flatten().copied().collect();
+        let mut to_parse: Vec<PathBuf> = affected.iter().cloned().collect();
+        let mut entries = Vec::new();
+        while !to_parse.is_empty() {
+            let parsed: Vec<_> = to_parse
+                .par_iter()
+                .filter_map(|path| {
+                    let entry = self.get_entry(path)?;
+                    let value = handler.load_file(entry, parser)?;
+                    Some((entry.clone(), handler.defined_keys(&value), value))
+                })
+                .collect();
+            for (_, defined, _) in &parsed {
+                keys.extend(defined);
+            }
+            entries.extend(parsed);
+            // Other files that define the same keys may have been overridden by the changed
+            // files, or may override their new keys, so they are handled again too.
+            to_parse = loaded
+                .files
+                .iter()
+                .filter(|(path, defined)| {
+                    !affected.contains(*path) && defined.iter().any(|key| keys.contains(key))
+                })
+                .map(|(path, _)| path.clone())
+                .collect();
+            let forget: TigerHashSet<&Path> = to_parse.iter().map(PathBuf::as_path).collect();
+            handler.forget_files(&|path| forget.contains(path));
+            affected.extend(to_parse.iter().cloned());
+        }
+        entries.sort_by(|(a, _, _), (b, _, _)| a.path.cmp(&b.path));
+        for path in &affected {
+            loaded.files.remove(path);
+        }
+        for (entry, defined, value) in entries {
+            loaded.files.insert(entry.path.clone(), defined);
+            handler.handle_file(&entry, value);
+        }
+        handler.finalize();
+        Some(affected.into_iter().collect())
+    }
+
     pub fn mark_used(&self, file: &str) {
         let file = file.strip_prefix('/').unwrap_or(file);
         self.used.write().unwrap().insert(file.to_string());
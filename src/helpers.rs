@@ -5,11 +5,13 @@ use bimap::BiHashMap;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
+use crate::block::Block;
+use crate::fileset::FileKind;
 use crate::game::Game;
 use crate::item::Item;
 #[cfg(any(feature = "vic3", feature = "eu5"))]
 use crate::report::err;
-use crate::report::{ErrorKey, tips, warn};
+use crate::report::{Confidence, ErrorKey, Severity, item_span, report, tips, warn};
 #[cfg(feature = "hoi4")]
 use crate::scopes::Scopes;
 use crate::token::Token;
@@ -42,10 +44,21 @@ pub fn dup_error(key: &Token, other: &Token, id: &str) {
         .push();
 }
 
-/// Warns about an exact redefinition of a database item
-pub fn exact_dup_error(key: &Token, other: &Token, id: &str) {
+/// Warns about an exact redefinition of a database item.
+/// The fix removes one of the two, preferring the newer one if it's in the mod. Only items with
+/// a block can be removed this way.
+pub fn exact_dup_error(
+    key: &Token,
+    block: Option<&Block>,
+    other: &Token,
+    other_block: Option<&Block>,
+    id: &str,
+) {
+    let redundant = if key.loc.kind == FileKind::Mod { (key, block) } else { (other, other_block) };
+    let span = redundant.1.and_then(|block| item_span(redundant.0, block));
     warn(ErrorKey::ExactDuplicateItem)
         .msg(format!("{id} is redefined by an identical {id}"))
+        .opt_fix_removal(span)
         .loc(other)
         .loc_msg(key, format!("the other {id} is here"))
         .push();
@@ -117,6 +130,48 @@ pub fn stringify_list(v: &[&str]) -> String {
     format!("{}", Choices::AndChoices(v))
 }
 
+/// Return the Levenshtein distance between two strings, counted in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // Keep only one row of the table.
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// If exactly one of the `candidates` is a likely misspelling of `word`, return it.
+///
+/// Short words get less leeway, so that for example `age` doesn't suggest `war`.
+pub fn did_you_mean<'a, I>(word: &str, candidates: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let max_distance = match word.chars().count() {
+        0..=3 => return None,
+        4..=7 => 1,
+        _ => 2,
+    };
+    let mut found = None;
+    for candidate in candidates {
+        if candidate != word && edit_distance(word, candidate) <= max_distance {
+            if found.is_some_and(|found| found != candidate) {
+                return None;
+            }
+            found = Some(candidate);
+        }
+    }
+    found
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg(feature = "jomini")]
 pub enum TriBool {
@@ -142,6 +197,19 @@ pub const BANNED_NAMES: &[&str] = &[
     "take_hostage", // actually used by vanilla CK3
 ];
 
+/// Report each of the definitions whose keys are given as unused, with one abbreviated report per
+/// key, in the order of their locations.
+pub(crate) fn report_unused<'a, I>(key: ErrorKey, conf: Confidence, msg: &str, keys: I)
+where
+    I: IntoIterator<Item = &'a Token>,
+{
+    let mut keys: Vec<&Token> = keys.into_iter().collect();
+    keys.sort_unstable_by_key(|key| key.loc);
+    for token in keys {
+        report(key, Severity::Untidy).conf(conf).msg(msg).abbreviated(token).push();
+    }
+}
+
 pub(crate) type BiTigerHashMap<L, R> = BiHashMap<L, R, RandomState, RandomState>;
 
 #[derive(Debug, Clone)]
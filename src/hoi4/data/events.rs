@@ -1,18 +1,20 @@
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering::Relaxed;
 
 use crate::block::{Block, BlockItem, Field};
 use crate::context::{Reason, ScopeContext, Signature};
 use crate::everything::Everything;
 use crate::fileset::{FileEntry, FileHandler};
-use crate::helpers::{TigerHashMap, TigerHashSet, dup_error};
+use crate::helpers::{TigerHashMap, TigerHashSet, dup_error, report_unused};
 use crate::hoi4::events::{get_event_scope, validate_event};
 use crate::item::Item;
 use crate::lowercase::Lowercase;
 use crate::parse::ParserMemory;
 use crate::pdxfile::PdxFile;
-use crate::report::{ErrorKey, err, warn};
+use crate::report::{Confidence, ErrorKey, err, warn};
 use crate::scopes::Scopes;
 use crate::token::Token;
 use crate::variables::Variables;
@@ -52,7 +54,7 @@ impl Hoi4Events {
         }
     }
 
-    fn get_event<'a>(&'a self, key: &'a str) -> Option<&'a Event> {
+    pub fn get_event<'a>(&'a self, key: &'a str) -> Option<&'a Event> {
         if let Some((namespace, id)) = key.split_once('.')
             && let Ok(id) = u16::from_str(id)
         {
@@ -61,8 +63,13 @@ impl Hoi4Events {
         None
     }
 
+    /// Like `get_event`, but records that the event is used.
+    fn use_event<'a>(&'a self, key: &'a str) -> Option<&'a Event> {
+        self.get_event(key).inspect(|event| event.used.store(true, Relaxed))
+    }
+
     pub fn check_scope(&self, token: &Token, sc: &mut ScopeContext, data: &Everything) {
-        if let Some(event) = self.get_event(token.as_str()) {
+        if let Some(event) = self.use_event(token.as_str()) {
             sc.expect(event.expects_scope, &Reason::Token(token.clone()), data);
         }
     }
@@ -80,13 +87,7 @@ impl Hoi4Events {
     }
 
     pub fn exists(&self, key: &str) -> bool {
-        if let Some((namespace, id)) = key.split_once('.')
-            && let Ok(id) = u16::from_str(id)
-            && self.events.contains_key(&(namespace, id))
-        {
-            return true;
-        }
-        false
+        self.use_event(key).is_some()
     }
 
     pub fn iter_keys(&self) -> impl Iterator<Item = &Token> {
@@ -99,8 +100,29 @@ impl Hoi4Events {
         }
     }
 
+    /// Forget the cached validation results, so that the next validation starts fresh.
+    pub fn clear_caches(&mut self) {
+        for item in self.events.values_mut() {
+            item.visited.get_mut().unwrap().clear();
+        }
+    }
+
+    /// Report the triggered-only events that nothing fires. Other events fire on their own.
+    pub fn check_unused(&self) {
+        let unused = self.events.values().filter(|item| {
+            !item.used.load(Relaxed) && item.block.field_value_is("is_triggered_only", "yes")
+        });
+        let msg = "Event is never fired";
+        report_unused(
+            ErrorKey::UnusedDefinition,
+            Confidence::Reasonable,
+            msg,
+            unused.map(|i| &i.key),
+        );
+    }
+
     pub fn validate_call(&self, key: &Token, data: &Everything, sc: &mut ScopeContext) {
-        if let Some(event) = self.get_event(key.as_str()) {
+        if let Some(event) = self.use_event(key.as_str()) {
             event.validate_call(data, sc);
         }
     }
@@ -152,13 +174,16 @@ pub struct Event {
     expects_scope: Scopes,
     expects_from_token: Token,
     visited: Mutex<TigerHashSet<Signature>>,
+    /// Whether anything fires this event
+    used: AtomicBool,
 }
 
 impl Event {
     pub fn new(key: Token, block: Block, id: Token) -> Self {
         let (expects_scope, expects_from_token) = get_event_scope(&key, &block);
         let visited = Mutex::new(TigerHashSet::default());
-        Self { key, block, id, expects_scope, expects_from_token, visited }
+        let used = AtomicBool::new(false);
+        Self { key, block, id, expects_scope, expects_from_token, visited, used }
     }
 
     pub fn validate(&self, data: &Everything) {
@@ -27,6 +27,14 @@ static SCOPE_EFFECT_MAP: LazyLock<TigerHashMap<&'static str, (Scopes, Effect)>>
         hash
     });
 
+/// Iterate over the names of all the builtin effects, except the ones that have been removed from the game.
+pub fn iter_effect_names() -> impl std::iter::Iterator<Item = &'static str> {
+    SCOPE_EFFECT
+        .iter()
+        .filter(|(_, _, v)| !matches!(v, Effect::Removed(..)))
+        .map(|(_, name, _)| *name)
+}
+
 // LAST UPDATED HOI4 VERSION 1.16.4
 // See `documentation/effects_documentation.md` from the game files.
 // TODO: activate_advisor and deactivate_advisor can't be checked yet because character idea tokens
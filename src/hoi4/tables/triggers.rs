@@ -37,6 +37,11 @@ static TRIGGER_MAP: LazyLock<TigerHashMap<&'static str, (Scopes, Trigger)>> = La
     hash
 });
 
+/// Iterate over the names of all the builtin triggers.
+pub fn iter_trigger_names() -> impl std::iter::Iterator<Item = &'static str> {
+    TRIGGER.iter().map(|(_, name, _)| *name)
+}
+
 /// LAST UPDATED HOI4 VERSION 1.16.4
 /// See `documentation/triggers_documentation.md` from the game files.
 /// TODO HOI4
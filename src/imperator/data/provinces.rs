@@ -608,17 +608,13 @@ mod tests {
     use super::*;
 
     use std::path::PathBuf;
-    use std::sync::{LazyLock, Mutex};
 
     use crate::fileset::FileKind;
     use crate::report::take_reports;
+    use crate::session::Session;
 
-    static TEST_MUTEX: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
-
-    fn with_test_lock<T>(f: impl FnOnce() -> T) -> T {
-        let _guard = TEST_MUTEX.lock().unwrap();
-        let _ = take_reports();
-        f()
+    fn in_session<T: Send, F: FnOnce() -> T + Send>(f: F) -> T {
+        Session::new().enter(f)
     }
 
     fn loc(line: u32, column: u32) -> Loc {
@@ -676,7 +672,7 @@ mod tests {
 
     #[test]
     fn adjacency_start_out_of_bounds_errors() {
-        with_test_lock(|| {
+        in_session(|| {
             let img = RgbImage::from_pixel(2, 2, Rgb([1, 2, 3]));
             let provinces = base_provinces(img, Rgb([1, 2, 3]), Rgb([9, 9, 9]));
 
@@ -693,7 +689,7 @@ mod tests {
 
     #[test]
     fn adjacency_start_wrong_color_errors() {
-        with_test_lock(|| {
+        in_session(|| {
             let mut img = RgbImage::from_pixel(2, 2, Rgb([0, 0, 0]));
             img.put_pixel(0, 0, Rgb([9, 9, 9]));
             let provinces = base_provinces(img, Rgb([1, 2, 3]), Rgb([7, 8, 9]));
@@ -711,7 +707,7 @@ mod tests {
 
     #[test]
     fn adjacency_stop_wrong_color_errors_when_start_sentinel() {
-        with_test_lock(|| {
+        in_session(|| {
             let mut img = RgbImage::from_pixel(2, 2, Rgb([0, 0, 0]));
             img.put_pixel(1, 1, Rgb([9, 9, 9]));
             let provinces = base_provinces(img, Rgb([1, 2, 3]), Rgb([7, 8, 9]));
@@ -729,7 +725,7 @@ mod tests {
 
     #[test]
     fn adjacency_one_endpoint_sentinel_can_still_pass() {
-        with_test_lock(|| {
+        in_session(|| {
             let mut img = RgbImage::from_pixel(2, 2, Rgb([0, 0, 0]));
             img.put_pixel(1, 0, Rgb([7, 8, 9]));
             let provinces = base_provinces(img, Rgb([1, 2, 3]), Rgb([7, 8, 9]));
@@ -744,7 +740,7 @@ mod tests {
 
     #[test]
     fn adjacency_kind_invalid_errors_even_if_coords_sentinel() {
-        with_test_lock(|| {
+        in_session(|| {
             let img = RgbImage::from_pixel(2, 2, Rgb([1, 2, 3]));
             let provinces = base_provinces(img, Rgb([1, 2, 3]), Rgb([9, 9, 9]));
 
@@ -761,7 +757,7 @@ mod tests {
 
     #[test]
     fn adjacency_kind_sea_and_river_large_are_allowed() {
-        with_test_lock(|| {
+        in_session(|| {
             let mut img = RgbImage::from_pixel(2, 2, Rgb([0, 0, 0]));
             img.put_pixel(0, 0, Rgb([1, 2, 3]));
             img.put_pixel(1, 0, Rgb([7, 8, 9]));
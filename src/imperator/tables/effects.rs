@@ -25,6 +25,14 @@ static SCOPE_EFFECT_MAP: LazyLock<TigerHashMap<&'static str, (Scopes, Effect)>>
         hash
     });
 
+/// Iterate over the names of all the builtin effects, except the ones that have been removed from the game.
+pub fn iter_effect_names() -> impl Iterator<Item = &'static str> {
+    SCOPE_EFFECT
+        .iter()
+        .filter(|(_, _, v)| !matches!(v, Effect::Removed(..)))
+        .map(|(_, name, _)| *name)
+}
+
 // LAST UPDATED VERSION 2.0.4
 // See `effects.log` from the game data dumps
 // Note: There are a lot of effects here that are marked as "Unchecked"
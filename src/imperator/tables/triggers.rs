@@ -56,6 +56,11 @@ static TRIGGER_MAP: LazyLock<TigerHashMap<&'static str, (Scopes, Trigger)>> = La
     hash
 });
 
+/// Iterate over the names of all the builtin triggers.
+pub fn iter_trigger_names() -> impl Iterator<Item = &'static str> {
+    TRIGGER.iter().map(|(_, name, _)| *name)
+}
+
 /// LAST UPDATED IMPERATOR VERSION 2.0.4
 /// See `triggers.log` from the game data dumps
 /// A key ends with '(' if it is the version that takes a parenthesized argument in script.
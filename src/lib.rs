@@ -25,23 +25,38 @@ compile_error!(
     "exactly one of the features \"ck3\", \"vic3\", \"imperator\", \"eu5\", \"hoi4\" must be enabled"
 );
 
+pub use crate::block::{BV, Block, BlockItem, Comparator, Field};
 pub use crate::config_load::validate_config_file;
+pub use crate::context::ScriptSignature;
+pub use crate::data::localization::Language;
+pub use crate::effect::builtin_effect_names;
+#[cfg(feature = "jomini")]
+pub use crate::event_graph::{
+    Delay, EVENT_GRAPH_SCHEMA_VERSION, EdgeKind, EventGraph, GraphEdge, GraphNode, NodeKind,
+};
 pub use crate::everything::Everything;
 pub use crate::fileset::FileKind;
 pub use crate::game::Game;
 pub use crate::helpers::{TigerHashMap, TigerHashSet};
 pub use crate::item::Item;
 pub use crate::launcher_settings::get_version_from_launcher;
+pub use crate::loca_formats::LocaFormat;
 #[cfg(any(feature = "vic3", feature = "eu5"))]
 pub use crate::mod_metadata::ModMetadata;
 #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
 pub use crate::modfile::ModFile;
+pub use crate::query::Definition;
 pub use crate::report::{
-    Confidence, LogReportMetadata, LogReportPointers, PointedMessage, Severity,
-    add_loaded_mod_root, disable_ansi_colors, emit_reports, log, set_output_style,
-    set_show_loaded_mods, set_show_vanilla, suppress_from_json, take_reports,
+    BaselineEntry, ChangedLines, Confidence, Fix, LogReport, LogReportMetadata, LogReportPointers,
+    OutputFormat, PointedMessage, Severity, add_loaded_mod_root, apply_fixes, clear_baseline,
+    count_failing_reports, disable_ansi_colors, emit_given_reports, emit_reports, load_baseline,
+    log, set_changed_lines, set_fail_on, set_output_style, set_show_loaded_mods, set_show_vanilla,
+    stale_baseline_entries, suppress_from_json, take_emitted_reports, take_reports, write_baseline,
 };
+pub use crate::scopes::Scopes;
+pub use crate::session::Session;
 pub use crate::token::{Loc, Token};
+pub use crate::trigger::builtin_trigger_names;
 
 #[cfg(feature = "internal_benches")]
 mod benches;
@@ -57,7 +72,7 @@ mod imperator;
 #[cfg(feature = "vic3")]
 mod vic3;
 
-mod block;
+pub mod block;
 mod config_load;
 mod context;
 mod data;
@@ -66,18 +81,25 @@ mod datatype;
 mod date;
 mod db;
 mod dds;
+mod declared_signature;
 mod defines;
 mod desc;
 mod effect;
 #[cfg(feature = "jomini")]
 mod effect_validation;
+#[cfg(feature = "jomini")]
+mod event_graph;
 mod everything;
+mod explain;
 mod fileset;
 mod game;
 mod gui;
 mod helpers;
 mod item;
 mod launcher_settings;
+mod loca_formats;
+mod loca_snapshot;
+mod loca_stubs;
 mod lowercase;
 mod macros;
 #[cfg(any(feature = "vic3", feature = "eu5"))]
@@ -89,11 +111,14 @@ mod on_action;
 mod parse;
 mod pathtable;
 mod pdxfile;
+mod query;
 mod report;
 mod rivers;
 mod scopes;
+mod script_docs;
 #[cfg(feature = "jomini")]
 mod script_value;
+mod session;
 mod special_tokens;
 mod token;
 mod tooltipped;
@@ -104,3 +129,4 @@ mod validator;
 #[cfg(feature = "jomini")]
 mod variable_scopes;
 mod variables;
+mod xref;
@@ -0,0 +1,138 @@
+//! Comma-separated values, as described in RFC 4180. The header row names the columns `key`, the
+//! base language, the translated language, and `location`.
+
+use anyhow::{Result, bail};
+
+use crate::data::localization::Language;
+use crate::loca_formats::{TranslationFile, TranslationUnit};
+
+pub(super) fn write_csv(base: Language, lang: Language, units: &[TranslationUnit]) -> String {
+    let mut output = String::new();
+    write_record(&mut output, &["key", base.into(), lang.into(), "location"]);
+    for unit in units {
+        write_record(&mut output, &[&unit.key, &unit.source, &unit.target, &unit.location]);
+    }
+    output
+}
+
+fn write_record(output: &mut String, fields: &[&str]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            output.push(',');
+        }
+        if field.contains([',', '"', '\r', '\n']) {
+            output.push('"');
+            output.push_str(&field.replace('"', "\"\""));
+            output.push('"');
+        } else {
+            output.push_str(field);
+        }
+    }
+    output.push_str("\r\n");
+}
+
+pub(super) fn read_csv(contents: &str) -> Result<TranslationFile> {
+    let mut records = parse_records(contents)?.into_iter();
+    let Some(header) = records.next() else {
+        bail!("the file is empty");
+    };
+    if header.len() < 3 || header[0] != "key" {
+        bail!("expected a header row with key, the base language and the translated language");
+    }
+    let lang = Language::try_from(header[2].as_str()).ok();
+    let mut units = Vec::new();
+    for mut record in records {
+        if record.iter().all(String::is_empty) {
+            continue;
+        }
+        record.resize(4, String::new());
+        let mut fields = record.into_iter();
+        units.push(TranslationUnit {
+            key: fields.next().unwrap_or_default(),
+            source: fields.next().unwrap_or_default(),
+            target: fields.next().unwrap_or_default(),
+            location: fields.next().unwrap_or_default(),
+        });
+    }
+    Ok(TranslationFile { lang, units })
+}
+
+/// Split the contents into records of fields. Quoted fields may contain commas, line breaks, and
+/// quotes that are doubled.
+fn parse_records(contents: &str) -> Result<Vec<Vec<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut chars = contents.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            } else {
+                field.push(c);
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        bail!("a quoted field is not closed");
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quoted_fields() {
+        let contents = "key,english,french,location\r\n\
+            a_key,\"Two\r\nlines\",\"Deux\nlignes, \"\"citées\"\"\",x.yml:2\r\n\
+            b_key,\"\"\"Quoted\"\"\",,x.yml:3\n";
+        let file = read_csv(contents).unwrap();
+        assert_eq!(file.lang, Some(Language::French));
+        assert_eq!(file.units.len(), 2);
+        assert_eq!(file.units[0].source, "Two\r\nlines");
+        assert_eq!(file.units[0].target, "Deux\nlignes, \"citées\"");
+        assert_eq!(file.units[0].location, "x.yml:2");
+        assert_eq!(file.units[1].source, "\"Quoted\"");
+        assert_eq!(file.units[1].target, "");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let units = vec![TranslationUnit {
+            key: "a_key".to_owned(),
+            source: "Say \"hi\",\nthen go".to_owned(),
+            target: "Dis \"salut\",\r\npuis va".to_owned(),
+            location: "x.yml:2".to_owned(),
+        }];
+        let file = read_csv(&write_csv(Language::English, Language::French, &units)).unwrap();
+        assert_eq!(file.units, units);
+    }
+
+    #[test]
+    fn test_unclosed_quote() {
+        assert!(read_csv("key,english,french\na_key,\"Open,x\n").is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! Export of the mod's localization to the file formats that translation tools read, and import
+//! of the translations back into `.yml` files.
+//!
+//! An exported file is for one language. It has a unit for each of the mod's localization keys,
+//! with the base language text as the source, the current translation if there is one, and the
+//! place where the key is defined.
+
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use strum_macros::{Display, EnumString};
+
+use crate::data::localization::{Language, merge_translations};
+use crate::everything::Everything;
+
+mod csv;
+mod po;
+mod xliff;
+
+/// The file formats that localization can be exported to and imported from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum LocaFormat {
+    /// Comma-separated values, with columns for the key, the base language text, the translation
+    /// and the location of the key.
+    Csv,
+    /// A gettext PO file.
+    #[default]
+    Po,
+    /// An XLIFF 1.2 document.
+    Xliff,
+}
+
+impl LocaFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            LocaFormat::Csv => "csv",
+            LocaFormat::Po => "po",
+            LocaFormat::Xliff => "xlf",
+        }
+    }
+
+    fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(LocaFormat::Csv),
+            "po" => Some(LocaFormat::Po),
+            "xlf" | "xliff" => Some(LocaFormat::Xliff),
+            _ => None,
+        }
+    }
+}
+
+/// One localization key with its text in the base language and in the language being translated
+/// into.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct TranslationUnit {
+    pub(crate) key: String,
+    pub(crate) source: String,
+    /// The translation, or the empty string if there is none yet.
+    pub(crate) target: String,
+    /// The path and line number of the base language entry.
+    pub(crate) location: String,
+}
+
+/// A translation to write to one of the mod's `.yml` files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ImportedTranslation<'a> {
+    pub(crate) key: &'a str,
+    /// The line of the file that has the key's entry in this language, if there is one.
+    pub(crate) line: Option<u32>,
+    pub(crate) version: u32,
+    pub(crate) text: &'a str,
+}
+
+/// The contents of an imported file.
+#[derive(Debug, Default)]
+struct TranslationFile {
+    /// The language of the translations, if the file says.
+    lang: Option<Language>,
+    units: Vec<TranslationUnit>,
+}
+
+impl Everything {
+    /// Write a file in `dir` for each language that the mod can be translated into, named after
+    /// the language, with all of the mod's localization in the given `format`.
+    ///
+    /// Returns the files written, with the number of keys in each.
+    pub fn export_loca(&self, dir: &Path, format: LocaFormat) -> Result<Vec<(PathBuf, usize)>> {
+        let base = self.localization.base_lang();
+        create_dir_all(dir).with_context(|| format!("could not create {}", dir.display()))?;
+        let mut written = Vec::new();
+        for lang in self.localization.translation_langs() {
+            let units = self.localization.translation_units(lang);
+            if units.is_empty() {
+                continue;
+            }
+            let contents = match format {
+                LocaFormat::Csv => csv::write_csv(base, lang, &units),
+                LocaFormat::Po => po::write_po(base, lang, &units),
+                LocaFormat::Xliff => xliff::write_xliff(base, lang, &units),
+            };
+            let path = dir.join(format!("{lang}.{}", format.extension()));
+            write(&path, contents)
+                .with_context(|| format!("could not write {}", path.display()))?;
+            written.push((path, units.len()));
+        }
+        Ok(written)
+    }
+
+    /// Read translations from a file that was exported with [`Everything::export_loca`] and
+    /// filled in by translators, and write them into the mod's `.yml` files. An entry that a file
+    /// already has is replaced on its own line, and the rest of the file is kept as it is. The
+    /// format is known from the file's extension. The language is taken from the file's contents,
+    /// or else from its name.
+    ///
+    /// Returns the `.yml` files written, with the number of translations in each.
+    pub fn import_loca(&self, path: &Path) -> Result<Vec<(PathBuf, usize)>> {
+        let Some(format) = LocaFormat::from_path(path) else {
+            bail!("{} is not a .csv, .po or .xlf file", path.display());
+        };
+        let contents =
+            read_to_string(path).with_context(|| format!("could not read {}", path.display()))?;
+        let contents = contents.strip_prefix('\u{feff}').unwrap_or(&contents);
+        let file = match format {
+            LocaFormat::Csv => csv::read_csv(contents),
+            LocaFormat::Po => po::read_po(contents),
+            LocaFormat::Xliff => xliff::read_xliff(contents),
+        }
+        .with_context(|| format!("could not parse {}", path.display()))?;
+
+        let lang = file.lang.or_else(|| {
+            let stem = path.file_stem()?.to_str()?;
+            Language::try_from(stem).ok().or_else(|| Language::from_code(stem))
+        });
+        let Some(lang) = lang else {
+            bail!("could not tell which language {} is for", path.display());
+        };
+        if lang == self.localization.base_lang() {
+            bail!("{} is for the base language {lang}", path.display());
+        }
+
+        let mut written = Vec::new();
+        for (pathname, translations) in self.localization.import_translations(lang, &file.units) {
+            let fullpath = self.mod_root().join(pathname);
+            let contents = if fullpath.exists() {
+                let contents = read_to_string(&fullpath)
+                    .with_context(|| format!("could not read {}", fullpath.display()))?;
+                Some(contents)
+            } else {
+                None
+            };
+            let contents = merge_translations(contents.as_deref(), lang, &translations);
+            if let Some(dir) = fullpath.parent() {
+                create_dir_all(dir)
+                    .with_context(|| format!("could not create {}", dir.display()))?;
+            }
+            write(&fullpath, contents)
+                .with_context(|| format!("could not write {}", fullpath.display()))?;
+            written.push((fullpath, translations.len()));
+        }
+        Ok(written)
+    }
+}
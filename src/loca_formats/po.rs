@@ -0,0 +1,205 @@
+//! Gettext PO files. The localization key is the `msgctxt` of each entry, and the location is
+//! a `#:` reference comment. Entries marked as fuzzy are not imported.
+
+use std::fmt::Write as _;
+
+use anyhow::{Result, bail};
+
+use crate::data::localization::Language;
+use crate::loca_formats::{TranslationFile, TranslationUnit};
+
+pub(super) fn write_po(base: Language, lang: Language, units: &[TranslationUnit]) -> String {
+    let mut output = String::new();
+    _ = writeln!(output, "msgid \"\"");
+    _ = writeln!(output, "msgstr \"\"");
+    _ = writeln!(output, "\"Content-Type: text/plain; charset=UTF-8\\n\"");
+    _ = writeln!(output, "\"Language: {}\\n\"", lang.code());
+    _ = writeln!(output, "\"X-Source-Language: {}\\n\"", base.code());
+    _ = writeln!(output, "\"X-Generator: tiger\\n\"");
+    for unit in units {
+        _ = writeln!(output);
+        _ = writeln!(output, "#: {}", unit.location);
+        _ = writeln!(output, "msgctxt \"{}\"", escape(&unit.key));
+        _ = writeln!(output, "msgid \"{}\"", escape(&unit.source));
+        _ = writeln!(output, "msgstr \"{}\"", escape(&unit.target));
+    }
+    output
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some('t') => unescaped.push('\t'),
+                Some('r') => unescaped.push('\r'),
+                Some(c) => unescaped.push(c),
+                None => (),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// The field of a PO entry that a string continues.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Msgctxt,
+    Msgid,
+    Msgstr,
+}
+
+/// A PO entry while it's being read.
+#[derive(Default)]
+struct Entry {
+    msgctxt: Option<String>,
+    msgid: String,
+    msgstr: String,
+    location: String,
+    fuzzy: bool,
+}
+
+pub(super) fn read_po(contents: &str) -> Result<TranslationFile> {
+    let mut entries = Vec::new();
+    let mut entry = Entry::default();
+    let mut field = None;
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        let (keyword, rest) = if let Some(comment) = line.strip_prefix('#') {
+            // A comment after a string starts the next entry.
+            if field.is_some() {
+                entries.push(std::mem::take(&mut entry));
+                field = None;
+            }
+            if let Some(location) = comment.strip_prefix(':') {
+                location.trim().clone_into(&mut entry.location);
+            } else if let Some(flags) = comment.strip_prefix(',') {
+                entry.fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            }
+            continue;
+        } else if line.is_empty() {
+            continue;
+        } else if let Some(rest) = line.strip_prefix("msgctxt ") {
+            (Some(Field::Msgctxt), rest)
+        } else if let Some(rest) = line.strip_prefix("msgid ") {
+            (Some(Field::Msgid), rest)
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            (Some(Field::Msgstr), rest)
+        } else if line.starts_with("msgid_plural") || line.starts_with("msgstr[") {
+            bail!("line {}: plural forms are not supported", i + 1);
+        } else {
+            (None, line)
+        };
+
+        if let Some(keyword) = keyword {
+            // A new msgctxt, or a msgid that doesn't follow a msgctxt, starts the next entry.
+            let starts_entry = keyword == Field::Msgctxt
+                || (keyword == Field::Msgid && field != Some(Field::Msgctxt));
+            if starts_entry && field.is_some() {
+                entries.push(std::mem::take(&mut entry));
+            }
+            field = Some(keyword);
+        }
+        let Some(string) = rest.trim().strip_prefix('"').and_then(|s| s.strip_suffix('"')) else {
+            bail!("line {}: expected a quoted string", i + 1);
+        };
+        let string = unescape(string);
+        match field {
+            Some(Field::Msgctxt) => entry.msgctxt.get_or_insert_default().push_str(&string),
+            Some(Field::Msgid) => entry.msgid.push_str(&string),
+            Some(Field::Msgstr) => entry.msgstr.push_str(&string),
+            None => bail!("line {}: string outside of an entry", i + 1),
+        }
+    }
+    if field.is_some() {
+        entries.push(entry);
+    }
+
+    let mut lang = None;
+    let mut units = Vec::new();
+    for entry in entries {
+        let Some(key) = entry.msgctxt else {
+            if entry.msgid.is_empty() {
+                // This is the header.
+                lang = entry
+                    .msgstr
+                    .lines()
+                    .find_map(|line| Language::from_code(line.strip_prefix("Language:")?.trim()));
+            }
+            continue;
+        };
+        let target = if entry.fuzzy { String::new() } else { entry.msgstr };
+        units.push(TranslationUnit { key, source: entry.msgid, target, location: entry.location });
+    }
+    Ok(TranslationFile { lang, units })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escapes_and_continued_strings() {
+        let contents = "msgid \"\"\n\
+            msgstr \"\"\n\
+            \"Language: fr\\n\"\n\
+            \n\
+            #: x.yml:2\n\
+            msgctxt \"a_key\"\n\
+            msgid \"Say \\\"hi\\\"\\n\"\n\
+            \"then go\"\n\
+            msgstr \"\"\n\
+            \"Dis \\\"salut\\\"\\n\"\n\
+            \"puis va \\\\o/\"\n\
+            \n\
+            #, fuzzy\n\
+            msgctxt \"b_key\"\n\
+            msgid \"Guess\"\n\
+            msgstr \"Devine\"\n";
+        let file = read_po(contents).unwrap();
+        assert_eq!(file.lang, Some(Language::French));
+        assert_eq!(file.units.len(), 2);
+        assert_eq!(file.units[0].key, "a_key");
+        assert_eq!(file.units[0].source, "Say \"hi\"\nthen go");
+        assert_eq!(file.units[0].target, "Dis \"salut\"\npuis va \\o/");
+        assert_eq!(file.units[0].location, "x.yml:2");
+        assert_eq!(file.units[1].target, "");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let units = vec![TranslationUnit {
+            key: "a_key".to_owned(),
+            source: "Say \"hi\",\nthen\tgo \\".to_owned(),
+            target: "Dis \"salut\",\r\npuis va".to_owned(),
+            location: "x.yml:2".to_owned(),
+        }];
+        let file = read_po(&write_po(Language::English, Language::French, &units)).unwrap();
+        assert_eq!(file.units, units);
+    }
+
+    #[test]
+    fn test_unsupported() {
+        assert!(read_po("msgctxt \"a\"\nmsgid \"A\"\nmsgid_plural \"As\"\n").is_err());
+        assert!(read_po("msgctxt \"a\"\nmsgid \"A\nmsgstr \"\"\n").is_err());
+    }
+}
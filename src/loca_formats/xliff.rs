@@ -0,0 +1,221 @@
+//! XLIFF 1.2 documents, with a `trans-unit` for each localization key. The location is a `note`.
+//!
+//! Only the `source`, `target` and `note` elements of each `trans-unit` are read. The text of
+//! inline elements inside them is kept, and their tags are dropped. Entities other than the ones
+//! that XML predefines are rejected, because there's no DTD to look them up in.
+
+use std::fmt::Write as _;
+
+use anyhow::{Context, Result, bail};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+
+use crate::data::localization::Language;
+use crate::loca_formats::{TranslationFile, TranslationUnit};
+use crate::report::xml_escape;
+
+pub(super) fn write_xliff(base: Language, lang: Language, units: &[TranslationUnit]) -> String {
+    let mut output = String::new();
+    _ = writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    _ = writeln!(output, r#"<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">"#);
+    _ = writeln!(
+        output,
+        r#"  <file original="localization" source-language="{}" target-language="{}" datatype="plaintext">"#,
+        base.code().replace('_', "-"),
+        lang.code().replace('_', "-"),
+    );
+    _ = writeln!(output, "    <body>");
+    for unit in units {
+        let key = xml_escape(&unit.key);
+        _ = writeln!(output, r#"      <trans-unit id="{key}" resname="{key}">"#);
+        _ = writeln!(output, "        <source>{}</source>", xml_escape(&unit.source));
+        if !unit.target.is_empty() {
+            _ = writeln!(output, "        <target>{}</target>", xml_escape(&unit.target));
+        }
+        _ = writeln!(output, "        <note>{}</note>", xml_escape(&unit.location));
+        _ = writeln!(output, "      </trans-unit>");
+    }
+    _ = writeln!(output, "    </body>");
+    _ = writeln!(output, "  </file>");
+    _ = writeln!(output, "</xliff>");
+    output
+}
+
+pub(super) fn read_xliff(contents: &str) -> Result<TranslationFile> {
+    let mut reader = Reader::from_str(contents);
+    let mut lang = None;
+    let mut units = Vec::new();
+    // The trans-unit being read, with how deep inside it the reader is.
+    let mut unit: Option<(TranslationUnit, usize)> = None;
+    // The field of the trans-unit whose text is being collected.
+    let mut field: Option<(Field, String)> = None;
+    loop {
+        let event =
+            reader.read_event().with_context(|| format!("at byte {}", reader.error_position()))?;
+        match event {
+            Event::Start(tag) | Event::Empty(tag) if tag.local_name().as_ref() == b"file" => {
+                lang =
+                    attribute(&tag, "target-language")?.and_then(|code| Language::from_code(&code));
+            }
+            Event::Start(tag) if tag.local_name().as_ref() == b"trans-unit" => {
+                if unit.is_some() {
+                    bail!("a trans-unit is inside another trans-unit");
+                }
+                let Some(key) = attribute(&tag, "resname")?.or(attribute(&tag, "id")?) else {
+                    bail!("a trans-unit has no id");
+                };
+                unit = Some((TranslationUnit { key, ..TranslationUnit::default() }, 0));
+            }
+            Event::Start(tag) => {
+                if let Some((_, depth)) = &mut unit {
+                    // Only the direct children of the trans-unit are its fields. The text of
+                    // inline elements inside a field is kept and their tags are dropped.
+                    if *depth == 0 {
+                        field = Field::from_name(tag.local_name().as_ref())
+                            .map(|name| (name, String::new()));
+                    }
+                    *depth += 1;
+                }
+            }
+            Event::Text(text) => {
+                if let Some((_, collected)) = &mut field {
+                    collected.push_str(&text.unescape()?);
+                }
+            }
+            Event::CData(cdata) => {
+                if let Some((_, collected)) = &mut field {
+                    collected.push_str(&cdata.decode()?);
+                }
+            }
+            Event::End(tag) => match &mut unit {
+                Some((_, 0)) => {
+                    if tag.local_name().as_ref() == b"trans-unit"
+                        && let Some((unit, _)) = unit.take()
+                    {
+                        units.push(unit);
+                    }
+                }
+                Some((unit, depth)) => {
+                    *depth -= 1;
+                    if *depth == 0
+                        && let Some((name, text)) = field.take()
+                    {
+                        name.set(unit, text);
+                    }
+                }
+                None => (),
+            },
+            Event::Eof => break,
+            _ => (),
+        }
+    }
+    if unit.is_some() {
+        bail!("a trans-unit is not closed");
+    }
+    Ok(TranslationFile { lang, units })
+}
+
+/// The children of a `trans-unit` that are read.
+#[derive(Clone, Copy)]
+enum Field {
+    Source,
+    Target,
+    Note,
+}
+
+impl Field {
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"source" => Some(Field::Source),
+            b"target" => Some(Field::Target),
+            b"note" => Some(Field::Note),
+            _ => None,
+        }
+    }
+
+    /// Set this field of `unit` to `text`, unless an earlier element already did.
+    fn set(self, unit: &mut TranslationUnit, text: String) {
+        let value = match self {
+            Field::Source => &mut unit.source,
+            Field::Target => &mut unit.target,
+            Field::Note => &mut unit.location,
+        };
+        if value.is_empty() {
+            *value = text;
+        }
+    }
+}
+
+/// Return the unescaped value of the attribute `name` of a start tag.
+fn attribute(tag: &BytesStart, name: &str) -> Result<Option<String>> {
+    let Some(attribute) = tag.try_get_attribute(name)? else {
+        return Ok(None);
+    };
+    Ok(Some(attribute.unescape_value()?.into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(key: &str, source: &str, target: &str, location: &str) -> TranslationUnit {
+        TranslationUnit {
+            key: key.to_owned(),
+            source: source.to_owned(),
+            target: target.to_owned(),
+            location: location.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let units = vec![
+            unit("a_key", "Gold & <glory>", "L'or et \"la gloire\"", "x.yml:2"),
+            unit("b_key", "Untranslated", "", "x.yml:3"),
+        ];
+        let file = read_xliff(&write_xliff(Language::English, Language::French, &units)).unwrap();
+        assert_eq!(file.lang, Some(Language::French));
+        assert_eq!(file.units, units);
+    }
+
+    #[test]
+    fn test_what_tools_write() {
+        let contents = r#"<?xml version='1.0' encoding='UTF-8'?>
+<xliff version='1.2' xmlns='urn:oasis:names:tc:xliff:document:1.2'>
+  <file datatype='plaintext' target-language='fr-FR' original='localization'>
+    <body>
+      <trans-unit resname = 'a_key' id="1">
+        <source>A <![CDATA[<b>bold</b> & plain]]> text</source>
+        <target state="translated">Un <g id="1">texte <x id="2"/>en <g id="3">gras</g></g> &#x41;&#66;</target>
+        <note from="developer">x.yml:2</note>
+        <alt-trans><target>Autre</target></alt-trans>
+      </trans-unit>
+      <trans-unit id="b_key"><source/><target></target></trans-unit>
+    </body>
+  </file>
+</xliff>
+"#;
+        let file = read_xliff(contents).unwrap();
+        assert_eq!(file.lang, Some(Language::French));
+        assert_eq!(
+            file.units,
+            vec![
+                unit("a_key", "A <b>bold</b> & plain text", "Un texte en gras AB", "x.yml:2"),
+                unit("b_key", "", "", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let wrap = |body: &str| format!("<xliff><file><body>{body}</body></file></xliff>");
+        let nbsp = wrap("<trans-unit id='a'><source>A&nbsp;B</source></trans-unit>");
+        assert!(read_xliff(&nbsp).is_err());
+        let nested = wrap("<trans-unit id='a'><trans-unit id='b'></trans-unit></trans-unit>");
+        assert!(read_xliff(&nested).is_err());
+        let mismatched = wrap("<trans-unit id='a'><source>A</target></trans-unit>");
+        assert!(read_xliff(&mismatched).is_err());
+        assert!(read_xliff(&wrap("<trans-unit><source>A</source></trans-unit>")).is_err());
+        assert!(read_xliff("<xliff><file><body><trans-unit id='a'><source>A</source>").is_err());
+    }
+}
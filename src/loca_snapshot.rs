@@ -0,0 +1,93 @@
+//! Snapshots of the localization texts, to find translations whose base language text has changed
+//! since they were last updated.
+//!
+//! A snapshot records, for each translated key, a hash of the translation and a hash of the base
+//! language text that it was translated from. When a snapshot is loaded with
+//! [`Everything::load_loca_snapshot`], the translations whose base text has changed while their
+//! own text hasn't are reported as stale. [`Everything::write_loca_snapshot`] records the current
+//! texts, except that stale translations keep their old record so that they stay reported until
+//! they are updated.
+
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, write};
+use std::io::Cursor;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use murmur3::murmur3_32;
+use serde::{Deserialize, Serialize};
+
+use crate::everything::Everything;
+
+/// The version of the snapshot file format that is written.
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct LocaSnapshot {
+    #[serde(rename = "tiger_loca_snapshot")]
+    version: u32,
+    /// The language that the translations were compared with.
+    pub(crate) base: String,
+    /// The records, indexed by localization key and then by language.
+    pub(crate) keys: BTreeMap<String, BTreeMap<String, Translated>>,
+}
+
+impl LocaSnapshot {
+    pub(crate) fn new(base: String) -> Self {
+        Self { version: SNAPSHOT_VERSION, base, keys: BTreeMap::new() }
+    }
+}
+
+/// The hashes of a translation and of the base language text it was translated from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Translated {
+    pub(crate) base: u32,
+    pub(crate) text: u32,
+}
+
+impl Translated {
+    pub(crate) fn new(base: &str, text: &str) -> Self {
+        Self { base: text_hash(base), text: text_hash(text) }
+    }
+
+    /// Whether the base text has changed since this record was made, while the translation
+    /// hasn't.
+    pub(crate) fn is_stale(self, current: Translated) -> bool {
+        self.text == current.text && self.base != current.base
+    }
+}
+
+fn text_hash(text: &str) -> u32 {
+    // unwrap is safe because reading from a string can't fail
+    murmur3_32(&mut Cursor::new(text), 0).unwrap()
+}
+
+impl Everything {
+    /// Load a snapshot that was written by [`Everything::write_loca_snapshot`], so that the
+    /// translations whose base language text has changed since then are reported.
+    ///
+    /// This must be called before [`Everything::validate_all`].
+    pub fn load_loca_snapshot(&mut self, fullpath: &Path) -> Result<()> {
+        let contents = read_to_string(fullpath)
+            .with_context(|| format!("could not read snapshot {}", fullpath.display()))?;
+        let snapshot: LocaSnapshot = serde_json::from_str(&contents)
+            .with_context(|| format!("could not parse snapshot {}", fullpath.display()))?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            bail!("snapshot {} was written by a newer version of tiger", fullpath.display());
+        }
+        self.loca_snapshot = Some(snapshot);
+        Ok(())
+    }
+
+    /// Write a snapshot of the mod's current localization texts, for a later
+    /// [`Everything::load_loca_snapshot`]. Returns the number of translations recorded.
+    ///
+    /// The translations that are stale according to the loaded snapshot keep their old record.
+    pub fn write_loca_snapshot(&self, fullpath: &Path) -> Result<usize> {
+        let snapshot = self.localization.snapshot(self.loca_snapshot.as_ref());
+        let count = snapshot.keys.values().map(BTreeMap::len).sum();
+        write(fullpath, serde_json::to_string_pretty(&snapshot)?)
+            .with_context(|| format!("could not write snapshot {}", fullpath.display()))?;
+        Ok(count)
+    }
+}
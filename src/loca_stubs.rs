@@ -0,0 +1,57 @@
+//! Stub localization files with the keys that validation reported missing, for translators to
+//! fill in.
+
+use std::fs::{OpenOptions, create_dir_all, read};
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::everything::Everything;
+use crate::fileset::FileHandler;
+
+impl Everything {
+    /// Write a stub localization file for each language that has missing keys, at
+    /// `localization/<lang>/zz_tiger_missing_l_<lang>.yml` in the mod. The missing keys get the
+    /// base language text, or else the key itself, as their text. If the stub file already
+    /// exists, the keys are added at its end and its lines are left as they are, so that it can
+    /// be filled in bit by bit.
+    ///
+    /// This must be called after [`Everything::validate_all`]. Returns the files written, with
+    /// the number of missing keys in each.
+    pub fn write_missing_loca(&self) -> Result<Vec<(PathBuf, usize)>> {
+        let mut written = Vec::new();
+        for lang in self.localization.iter_lang() {
+            let Some((lines, count)) = self.localization.missing_stubs(lang) else {
+                continue;
+            };
+            let pathname = self
+                .localization
+                .subpath()
+                .join(lang.to_string())
+                .join(format!("zz_tiger_missing_l_{lang}.yml"));
+            let fullpath = self.mod_root().join(&pathname);
+            if let Some(dir) = fullpath.parent() {
+                create_dir_all(dir)
+                    .with_context(|| format!("could not create {}", dir.display()))?;
+            }
+            let old = read(&fullpath).unwrap_or_default();
+            let mut contents = if old.is_empty() {
+                format!("\u{feff}l_{lang}:\n")
+            } else if old.ends_with(b"\n") {
+                String::new()
+            } else {
+                "\n".to_owned()
+            };
+            contents.push_str(&lines);
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&fullpath)
+                .and_then(|mut file| file.write_all(contents.as_bytes()))
+                .with_context(|| format!("could not write {}", fullpath.display()))?;
+            written.push((fullpath, count));
+        }
+        Ok(written)
+    }
+}
@@ -2,9 +2,10 @@
 
 use std::hash::Hash;
 use std::num::NonZeroU32;
-use std::sync::{LazyLock, RwLock};
+use std::sync::RwLock;
 
 use crate::helpers::{BiTigerHashMap, TigerHashMap};
+use crate::session::SessionState;
 use crate::token::{Loc, Token};
 use crate::tooltipped::Tooltipped;
 
@@ -71,6 +72,11 @@ impl<T> MacroCache<T> {
         let key = MacroKey::new(key.loc, args, tooltipped, negated);
         self.cache.write().unwrap().insert(key, value);
     }
+
+    /// Forget all cached expansions, so that they will be validated again.
+    pub fn clear(&mut self) {
+        self.cache.get_mut().unwrap().clear();
+    }
 }
 
 impl<T> Default for MacroCache<T> {
@@ -79,8 +85,10 @@ impl<T> Default for MacroCache<T> {
     }
 }
 
-/// Global macro map
-pub(crate) static MACRO_MAP: LazyLock<MacroMap> = LazyLock::new(MacroMap::default);
+/// Return the macro map of the current [`Session`](crate::Session).
+pub(crate) fn macro_map() -> &'static MacroMap {
+    &SessionState::current().macro_map
+}
 
 #[derive(Default)]
 pub struct MacroMap(RwLock<MacroMapInner>);
@@ -6,17 +6,20 @@ use std::fmt::Display;
 
 use bitflags::Flags;
 
+#[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator", feature = "hoi4"))]
 use crate::block::Block;
 use crate::everything::Everything;
+#[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator", feature = "hoi4"))]
 use crate::game::Game;
 #[cfg(feature = "hoi4")]
 use crate::hoi4::tables::modifs::modif_loc_hoi4;
 #[cfg(any(feature = "ck3", feature = "vic3", feature = "hoi4"))]
 use crate::item::Item;
 use crate::report::{ErrorKey, Severity, err};
-#[cfg(feature = "jomini")]
+#[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator"))]
 use crate::script_value::validate_non_dynamic_script_value;
 use crate::token::Token;
+#[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator", feature = "hoi4"))]
 use crate::validator::Validator;
 #[cfg(feature = "vic3")]
 use crate::vic3::tables::modifs::modif_loc_vic3;
@@ -39,6 +42,19 @@ pub trait ModifKinds: Display + Flags + Copy {
     fn lookup_modif(name: &Token, data: &Everything, warn: Option<Severity>) -> Option<Self>;
 }
 
+#[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator", feature = "hoi4"))]
+/// Look up a modif with [`ModifKinds::lookup_modif`], falling back on the game's `script_docs`
+/// logs. Modifs that are only known from the logs are accepted for all kinds.
+fn lookup_modif<MK: ModifKinds>(
+    name: &Token,
+    data: &Everything,
+    warn: Option<Severity>,
+) -> Option<MK> {
+    MK::lookup_modif(name, data, warn)
+        .or_else(|| data.script_docs.has_modifier(name.as_str()).then(MK::all))
+}
+
+#[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator", feature = "hoi4"))]
 pub fn validate_modifs<'a, MK: ModifKinds>(
     _block: &Block,
     data: &'a Everything,
@@ -50,7 +66,7 @@ pub fn validate_modifs<'a, MK: ModifKinds>(
         let mut vd = Validator::new(block, data);
         // Same as below, but with no loca check
         vd.unknown_fields(|key, bv| {
-            if let Some(mk) = MK::lookup_modif(key, data, Some(Severity::Error)) {
+            if let Some(mk) = lookup_modif::<MK>(key, data, Some(Severity::Error)) {
                 kinds.require(mk, key);
 
                 // TODO HOI4
@@ -79,7 +95,7 @@ pub fn validate_modifs<'a, MK: ModifKinds>(
             return;
         }
 
-        if let Some(mk) = MK::lookup_modif(key, data, Some(Severity::Error)) {
+        if let Some(mk) = lookup_modif::<MK>(key, data, Some(Severity::Error)) {
             kinds.require(mk, key);
             if Game::is_jomini() {
                 #[cfg(feature = "jomini")]
@@ -118,13 +134,14 @@ pub fn validate_modifs<'a, MK: ModifKinds>(
     });
 }
 
+#[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator", feature = "hoi4"))]
 pub fn verify_modif_exists<MK: ModifKinds>(
     key: &Token,
     data: &Everything,
     kinds: MK,
     sev: Severity,
 ) {
-    if let Some(mk) = MK::lookup_modif(key, data, Some(sev)) {
+    if let Some(mk) = lookup_modif::<MK>(key, data, Some(sev)) {
         kinds.require(mk, key);
     }
     // All modifiers are potentially valid in vic3
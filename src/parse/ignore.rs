@@ -21,7 +21,7 @@ pub enum IgnoreSize {
     End,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct IgnoreFilter {
     key: Option<ErrorKey>,
     text: Option<String>,
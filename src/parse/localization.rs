@@ -324,9 +324,11 @@ impl LocaParser {
             return self.error_line(key);
         }
 
-        // Skip optional number after :
+        // Optional version number after :
+        let mut version: Option<u32> = None;
         while let Some(c) = self.chars.peek() {
-            if c.is_ascii_digit() {
+            if let Some(digit) = c.to_digit(10) {
+                version = Some(version.unwrap_or(0).saturating_mul(10).saturating_add(digit));
                 self.next_char();
             } else {
                 break;
@@ -408,7 +410,7 @@ impl LocaParser {
         } else {
             LocaValue::Concat(take(&mut self.value))
         };
-        Some(LocaEntry::new(key, value, Some(token)))
+        Some(LocaEntry::new(key, value, Some(token)).with_version(version))
     }
 }
 
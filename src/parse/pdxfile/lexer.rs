@@ -1,9 +1,11 @@
 use std::fmt::{Display, Formatter};
 use std::iter::Peekable;
+use std::mem::take;
 use std::str::CharIndices;
 
 use crate::block::Comparator;
 use crate::block::Eq::Single;
+use crate::declared_signature::register_signature_comments;
 use crate::game::Game;
 use crate::parse::ignore::{IgnoreFilter, IgnoreSize, parse_comment};
 use crate::parse::pdxfile::{CharExt, Cob};
@@ -154,6 +156,12 @@ pub struct Lexer<'input> {
     active_block_ignores: Vec<(usize, u32, IgnoreFilter)>,
     /// Track the nested begin/end ignore ranges.
     active_range_ignores: Vec<(u32, IgnoreFilter)>,
+    /// The `# tiger:` comments at the top level, waiting for the item right below them.
+    pending_signature: Vec<Token>,
+    /// The line of the last comment that had a line of its own.
+    comment_line: u32,
+    /// The line of the last token.
+    token_line: u32,
 }
 
 impl<'input> Lexer<'input> {
@@ -171,6 +179,9 @@ impl<'input> Lexer<'input> {
             pending_block_ignores: Vec::new(),
             active_block_ignores: Vec::new(),
             active_range_ignores: Vec::new(),
+            pending_signature: Vec::new(),
+            comment_line: 0,
+            token_line: 0,
         }
     }
 
@@ -231,13 +242,24 @@ impl<'input> Lexer<'input> {
         true
     }
 
-    /// Apply the pending line-ignores to the current line.
-    fn apply_line_ignores(&mut self) {
+    /// Apply the pending comments to the token that starts here: the line-ignores to its line,
+    /// and the `# tiger:` comments to the item if they are right above it.
+    fn apply_pending_comments(&mut self) {
         let line = self.loc.line;
-        let path = self.loc.pathname();
-        for filter in self.pending_line_ignores.drain(..) {
-            register_ignore_filter(path, line..=line, filter);
+        if !self.pending_line_ignores.is_empty() {
+            let path = self.loc.pathname();
+            for filter in self.pending_line_ignores.drain(..) {
+                register_ignore_filter(path, line..=line, filter);
+            }
+        }
+        if !self.pending_signature.is_empty() {
+            let signature = take(&mut self.pending_signature);
+            if self.brace_depth == 0 && line == self.comment_line + 1 && self.loc.link_idx.is_none()
+            {
+                register_signature_comments(self.loc, signature);
+            }
         }
+        self.token_line = line;
     }
 
     /// Apply the pending block-ignores to the current open brace.
@@ -272,7 +294,7 @@ impl Iterator for Lexer<'_> {
                 // variable reference start and a component of an id, but can't start an id.
                 '@' => {
                     // A variable reference @name
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let mut id = self.start_cob();
                     id.add_char(c);
                     let start_i = i;
@@ -316,7 +338,12 @@ impl Iterator for Lexer<'_> {
                             "@:register-variable" => {
                                 let msg = format!("unknown reader directive `{token}`");
                                 let info = "did you mean `@:register_variable`?";
-                                err(ErrorKey::ParseError).msg(msg).info(info).loc(&token).push();
+                                err(ErrorKey::ParseError)
+                                    .msg(msg)
+                                    .info(info)
+                                    .fix(&token, "@:register_variable")
+                                    .loc(&token)
+                                    .push();
                                 None
                             }
                             "@:load_variable" => {
@@ -328,7 +355,12 @@ impl Iterator for Lexer<'_> {
                             "@:load-variable" => {
                                 let msg = format!("unknown reader directive `{token}`");
                                 let info = "did you mean `@:load_variable`?";
-                                err(ErrorKey::ParseError).msg(msg).info(info).loc(&token).push();
+                                err(ErrorKey::ParseError)
+                                    .msg(msg)
+                                    .info(info)
+                                    .fix(&token, "@:load_variable")
+                                    .loc(&token)
+                                    .push();
                                 None
                             }
                             "@:define" => Some(Lexeme::Directive(Directive::Define, token)),
@@ -372,7 +404,7 @@ impl Iterator for Lexer<'_> {
                 // `+` can start a number, and numbers are treated as ids here.
                 _ if !self.in_calc && (c.is_id_char() || c == '+') => {
                     // An unquoted token
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let mut id = self.start_cob();
                     id.add_char(c);
                     let start_i = i;
@@ -390,7 +422,7 @@ impl Iterator for Lexer<'_> {
                     return Some(Ok((start_i, Lexeme::General(token), self.eof_offset())));
                 }
                 _ if c.is_comparator_char() => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let mut id = self.start_cob();
                     id.add_char(c);
                     let start_i = i;
@@ -411,7 +443,7 @@ impl Iterator for Lexer<'_> {
                 }
                 _ if self.in_calc && (c.is_local_value_char() || c == '.') => {
                     // A number or the name of a reader variable, inside a `@[` calculation
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let mut id = self.start_cob();
                     id.add_char(c);
                     let start_i = i;
@@ -433,12 +465,12 @@ impl Iterator for Lexer<'_> {
                 // The ; is silently accepted because putting it after a number is a common mistake
                 // and doesn't seem to cause any harm.
                 ';' => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     self.consume();
                 }
                 '"' => {
                     // A quoted token
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let start_i = i;
                     let start_loc = self.loc;
                     let mut prev_char = c;
@@ -520,6 +552,7 @@ impl Iterator for Lexer<'_> {
                 }
                 '#' => {
                     // A comment
+                    let line = self.loc.line;
                     self.consume();
                     let mut comment = self.start_cob();
                     while let Some((_, c)) = self.peek() {
@@ -530,11 +563,24 @@ impl Iterator for Lexer<'_> {
                         comment.add_char(c);
                         self.consume();
                     }
-                    let s = if matches!(comment, Cob::Uninit) {
-                        ""
+                    let token = if matches!(comment, Cob::Uninit) {
+                        None
                     } else {
-                        comment.take_to_token().as_str()
+                        Some(comment.take_to_token())
                     };
+                    let s = token.as_ref().map_or("", Token::as_str);
+                    // Collect the `# tiger:` comments that may be right above an item.
+                    if line != self.token_line {
+                        if line != self.comment_line + 1 {
+                            self.pending_signature.clear();
+                        }
+                        self.comment_line = line;
+                        if self.brace_depth == 0
+                            && let Some(rest) = token.and_then(|t| t.trim().strip_prefix("tiger:"))
+                        {
+                            self.pending_signature.push(rest);
+                        }
+                    }
                     if let Some(spec) = parse_comment(s) {
                         match spec.size {
                             IgnoreSize::Line => self.pending_line_ignores.push(spec.filter),
@@ -558,7 +604,7 @@ impl Iterator for Lexer<'_> {
                 }
                 '$' => {
                     // A macro parameter
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let start_i = i;
                     let start_loc = self.loc;
                     self.consume();
@@ -591,14 +637,14 @@ impl Iterator for Lexer<'_> {
                 }
                 '{' => {
                     self.brace_depth += 1;
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     self.apply_block_ignores();
                     let token = Token::from_static_str("{", self.loc);
                     self.consume();
                     return Some(Ok((i, Lexeme::BlockStart(token), i + 1)));
                 }
                 '}' => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     self.close_block_ignores();
                     if self.brace_depth > 0 {
                         self.brace_depth -= 1;
@@ -619,44 +665,44 @@ impl Iterator for Lexer<'_> {
                     return Some(Ok((i, Lexeme::BlockEnd(token), i + 1)));
                 }
                 ']' => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let token = Token::from_static_str("]", self.loc);
                     self.consume();
                     self.in_calc = false;
                     return Some(Ok((i, Lexeme::CalcEnd(token), i + 1)));
                 }
                 '(' => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let token = Token::from_static_str("(", self.loc);
                     self.consume();
                     return Some(Ok((i, Lexeme::OpenParen(token), i + 1)));
                 }
                 ')' => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let token = Token::from_static_str(")", self.loc);
                     self.consume();
                     return Some(Ok((i, Lexeme::CloseParen(token), i + 1)));
                 }
                 '+' => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let token = Token::from_static_str("+", self.loc);
                     self.consume();
                     return Some(Ok((i, Lexeme::Add(token), i + 1)));
                 }
                 '-' => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let token = Token::from_static_str("-", self.loc);
                     self.consume();
                     return Some(Ok((i, Lexeme::Subtract(token), i + 1)));
                 }
                 '*' => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let token = Token::from_static_str("*", self.loc);
                     self.consume();
                     return Some(Ok((i, Lexeme::Multiply(token), i + 1)));
                 }
                 '/' => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let token = Token::from_static_str("/", self.loc);
                     self.consume();
                     return Some(Ok((i, Lexeme::Divide(token), i + 1)));
@@ -664,7 +710,7 @@ impl Iterator for Lexer<'_> {
                 // TODO: should really detect ^Z anywhere in the input.
                 // Move this to consume() ?
                 CONTROL_Z => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let loc = self.loc;
                     self.consume();
                     let msg = "^Z in file";
@@ -678,7 +724,7 @@ impl Iterator for Lexer<'_> {
                     return None;
                 }
                 _ => {
-                    self.apply_line_ignores();
+                    self.apply_pending_comments();
                     let msg = format!("unrecognized character `{c}`");
                     err(ErrorKey::ParseError).msg(msg).loc(self.loc).push();
                     self.consume();
@@ -1,19 +1,18 @@
-//! A global table for the pathnames used in `FileEntry` and `Loc`.
+//! A table for the pathnames used in `FileEntry` and `Loc`. There is one per [`Session`](crate::Session).
 //!
-//! Using this will make the often-cloned Loc faster to copy, since it will just contain an index into the global table.
+//! Using this will make the often-cloned Loc faster to copy, since it will just contain an index into the table.
 //! It also makes it faster to compare pathnames, because the table will be created in lexical order by the caller
 //! ([`Fileset`](crate::fileset::Fileset)), with the exception of some stray files (such as the config file)
 //! where the order doesn't matter.
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
-use std::sync::{LazyLock, RwLock};
+
+use crate::session::SessionState;
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PathTableIndex(u32);
 
-static PATHTABLE: LazyLock<RwLock<PathTable>> = LazyLock::new(|| RwLock::new(PathTable::default()));
-
-/// A global table for the pathnames used in `FileEntry` and `Loc`.
+/// A table for the pathnames used in `FileEntry` and `Loc`.
 ///
 /// See the [`self`](module-level documentation) for details.
 #[derive(Debug, Default)]
@@ -27,13 +26,13 @@ pub struct PathTable {
 }
 
 impl PathTable {
-    /// Stores a path in the path table and returns the index for the entry.
+    /// Stores a path in the current session's path table and returns the index for the entry.
     /// It's assumed that the caller has a master list of paths and won't store duplicates.
     ///
     /// The indexes are guaranteed to be in ascending order, so that if the caller stores a sorted
     /// list of paths then the indexes will also be sorted.
     pub fn store(local: PathBuf, fullpath: PathBuf) -> PathTableIndex {
-        PATHTABLE.write().unwrap().store_internal(local, fullpath)
+        SessionState::current().pathtable.write().unwrap().store_internal(local, fullpath)
     }
 
     fn store_internal(&mut self, local: PathBuf, fullpath: PathBuf) -> PathTableIndex {
@@ -47,13 +46,13 @@ impl PathTable {
     /// Return the local path based on its index.
     /// This can panic if the index is not one provided by `PathTable::store`.
     pub fn lookup_path(idx: PathTableIndex) -> &'static Path {
-        PATHTABLE.read().unwrap().lookup_paths_inner(idx).0
+        SessionState::current().pathtable.read().unwrap().lookup_paths_inner(idx).0
     }
 
     /// Return the full path based on its index.
     /// This can panic if the index is not one provided by `PathTable::store`.
     pub fn lookup_fullpath(idx: PathTableIndex) -> &'static Path {
-        PATHTABLE.read().unwrap().lookup_paths_inner(idx).1
+        SessionState::current().pathtable.read().unwrap().lookup_paths_inner(idx).1
     }
 
     #[inline]
@@ -0,0 +1,77 @@
+//! Read-only access to the loaded game and mod files, for programs that use this crate as a
+//! library, such as documentation generators or mod browsers.
+//!
+//! None of these functions report anything, and looking items up here does not count as using
+//! them for the purpose of [`Everything::check_unused`].
+
+use crate::block::Block;
+use crate::context::ScriptSignature;
+use crate::data::localization::Language;
+use crate::everything::Everything;
+#[cfg(feature = "hoi4")]
+use crate::game::Game;
+use crate::item::Item;
+use crate::token::Token;
+
+/// The result of [`Everything::get_definition`].
+#[derive(Debug, Clone, Copy)]
+pub enum Definition<'a> {
+    /// The key and block that define the item.
+    Found(&'a Token, &'a Block),
+    /// There is no item with that key.
+    NotFound,
+    /// Definitions of this item type can't be looked up. Use [`Everything::get_localization`]
+    /// for localization, and [`Everything::iter_keys`] to find out which items exist.
+    Unsupported,
+}
+
+impl Everything {
+    /// Return the key and block that define the item `key` of type `itype`.
+    ///
+    /// This covers the items in the general database, scripted triggers, scripted effects, and
+    /// events. Other item types, such as localization, get [`Definition::Unsupported`].
+    pub fn get_definition<'a>(&'a self, itype: Item, key: &'a str) -> Definition<'a> {
+        let definition = match itype {
+            Item::ScriptedTrigger => self.triggers.peek(key).map(|item| (&item.key, &item.block)),
+            Item::ScriptedEffect => self.effects.peek(key).map(|item| (&item.key, &item.block)),
+            #[cfg(feature = "hoi4")]
+            Item::Event if Game::is_hoi4() => {
+                self.events_hoi4.get_event(key).map(|item| (&item.key, &item.block))
+            }
+            #[cfg(feature = "jomini")]
+            Item::Event => self.events.get_event(key).map(|item| (&item.key, &item.block)),
+            _ if self.database.has_blocks(itype) => self.database.peek_key_block(itype, key),
+            _ => return Definition::Unsupported,
+        };
+        definition.map_or(Definition::NotFound, |(key, block)| Definition::Found(key, block))
+    }
+
+    /// Return the languages that the mod has localization for.
+    pub fn languages(&self) -> impl Iterator<Item = Language> {
+        self.localization.iter_lang()
+    }
+
+    /// Return the text of the localization entry `key` in language `lang`, as written in the
+    /// file but without the enclosing quotes.
+    ///
+    /// Returns `None` if there is no such entry, or if its text could not be parsed.
+    pub fn get_localization(&self, key: &str, lang: Language) -> Option<&Token> {
+        self.localization.get_text(key, lang)
+    }
+
+    /// Return what validation found out about how the scripted trigger `key` must be called.
+    ///
+    /// This is only known after [`Everything::validate_all`], and not for scripted triggers
+    /// with macro parameters.
+    pub fn scripted_trigger_signature(&self, key: &str) -> Option<ScriptSignature> {
+        self.triggers.peek(key).and_then(|item| item.signature(self))
+    }
+
+    /// Return what validation found out about how the scripted effect `key` must be called.
+    ///
+    /// This is only known after [`Everything::validate_all`], and not for scripted effects
+    /// with macro parameters.
+    pub fn scripted_effect_signature(&self, key: &str) -> Option<ScriptSignature> {
+        self.effects.peek(key).and_then(|item| item.signature(self))
+    }
+}
@@ -0,0 +1,291 @@
+//! Baseline files record the reports of an earlier run, so that later runs only show what's new.
+//!
+//! Reports are matched to baseline entries by their key and the path of their first location.
+//! Among those candidates, an entry with the same message, with whitespace normalized, and the
+//! same source line is preferred. When the lines have moved or been edited a little, an entry with
+//! the same message and a similar line or a nearby line number is accepted too, so that small
+//! edits don't bring back old reports. The message must always match, so that a new report next
+//! to an old one is still shown.
+
+use std::fs::{read_to_string, write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::helpers::{TigerHashMap, TigerHashSet, edit_distance};
+use crate::report::errors::{Cache, Errors};
+use crate::report::{ErrorKey, LogReportMetadata, LogReportPointers};
+
+/// The version of the baseline file format that is written.
+const BASELINE_VERSION: u32 = 1;
+
+/// How similar two source lines must be, from 0.0 to 1.0, to count as the same line after edits.
+const MIN_SIMILARITY: f64 = 0.6;
+
+/// How far a line may have moved and still match a baseline entry, regardless of its content.
+const MAX_LINE_DISTANCE: u32 = 3;
+
+/// Groups of reports and entries larger than this are only matched by exact line content,
+/// because fuzzy matching them all against each other would be slow.
+const MAX_FUZZY_PAIRS: usize = 10_000;
+
+/// One report in a baseline file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub key: ErrorKey,
+    /// The path of the report's first location, relative to the mod or game root.
+    pub path: PathBuf,
+    pub message: String,
+    /// The line number of the report's first location, if it was not about the whole file.
+    pub linenr: Option<u32>,
+    /// The contents of that line.
+    pub line: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BaselineFile {
+    tiger_baseline: u32,
+    entries: Vec<BaselineEntry>,
+}
+
+/// The output of `--json`, which older versions used as baselines. Only the fields needed here
+/// are read.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonOutput {
+    Document {
+        reports: Vec<JsonReport>,
+    },
+    /// Before the output had a version, it was just the array of reports.
+    Unversioned(Vec<JsonReport>),
+}
+
+#[derive(Deserialize)]
+struct JsonReport {
+    key: ErrorKey,
+    message: String,
+    locations: Vec<JsonLocation>,
+}
+
+#[derive(Deserialize)]
+struct JsonLocation {
+    path: PathBuf,
+    linenr: Option<u32>,
+    line: Option<String>,
+}
+
+type GroupKey = (ErrorKey, PathBuf);
+
+/// The loaded baseline, with its entries grouped by what a report must match exactly.
+#[derive(Debug, Default)]
+pub(crate) struct Baseline {
+    groups: TigerHashMap<GroupKey, Vec<BaselineEntry>>,
+}
+
+/// The outcome of matching the reports against the baseline.
+pub(crate) struct BaselineMatches<'a, 'r> {
+    /// The reports that are in the baseline.
+    pub matched: TigerHashSet<(&'r LogReportMetadata, &'r LogReportPointers)>,
+    /// The baseline entries that no report matched.
+    pub stale: Vec<&'a BaselineEntry>,
+}
+
+impl Baseline {
+    fn new(entries: Vec<BaselineEntry>) -> Self {
+        let mut groups: TigerHashMap<GroupKey, Vec<BaselineEntry>> = TigerHashMap::default();
+        for entry in entries {
+            let key = (entry.key, entry.path.clone());
+            groups.entry(key).or_default().push(entry);
+        }
+        Self { groups }
+    }
+
+    /// Match the given reports against the baseline. Every entry matches at most one report.
+    pub(crate) fn match_reports<'a, 'r>(
+        &'a self,
+        reports: impl Iterator<Item = (&'r LogReportMetadata, &'r LogReportPointers)>,
+        cache: &Cache,
+    ) -> BaselineMatches<'a, 'r> {
+        let mut candidates: TigerHashMap<&GroupKey, Vec<ReportLine<'r>>> = TigerHashMap::default();
+        for (report, pointers) in reports {
+            let Some(first) = pointers.first() else { continue };
+            let key = (report.key, first.loc.pathname().to_path_buf());
+            if let Some((key, _)) = self.groups.get_key_value(&key) {
+                let line = cache.get_line(first.loc).map(normalize);
+                candidates.entry(key).or_default().push(ReportLine {
+                    report: (report, pointers),
+                    message: normalize(&report.msg),
+                    linenr: first.loc.line,
+                    line,
+                });
+            }
+        }
+
+        let mut matches = BaselineMatches { matched: TigerHashSet::default(), stale: Vec::new() };
+        for (key, entries) in &self.groups {
+            let Some(reports) = candidates.get_mut(key) else {
+                matches.stale.extend(entries);
+                continue;
+            };
+            let entry_messages: Vec<String> =
+                entries.iter().map(|entry| normalize(&entry.message)).collect();
+            let entry_lines: Vec<Option<String>> =
+                entries.iter().map(|entry| entry.line.as_deref().map(normalize)).collect();
+            let mut used = vec![false; entries.len()];
+
+            // First pass: the same line content, preferring the nearest line number.
+            reports.retain(|report| {
+                let best = (0..entries.len())
+                    .filter(|&i| !used[i] && entry_messages[i] == report.message)
+                    .filter(|&i| report.line.is_some() && entry_lines[i] == report.line)
+                    .min_by_key(|&i| line_distance(entries[i].linenr, report.linenr));
+                if let Some(i) = best {
+                    used[i] = true;
+                    matches.matched.insert(report.report);
+                    false
+                } else {
+                    true
+                }
+            });
+
+            // Second pass: the same message, with similar line content or on a line that hasn't
+            // moved far.
+            if reports.len() * entries.len() <= MAX_FUZZY_PAIRS {
+                for report in reports.iter() {
+                    let best = (0..entries.len())
+                        .filter(|&i| !used[i] && entry_messages[i] == report.message)
+                        .filter_map(|i| {
+                            let similarity = match (&entry_lines[i], &report.line) {
+                                (Some(a), Some(b)) => similarity(a, b),
+                                _ => 0.0,
+                            };
+                            let distance = line_distance(entries[i].linenr, report.linenr);
+                            (similarity >= MIN_SIMILARITY || distance <= MAX_LINE_DISTANCE)
+                                .then_some((i, similarity, distance))
+                        })
+                        .max_by(|(_, sim_a, dist_a), (_, sim_b, dist_b)| {
+                            sim_a.total_cmp(sim_b).then(dist_b.cmp(dist_a))
+                        });
+                    if let Some((i, _, _)) = best {
+                        used[i] = true;
+                        matches.matched.insert(report.report);
+                    }
+                }
+            }
+
+            matches
+                .stale
+                .extend(entries.iter().zip(used).filter(|(_, used)| !used).map(|(e, _)| e));
+        }
+        matches
+    }
+}
+
+struct ReportLine<'r> {
+    report: (&'r LogReportMetadata, &'r LogReportPointers),
+    /// The message, with whitespace normalized.
+    message: String,
+    linenr: u32,
+    line: Option<String>,
+}
+
+/// Make a baseline entry that describes this report.
+pub(crate) fn baseline_entry(
+    report: &LogReportMetadata,
+    pointers: &LogReportPointers,
+    cache: &Cache,
+) -> Option<BaselineEntry> {
+    let first = pointers.first()?;
+    Some(BaselineEntry {
+        key: report.key,
+        path: first.loc.pathname().to_path_buf(),
+        message: report.msg.clone(),
+        linenr: (first.loc.line > 0).then_some(first.loc.line),
+        line: cache.get_line(first.loc).map(str::to_owned),
+    })
+}
+
+/// Collapse runs of whitespace to single spaces, and remove whitespace at the ends.
+fn normalize(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn line_distance(linenr: Option<u32>, report_linenr: u32) -> u32 {
+    linenr.unwrap_or(0).abs_diff(report_linenr)
+}
+
+/// Return how similar two strings are, from 0.0 for completely different to 1.0 for the same.
+/// It's based on the edit distance between them.
+#[allow(clippy::cast_precision_loss)] // the lengths are of source lines
+fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longest as f64
+}
+
+/// Load a baseline file, so that the reports in it are not printed.
+///
+/// Both the files written by [`write_baseline`] and the output of `--json` are accepted.
+pub fn load_baseline(fullpath: &Path) -> Result<()> {
+    let contents = read_to_string(fullpath)
+        .with_context(|| format!("could not read baseline {}", fullpath.display()))?;
+    let entries = if let Ok(file) = serde_json::from_str::<BaselineFile>(&contents) {
+        file.entries
+    } else {
+        let output: JsonOutput = serde_json::from_str(&contents)
+            .with_context(|| format!("could not parse baseline {}", fullpath.display()))?;
+        let (JsonOutput::Document { reports } | JsonOutput::Unversioned(reports)) = output;
+        reports
+            .into_iter()
+            .filter_map(|report| {
+                let location = report.locations.into_iter().next()?;
+                Some(BaselineEntry {
+                    key: report.key,
+                    path: location.path,
+                    message: report.message,
+                    linenr: location.linenr,
+                    line: location.line,
+                })
+            })
+            .collect()
+    };
+    Errors::get_mut().baseline = Some(Baseline::new(entries));
+    Ok(())
+}
+
+/// Stop suppressing the reports from the loaded baseline, if any.
+pub fn clear_baseline() {
+    Errors::get_mut().baseline = None;
+}
+
+/// The same as [`load_baseline`]. This is the older name, from when baselines had to match
+/// exactly.
+pub fn suppress_from_json(fullpath: &Path) -> Result<()> {
+    load_baseline(fullpath)
+}
+
+/// Write the stored reports that would be printed to a new baseline file.
+///
+/// Returns the number of entries written.
+pub fn write_baseline(fullpath: &Path) -> Result<usize> {
+    let errors = Errors::get();
+    let entries = errors.baseline_entries();
+    let count = entries.len();
+    let file = BaselineFile { tiger_baseline: BASELINE_VERSION, entries };
+    write(fullpath, serde_json::to_string_pretty(&file)?)
+        .with_context(|| format!("could not write baseline {}", fullpath.display()))?;
+    Ok(count)
+}
+
+/// Return the entries of the loaded baseline that don't match any of the stored reports.
+/// They are about problems that have been fixed, and can be removed from the baseline by writing
+/// it again.
+///
+/// Call this before [`emit_reports`](crate::emit_reports), because that leaves the stored reports
+/// empty.
+pub fn stale_baseline_entries() -> Vec<BaselineEntry> {
+    Errors::get().stale_baseline_entries()
+}
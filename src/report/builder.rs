@@ -5,9 +5,10 @@
 //!   without pointers, which would lead to panics.
 
 use crate::report::{
-    Confidence, ErrorKey, ErrorLoc, LogReportMetadata, LogReportPointers, LogReportStyle,
+    Confidence, ErrorKey, ErrorLoc, Fix, LogReportMetadata, LogReportPointers, LogReportStyle,
     PointedMessage, Severity, log,
 };
+use crate::token::Loc;
 
 // =================================================================================================
 // =============== Starting points:
@@ -71,7 +72,13 @@ impl ReportBuilderStage1 {
 
     /// Sets the main report message.
     pub fn msg<S: Into<String>>(self, msg: S) -> ReportBuilderStage2 {
-        ReportBuilderStage2 { stage1: self, msg: msg.into(), info: None, wiki: None }
+        ReportBuilderStage2 {
+            stage1: self,
+            msg: msg.into(),
+            info: None,
+            wiki: None,
+            fixes: Vec::new(),
+        }
     }
 }
 
@@ -82,6 +89,7 @@ pub struct ReportBuilderStage2 {
     msg: String,
     info: Option<String>,
     wiki: Option<String>,
+    fixes: Vec<Fix>,
 }
 
 impl ReportBuilderStage2 {
@@ -106,6 +114,35 @@ impl ReportBuilderStage2 {
         self
     }
 
+    /// Optional step. Adds a fix that replaces the text at `eloc` with `replacement`.
+    /// The fix should resolve the problem completely, so that `--fix` can apply it unattended.
+    /// For that reason, fixes are not added to reports of weak confidence.
+    pub fn fix<E: ErrorLoc, S: Into<String>>(mut self, eloc: E, replacement: S) -> Self {
+        if self.stage1.2 >= Confidence::Reasonable {
+            let length = eloc.loc_length();
+            let replacement = replacement.into();
+            self.fixes.push(Fix { loc: eloc.into_loc(), length, end: None, replacement });
+        }
+        self
+    }
+
+    /// Optional step. If `span` is `Some`, adds a fix that removes the text from its `Loc` up to
+    /// its end, which is the line and column just after the text. The text may span several lines.
+    /// Like [`ReportBuilderStage2::fix`], it is not added to reports of weak confidence.
+    pub fn opt_fix_removal(mut self, span: Option<(Loc, (u32, u32))>) -> Self {
+        if let Some((loc, end)) = span
+            && self.stage1.2 >= Confidence::Reasonable
+        {
+            self.fixes.push(Fix { loc, length: 0, end: Some(end), replacement: String::new() });
+        }
+        self
+    }
+
+    /// Optional step. Adds a fix if the `replacement` parameter is `Some`.
+    pub fn opt_fix<E: ErrorLoc, S: Into<String>>(self, eloc: E, replacement: Option<S>) -> Self {
+        if let Some(replacement) = replacement { self.fix(eloc, replacement) } else { self }
+    }
+
     pub fn loc<E: ErrorLoc>(self, eloc: E) -> ReportBuilderFull {
         let length = eloc.loc_length();
         ReportBuilderFull {
@@ -113,6 +150,7 @@ impl ReportBuilderStage2 {
             msg: self.msg,
             info: self.info,
             wiki: self.wiki,
+            fixes: self.fixes,
             pointers: vec![PointedMessage { loc: eloc.into_loc(), length, msg: None }],
         }
     }
@@ -124,6 +162,7 @@ impl ReportBuilderStage2 {
             msg: self.msg,
             info: self.info,
             wiki: self.wiki,
+            fixes: self.fixes,
             pointers: vec![PointedMessage { loc: eloc.into_loc(), length, msg: Some(msg.into()) }],
         }
     }
@@ -134,6 +173,7 @@ impl ReportBuilderStage2 {
             msg: self.msg,
             info: self.info,
             wiki: self.wiki,
+            fixes: self.fixes,
             pointers,
         }
     }
@@ -144,6 +184,7 @@ impl ReportBuilderStage2 {
             msg: self.msg,
             info: self.info,
             wiki: self.wiki,
+            fixes: self.fixes,
             pointers: vec![PointedMessage { loc: eloc.into_loc(), length: 0, msg: None }],
         }
     }
@@ -156,6 +197,7 @@ pub struct ReportBuilderFull {
     msg: String,
     info: Option<String>,
     wiki: Option<String>,
+    fixes: Vec<Fix>,
     pointers: LogReportPointers,
 }
 
@@ -186,6 +228,7 @@ impl ReportBuilderFull {
                 msg: self.msg,
                 info: self.info,
                 wiki: self.wiki,
+                fixes: self.fixes,
                 style: LogReportStyle::Full,
             },
             self.pointers,
@@ -204,6 +247,7 @@ pub struct ReportBuilderAbbreviated {
     msg: String,
     info: Option<String>,
     wiki: Option<String>,
+    fixes: Vec<Fix>,
     pointers: LogReportPointers,
 }
 
@@ -218,6 +262,7 @@ impl ReportBuilderAbbreviated {
                 msg: self.msg,
                 info: self.info,
                 wiki: self.wiki,
+                fixes: self.fixes,
                 style: LogReportStyle::Abbreviated,
             },
             self.pointers,
@@ -0,0 +1,140 @@
+//! Limit the reports to the lines that a change touched, as described by a unified diff.
+//!
+//! This is meant for reviewing changes to a mod: only the problems in the changed lines are
+//! shown, not the ones that were there before.
+
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+use crate::fileset::FileKind;
+use crate::helpers::TigerHashMap;
+use crate::report::LogReportPointers;
+
+/// The lines that were added or changed, per file. The paths are relative to the mod root.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangedLines {
+    files: TigerHashMap<PathBuf, Vec<RangeInclusive<u32>>>,
+}
+
+impl ChangedLines {
+    /// Read the changed lines from a unified diff, such as the output of `git diff`.
+    ///
+    /// Only the new side of the diff is used. The file names have their `b/` prefix removed, if
+    /// they have one, and should then be relative to the mod root. Deleted files are skipped.
+    pub fn from_diff(diff: &str) -> Self {
+        let mut files: TigerHashMap<PathBuf, Vec<RangeInclusive<u32>>> = TigerHashMap::default();
+        let mut current: Option<PathBuf> = None;
+        let mut after_old_name = false;
+        for line in diff.lines() {
+            // The new file name comes right after the old one. Checking that avoids mistaking an
+            // added line that starts with `++ ` for a file name.
+            let new_name = if after_old_name { line.strip_prefix("+++ ") } else { None };
+            after_old_name = line.starts_with("--- ");
+            if let Some(path) = new_name {
+                current = parse_diff_path(path);
+                if let Some(path) = &current {
+                    files.entry(path.clone()).or_default();
+                }
+            } else if let Some(hunk) = line.strip_prefix("@@ ") {
+                let (Some(path), Some(range)) = (&current, parse_hunk_header(hunk)) else {
+                    continue;
+                };
+                if let Some(ranges) = files.get_mut(path) {
+                    ranges.push(range);
+                }
+            }
+        }
+        Self { files }
+    }
+
+    /// Returns true iff no files were changed.
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns true iff the report points at a changed line in the mod. Reports about a whole
+    /// file count if that file changed at all.
+    pub(crate) fn touches(&self, pointers: &LogReportPointers) -> bool {
+        pointers.iter().any(|pointer| {
+            let loc = pointer.loc;
+            if loc.kind != FileKind::Mod {
+                return false;
+            }
+            let Some(ranges) = self.files.get(loc.pathname()) else {
+                return false;
+            };
+            loc.line == 0 || ranges.iter().any(|range| range.contains(&loc.line))
+        })
+    }
+}
+
+/// Parse the file name from a `+++` line. Returns `None` for deleted files.
+fn parse_diff_path(path: &str) -> Option<PathBuf> {
+    // There may be a timestamp after a tab.
+    let path = path.split('\t').next().unwrap_or(path).trim_end();
+    let path = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')).unwrap_or(path);
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path.strip_prefix("b/").unwrap_or(path);
+    Some(PathBuf::from(path))
+}
+
+/// Parse the new line range from a hunk header like `@@ -10,2 +12,3 @@`.
+/// Returns `None` if the hunk only deletes lines, or if the header can't be parsed.
+fn parse_hunk_header(hunk: &str) -> Option<RangeInclusive<u32>> {
+    let new = hunk.split_whitespace().find_map(|part| part.strip_prefix('+'))?;
+    let (start, count) = match new.split_once(',') {
+        Some((start, count)) => (start.parse::<u32>().ok()?, count.parse::<u32>().ok()?),
+        None => (new.parse::<u32>().ok()?, 1),
+    };
+    (count > 0).then(|| start..=start + count - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/events/my_events.txt b/events/my_events.txt
+index 1234567..89abcde 100644
+--- a/events/my_events.txt
++++ b/events/my_events.txt
+@@ -10,2 +10,3 @@ namespace = my
+-\told
++\tnew
++\tnewer
+@@ -40 +41 @@
+-x
++y
+@@ -50,3 +51,0 @@
+-a
+-b
+-c
+diff --git a/common/gone.txt b/common/gone.txt
+deleted file mode 100644
+--- a/common/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+";
+
+    #[test]
+    fn test_from_diff() {
+        let changed = ChangedLines::from_diff(DIFF);
+        let ranges = &changed.files[Path::new("events/my_events.txt")];
+        assert_eq!(ranges, &vec![10..=12, 41..=41]);
+        assert!(!changed.files.contains_key(Path::new("common/gone.txt")));
+        assert_eq!(changed.files.len(), 1);
+    }
+
+    #[test]
+    fn test_hunk_header() {
+        assert_eq!(parse_hunk_header("-1,5 +7,2 @@"), Some(7..=8));
+        assert_eq!(parse_hunk_header("-1 +1 @@ key = {"), Some(1..=1));
+        assert_eq!(parse_hunk_header("-3,2 +2,0 @@"), None);
+        assert_eq!(parse_hunk_header("garbage"), None);
+    }
+}
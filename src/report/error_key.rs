@@ -7,6 +7,7 @@ use strum_macros::{Display, EnumString};
 #[serde(rename_all = "kebab-case")]
 pub enum ErrorKey {
     Config,
+    ScriptDocs,
     ReadError,
     ParseError,
     BraceError,
@@ -58,7 +59,13 @@ pub enum ErrorKey {
     Colors,
     UnusedLocalization,
     LocalizationKeyCollision,
+    TranslationMismatch,
+    StaleTranslation,
+    MissingGlyph,
     UnusedFile,
+    UnusedDefinition,
+    UnusedItem,
+    UnreachableEvent,
     UnknownList,
     UnknownVariable,
     Choice,
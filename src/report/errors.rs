@@ -9,22 +9,28 @@ use std::iter::{empty, once};
 use std::mem::take;
 use std::ops::{Bound, RangeBounds};
 use std::path::{Path, PathBuf};
-use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::sync::{LazyLock, MutexGuard};
 
 use encoding_rs::{UTF_8, WINDOWS_1252};
+use strum_macros::{Display, EnumString};
 
+use crate::block::Comparator;
 use crate::helpers::{TigerHashMap, TigerHashSet};
-use crate::macros::MACRO_MAP;
+use crate::macros::macro_map;
 use crate::parse::ignore::IgnoreFilter;
+use crate::report::baseline::{Baseline, BaselineEntry, baseline_entry};
+use crate::report::changed_lines::ChangedLines;
 use crate::report::error_loc::ErrorLoc;
 use crate::report::filter::ReportFilter;
-use crate::report::suppress::{Suppression, SuppressionKey};
 use crate::report::writer::{log_report, log_summary};
-use crate::report::writer_json::log_report_json;
+use crate::report::writer_json::log_reports_json;
+use crate::report::writer_sarif::log_reports_sarif;
+use crate::report::writer_xml::{log_reports_checkstyle, log_reports_junit};
 use crate::report::{
-    ErrorKey, FilterRule, LogReport, LogReportMetadata, LogReportPointers, LogReportStyle,
-    OutputStyle, PointedMessage,
+    Confidence, ErrorKey, FilterRule, Fix, LogReport, LogReportMetadata, LogReportPointers,
+    LogReportStyle, OutputStyle, PointedMessage, Severity,
 };
+use crate::session::SessionState;
 use crate::set;
 use crate::token::Loc;
 
@@ -39,7 +45,24 @@ static LOG_ONCE: LazyLock<TigerHashSet<ErrorKey>> = LazyLock::new(|| {
     ])
 });
 
-static ERRORS: LazyLock<Mutex<Errors>> = LazyLock::new(|| Mutex::new(Errors::default()));
+/// The formats in which the reports can be printed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum OutputFormat {
+    /// The default format, meant for people to read.
+    #[default]
+    Text,
+    /// A JSON object with the `schema_version` of the format, information about the run, a
+    /// `summary` with the number of reports of each severity, and the `reports` array with one
+    /// object per report.
+    Json,
+    /// A SARIF 2.1.0 log, for code scanning tools.
+    Sarif,
+    /// `JUnit` XML, with a testcase per file and a failure per report.
+    Junit,
+    /// Checkstyle XML.
+    Checkstyle,
+}
 
 #[allow(missing_debug_implementations)]
 #[derive(Default)]
@@ -50,6 +73,9 @@ pub struct Errors<'a> {
     /// Loaded DLCs' error tags.
     pub(crate) loaded_dlcs_labels: Vec<String>,
 
+    /// The root directory of the mod being validated.
+    pub(crate) mod_root: Option<PathBuf>,
+
     pub(crate) cache: Cache,
 
     /// Determines whether a report should be printed.
@@ -58,7 +84,8 @@ pub struct Errors<'a> {
     /// Output color and style configuration.
     pub(crate) styles: OutputStyle,
 
-    pub(crate) suppress: TigerHashMap<SuppressionKey<'a>, Vec<Suppression>>,
+    /// Reports from an earlier run, that should not be printed again.
+    pub(crate) baseline: Option<Baseline>,
     // The range is decomposed into its start and end bounds in order to
     // avoid dyn shenanigans with the RangeBounds trait.
     ignore: TigerHashMap<&'a Path, Vec<IgnoreEntry>>,
@@ -70,26 +97,6 @@ pub struct Errors<'a> {
 }
 
 impl Errors<'_> {
-    fn should_suppress(&self, report: &LogReportMetadata, pointers: &LogReportPointers) -> bool {
-        let key = SuppressionKey { key: report.key, message: Cow::Borrowed(&report.msg) };
-        if let Some(v) = self.suppress.get(&key) {
-            for suppression in v {
-                if suppression.len() != pointers.len() {
-                    continue;
-                }
-                for (s, p) in suppression.iter().zip(pointers.iter()) {
-                    if s.path == p.loc.pathname()
-                        && s.tag == p.msg
-                        && s.line.as_deref() == self.cache.get_line(p.loc)
-                    {
-                        return true;
-                    }
-                }
-            }
-        }
-        false
-    }
-
     fn should_ignore(&self, report: &LogReportMetadata, pointers: &LogReportPointers) -> bool {
         for p in pointers {
             if let Some(vec) = self.ignore.get(p.loc.pathname()) {
@@ -108,25 +115,41 @@ impl Errors<'_> {
     /// Perform some checks to see whether the report should actually be logged.
     /// If yes, it will add it to the storage.
     fn push_report(&mut self, report: LogReportMetadata, pointers: LogReportPointers) {
-        if !self.filter.should_print_report(&report, &pointers)
-            || self.should_suppress(&report, &pointers)
-        {
+        if !self.filter.should_print_report(&report, &pointers) {
             return;
         }
         self.storage.entry(report).or_default().insert(pointers);
     }
 
+    /// Iterate over all the stored reports, with each occurrence separately, except the ones
+    /// that are ignored by `#tiger-ignore` directives.
+    fn occurrences(&self) -> impl Iterator<Item = (&LogReportMetadata, &LogReportPointers)> {
+        self.storage.iter().flat_map(move |(report, occurrences)| {
+            occurrences
+                .iter()
+                .filter(move |pointers| !self.should_ignore(report, pointers))
+                .map(move |pointers| (report, pointers))
+        })
+    }
+
     /// Extract the stored reports, sort them, and return them as a vector.
     pub fn flatten_reports(
         &self,
         consolidate: bool,
     ) -> Vec<(&LogReportMetadata, Cow<'_, LogReportPointers>, usize)> {
+        let in_baseline = self
+            .baseline
+            .as_ref()
+            .map(|baseline| baseline.match_reports(self.occurrences(), &self.cache).matched)
+            .unwrap_or_default();
         let mut reports: Vec<_> = self
             .storage
             .iter()
             .flat_map(|(report, occurrences)| -> Box<dyn Iterator<Item = _>> {
-                let mut iterator =
-                    occurrences.iter().filter(|pointers| !self.should_ignore(report, pointers));
+                let mut iterator = occurrences.iter().filter(|pointers| {
+                    !self.should_ignore(report, pointers)
+                        && !in_baseline.contains(&(report, *pointers))
+                });
                 match report.style {
                     LogReportStyle::Full => {
                         if consolidate && LOG_ONCE.contains(&report.key) {
@@ -182,9 +205,7 @@ impl Errors<'_> {
         reports
     }
 
-    /// Print the stored reports.
-    /// Set `json` if they should be printed as a JSON array. Otherwise they are printed in the
-    /// default output format.
+    /// Print the stored reports in the given `format`.
     ///
     /// Note that the default output format is not stable across versions. It is meant for human
     /// readability and occasionally gets changed to improve that.
@@ -195,45 +216,92 @@ impl Errors<'_> {
     pub fn emit_reports<O: Write + Send>(
         &mut self,
         output: &mut O,
-        json: bool,
+        format: OutputFormat,
         consolidate: bool,
         summary: bool,
     ) -> bool {
         let reports = self.flatten_reports(consolidate);
         let result = !reports.is_empty();
-        if json {
-            _ = writeln!(output, "[");
-            let mut first = true;
-            for (report, pointers, _) in &reports {
-                if !first {
-                    _ = writeln!(output, ",");
+        match format {
+            OutputFormat::Text => {
+                for (report, pointers, additional) in &reports {
+                    log_report(self, output, report, pointers, *additional);
+                }
+                if summary {
+                    log_summary(output, &self.styles, &reports);
                 }
-                first = false;
-                log_report_json(self, output, report, pointers);
-            }
-            _ = writeln!(output, "\n]");
-        } else {
-            for (report, pointers, additional) in &reports {
-                log_report(self, output, report, pointers, *additional);
-            }
-            if summary {
-                log_summary(output, &self.styles, &reports);
             }
+            OutputFormat::Json => log_reports_json(self, output, &reports),
+            OutputFormat::Sarif => log_reports_sarif(output, &reports),
+            OutputFormat::Junit => log_reports_junit(output, &reports),
+            OutputFormat::Checkstyle => log_reports_checkstyle(output, &reports),
         }
         self.storage.clear();
         result
     }
 
+    /// Collect the fixes of all the stored reports that would be printed.
+    pub(crate) fn fixes(&self) -> Vec<Fix> {
+        self.flatten_reports(false)
+            .into_iter()
+            .flat_map(|(report, _, _)| report.fixes.iter().cloned())
+            .collect()
+    }
+
+    /// Make baseline entries for all the stored reports that would be printed.
+    pub(crate) fn baseline_entries(&self) -> Vec<BaselineEntry> {
+        let mut entries: Vec<_> = self
+            .occurrences()
+            .filter_map(|(report, pointers)| baseline_entry(report, pointers, &self.cache))
+            .collect();
+        entries
+            .sort_by(|a, b| (&a.path, a.linenr, &a.message).cmp(&(&b.path, b.linenr, &b.message)));
+        entries
+    }
+
+    /// Return the entries of the loaded baseline that don't match any of the stored reports.
+    pub(crate) fn stale_baseline_entries(&self) -> Vec<BaselineEntry> {
+        self.baseline.as_ref().map_or_else(Vec::new, |baseline| {
+            let matches = baseline.match_reports(self.occurrences(), &self.cache);
+            let mut stale: Vec<_> = matches.stale.into_iter().cloned().collect();
+            stale.sort_by(|a, b| (&a.path, a.linenr).cmp(&(&b.path, b.linenr)));
+            stale
+        })
+    }
+
+    /// Count the stored reports that would be printed and that match the `fail_on` rule of the
+    /// filter.
+    pub fn count_failing_reports(&self, consolidate: bool) -> usize {
+        self.flatten_reports(consolidate)
+            .iter()
+            .filter(|(report, pointers, _)| self.filter.should_fail(report, pointers))
+            .count()
+    }
+
+    /// Extract the stored reports, in the same order and with the same filtering as
+    /// [`Errors::emit_reports`] would print them, but without printing them.
+    ///
+    /// The stored reports will be left empty.
+    pub fn take_emitted_reports(&mut self, consolidate: bool) -> Vec<LogReport> {
+        let reports = self
+            .flatten_reports(consolidate)
+            .into_iter()
+            .map(|(report, pointers, _)| (report.clone(), pointers.into_owned()))
+            .collect();
+        self.storage.clear();
+        reports
+    }
+
     pub fn store_source_file(&mut self, fullpath: PathBuf, source: &'static str) {
         self.cache.filecache.borrow_mut().insert(fullpath, source);
     }
 
-    /// Get a mutable lock on the global ERRORS struct.
+    /// Get a mutable lock on the current session's `Errors` struct.
     ///
     /// # Panics
     /// May panic when the mutex has been poisoned by another thread.
     pub fn get_mut() -> MutexGuard<'static, Errors<'static>> {
-        ERRORS.lock().unwrap()
+        SessionState::current().errors.lock().unwrap()
     }
 
     /// Like [`Errors::get_mut`] but intended for read-only access.
@@ -244,7 +312,7 @@ impl Errors<'_> {
     /// # Panics
     /// May panic when the mutex has been poisoned by another thread.
     pub fn get() -> MutexGuard<'static, Errors<'static>> {
-        ERRORS.lock().unwrap()
+        SessionState::current().errors.lock().unwrap()
     }
 }
 
@@ -294,13 +362,18 @@ impl Cache {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct IgnoreEntry {
     start: Bound<u32>,
     end: Bound<u32>,
     filter: IgnoreFilter,
 }
 
+/// Record the root directory of the mod being validated, to be mentioned in the JSON output.
+pub(crate) fn set_mod_root(mod_root: PathBuf) {
+    Errors::get_mut().mod_root = Some(mod_root);
+}
+
 /// Record a secondary mod to be loaded before the one being validated.
 /// `label` is what it should be called in the error reports; ideally only a few characters long.
 pub fn add_loaded_mod_root(label: String) {
@@ -333,7 +406,7 @@ fn pointed_msg_expansion(pointers: Vec<PointedMessage>) -> Vec<PointedMessage> {
             let mut first = true;
             std::iter::from_fn(move || match next_loc {
                 Some(mut stack) => {
-                    next_loc = stack.link_idx.and_then(|idx| MACRO_MAP.get_loc(idx));
+                    next_loc = stack.link_idx.and_then(|idx| macro_map().get_loc(idx));
                     stack.link_idx = None;
                     let next = if first {
                         PointedMessage { loc: stack, length: p.length, msg: p.msg.clone() }
@@ -354,9 +427,7 @@ pub fn will_maybe_log<E: ErrorLoc>(eloc: E, key: ErrorKey) -> bool {
     Errors::get().filter.should_maybe_print(key, eloc.into_loc())
 }
 
-/// Print all the stored reports to the error output.
-/// Set `json` if they should be printed as a JSON array. Otherwise they are printed in the
-/// default output format.
+/// Print all the stored reports to the error output, in the given `format`.
 ///
 /// Note that the default output format is not stable across versions. It is meant for human
 /// readability and occasionally gets changed to improve that.
@@ -364,11 +435,19 @@ pub fn will_maybe_log<E: ErrorLoc>(eloc: E, key: ErrorKey) -> bool {
 /// Returns true iff any reports were printed.
 pub fn emit_reports<O: Write + Send>(
     output: &mut O,
-    json: bool,
+    format: OutputFormat,
     consolidate: bool,
     summary: bool,
 ) -> bool {
-    Errors::get_mut().emit_reports(output, json, consolidate, summary)
+    Errors::get_mut().emit_reports(output, format, consolidate, summary)
+}
+
+/// Count the stored reports that would be printed and that should make the run fail, according
+/// to [`set_fail_on`] or the `fail_on` rule in the config file.
+///
+/// Call this before [`emit_reports`], because that leaves the stored reports empty.
+pub fn count_failing_reports(consolidate: bool) -> usize {
+    Errors::get().count_failing_reports(consolidate)
 }
 
 /// Extract the stored reports, sort them, and return them as a hashmap with the occurrences for
@@ -379,10 +458,66 @@ pub fn take_reports() -> TigerHashMap<LogReportMetadata, TigerHashSet<LogReportP
     take(&mut Errors::get_mut().storage)
 }
 
+/// Extract the stored reports, sorted and filtered the same way as [`emit_reports`] would print
+/// them. This is meant for callers that want to present the reports in their own way.
+///
+/// The stored reports will be left empty.
+pub fn take_emitted_reports(consolidate: bool) -> Vec<LogReport> {
+    Errors::get_mut().take_emitted_reports(consolidate)
+}
+
+/// Print the given reports in the default output format, as [`emit_reports`] would.
+/// This is meant for callers that took the reports with [`take_emitted_reports`] and want to
+/// print only some of them.
+///
+/// Set `summary` to also print the counts of the reports per severity.
+pub fn emit_given_reports<O: Write + Send>(output: &mut O, reports: &[LogReport], summary: bool) {
+    let errors = Errors::get();
+    for (report, pointers) in reports {
+        log_report(&errors, output, report, pointers, 0);
+    }
+    if summary {
+        let reports =
+            reports.iter().map(|(report, pointers)| (report, Cow::Borrowed(pointers), 0)).collect();
+        log_summary(output, &errors.styles, &reports);
+    }
+}
+
 pub fn store_source_file(fullpath: PathBuf, source: &'static str) {
     Errors::get_mut().store_source_file(fullpath, source);
 }
 
+/// Return a copy of the stored reports, leaving the storage as it is.
+pub(crate) fn copy_reports() -> TigerHashMap<LogReportMetadata, TigerHashSet<LogReportPointers>> {
+    Errors::get().storage.clone()
+}
+
+/// Add previously copied reports back to the storage.
+pub(crate) fn restore_reports(
+    reports: TigerHashMap<LogReportMetadata, TigerHashSet<LogReportPointers>>,
+) {
+    let storage = &mut Errors::get_mut().storage;
+    for (report, occurrences) in reports {
+        storage.entry(report).or_default().extend(occurrences);
+    }
+}
+
+/// Forget what is known about the contents of some files, because they have changed and are
+/// about to be reloaded. This drops their cached lines and the ignore filters that were
+/// registered for them.
+///
+/// `pathnames` are paths relative to the mod or vanilla root, and `fullpaths` are full filesystem
+/// paths.
+pub(crate) fn forget_files(pathnames: &[PathBuf], fullpaths: &[PathBuf]) {
+    let mut errors = Errors::get_mut();
+    errors.ignore.retain(|path, _| !pathnames.iter().any(|p| p == path));
+    let mut filecache = errors.cache.filecache.borrow_mut();
+    for fullpath in fullpaths {
+        filecache.remove(fullpath);
+    }
+    errors.cache.linecache.borrow_mut().clear();
+}
+
 pub fn register_ignore_filter<R>(pathname: &'static Path, lines: R, filter: IgnoreFilter)
 where
     R: RangeBounds<u32>,
@@ -390,7 +525,12 @@ where
     let start = lines.start_bound().cloned();
     let end = lines.end_bound().cloned();
     let entry = IgnoreEntry { start, end, filter };
-    Errors::get_mut().ignore.entry(pathname).or_default().push(entry);
+    let mut errors = Errors::get_mut();
+    let entries = errors.ignore.entry(pathname).or_default();
+    // The same file may be parsed again when it's reloaded.
+    if !entries.contains(&entry) {
+        entries.push(entry);
+    }
 }
 
 // =================================================================================================
@@ -423,7 +563,28 @@ pub fn set_show_loaded_mods(v: bool) {
     Errors::get_mut().filter.show_loaded_mods = v;
 }
 
+/// Configure the error reporter to only show errors that point at the given changed lines in
+/// the mod. With `None`, errors are shown regardless of which lines changed.
+pub fn set_changed_lines(changed_lines: Option<ChangedLines>) {
+    Errors::get_mut().filter.changed_lines = changed_lines;
+}
+
 /// Configure the error reporter to only show errors that match this [`FilterRule`].
 pub(crate) fn set_predicate(predicate: FilterRule) {
     Errors::get_mut().filter.predicate = predicate;
 }
+
+/// Configure which printed reports should make the run fail: those at or above both `severity`
+/// and `confidence`. This overrides the `fail_on` rule in the config file.
+pub fn set_fail_on(severity: Severity, confidence: Confidence) {
+    Errors::get_mut().filter.fail_on = Some(FilterRule::Conjunction(vec![
+        FilterRule::Severity(Comparator::AtLeast, severity),
+        FilterRule::Confidence(Comparator::AtLeast, confidence),
+    ]));
+}
+
+/// Configure which printed reports should make the run fail: those that match this
+/// [`FilterRule`].
+pub(crate) fn set_fail_on_predicate(predicate: Option<FilterRule>) {
+    Errors::get_mut().filter.fail_on = predicate;
+}
@@ -5,6 +5,7 @@ use crate::block::Comparator;
 
 use crate::Token;
 use crate::fileset::FileKind;
+use crate::report::changed_lines::ChangedLines;
 use crate::report::{
     Confidence, ErrorKey, ErrorLoc, LogReportMetadata, LogReportPointers, Severity, err,
 };
@@ -20,6 +21,11 @@ pub struct ReportFilter {
     pub show_loaded_mods: bool,
     /// A complex trigger that evaluates a report to assess whether it should be printed.
     pub predicate: FilterRule,
+    /// A trigger that evaluates a printed report to assess whether it should make the run fail.
+    /// If it's `None`, no report makes the run fail.
+    pub fail_on: Option<FilterRule>,
+    /// If set, only reports that point at these lines in the mod are printed.
+    pub changed_lines: Option<ChangedLines>,
 }
 
 impl ReportFilter {
@@ -27,6 +33,7 @@ impl ReportFilter {
     /// A print will be rejected if the report matches at least one of the following conditions:
     /// - Its Severity or Confidence level is too low.
     /// - It's from vanilla or a loaded mod and the program is configured to ignore those locations.
+    /// - Only changed lines should be reported, and it doesn't point at any of them.
     /// - The filter has a trigger, and the report doesn't match it.
     pub fn should_print_report(
         &self,
@@ -46,9 +53,19 @@ impl ReportFilter {
         if out_of_scope {
             return false;
         }
+        if let Some(changed_lines) = &self.changed_lines
+            && !changed_lines.touches(pointers)
+        {
+            return false;
+        }
         self.predicate.apply(report, pointers)
     }
 
+    /// Returns true iff the report is one that should make the run fail.
+    pub fn should_fail(&self, report: &LogReportMetadata, pointers: &LogReportPointers) -> bool {
+        self.fail_on.as_ref().is_some_and(|rule| rule.apply(report, pointers))
+    }
+
     /// TODO: Check the filter rules to be more sure.
     pub fn should_maybe_print(&self, key: ErrorKey, loc: Loc) -> bool {
         if key == ErrorKey::Config {
@@ -0,0 +1,128 @@
+//! Apply the machine-applicable fixes attached to the reports to the mod's files.
+//!
+//! Fixes are attached only where the right text is already known, such as a misspelled name with
+//! a single likely correction or an exact duplicate to remove. Localization that
+//! [`Localization::suggest`](crate::data::localization::Localization::suggest) proposes would
+//! have to be written by the modder, and indentation isn't checked at all, so neither has fixes.
+
+use std::fs::{read, write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::block::Block;
+use crate::fileset::FileKind;
+use crate::helpers::TigerHashMap;
+use crate::report::Fix;
+use crate::report::errors::Errors;
+use crate::token::{Loc, Token};
+
+const BOM: &str = "\u{feff}";
+
+/// Apply the fixes of all the stored reports that would be printed, editing the files in place.
+///
+/// Only files in the mod itself are changed. Fixes that overlap an earlier fix in the same file,
+/// or that don't fit the file's current contents, are skipped.
+///
+/// Returns the number of fixes applied.
+pub fn apply_fixes() -> Result<usize> {
+    let mut by_file: TigerHashMap<&'static Path, Vec<Fix>> = TigerHashMap::default();
+    for fix in Errors::get().fixes() {
+        if fix.loc.kind == FileKind::Mod && fix.loc.link_idx.is_none() && fix.loc.line > 0 {
+            by_file.entry(fix.loc.fullpath()).or_default().push(fix);
+        }
+    }
+    let mut applied = 0;
+    for (fullpath, fixes) in by_file {
+        applied += apply_fixes_to_file(fullpath, fixes)?;
+    }
+    Ok(applied)
+}
+
+fn apply_fixes_to_file(fullpath: &Path, mut fixes: Vec<Fix>) -> Result<usize> {
+    let bytes = read(fullpath).with_context(|| format!("could not read {}", fullpath.display()))?;
+    // Files that aren't UTF-8 are left alone, rather than risk changing their encoding.
+    let Ok(contents) = String::from_utf8(bytes) else {
+        return Ok(0);
+    };
+    let (bom, contents) = match contents.strip_prefix(BOM) {
+        Some(contents) => (BOM, contents),
+        None => ("", contents.as_str()),
+    };
+    let line_starts = line_starts(contents);
+
+    // Apply the fixes from the end of the file backward, so that each fix leaves the locations
+    // of the ones still to be done unchanged.
+    fixes.sort_by_key(|fix| ((fix.loc.line, fix.loc.column), fix.end()));
+    fixes.dedup();
+    let mut new_contents = contents.to_owned();
+    let mut applied = 0;
+    let mut limit = usize::MAX;
+    for fix in fixes.iter().rev() {
+        let (end_line, end_column) = fix.end();
+        let (Some(mut start), Some(mut end)) = (
+            byte_offset(contents, &line_starts, fix.loc.line, fix.loc.column),
+            byte_offset(contents, &line_starts, end_line, end_column),
+        ) else {
+            continue;
+        };
+        // A removal that leaves only whitespace on its lines takes the whole lines.
+        if fix.replacement.is_empty() && start < end {
+            let line_start = contents[..start].rfind('\n').map_or(0, |i| i + 1);
+            let after = &contents[end..];
+            let line_end = after.find('\n').map_or(after.len(), |i| i + 1);
+            if contents[line_start..start].trim().is_empty() && after[..line_end].trim().is_empty()
+            {
+                start = line_start;
+                end += line_end;
+            }
+        }
+        if start > end || end > limit {
+            continue;
+        }
+        new_contents.replace_range(start..end, &fix.replacement);
+        limit = start;
+        applied += 1;
+    }
+
+    if applied > 0 {
+        let new_contents = format!("{bom}{new_contents}");
+        write(fullpath, new_contents)
+            .with_context(|| format!("could not write {}", fullpath.display()))?;
+    }
+    Ok(applied)
+}
+
+/// Return the byte offsets at which the lines of `contents` start. If `contents` ends with a line
+/// end, the last offset is its length, for the empty line after it.
+fn line_starts(contents: &str) -> Vec<usize> {
+    std::iter::once(0).chain(contents.match_indices('\n').map(|(i, _)| i + 1)).collect()
+}
+
+/// Return the byte offset in `contents` of the given line and column, which are 1-based. The
+/// column may be just past the end of the line, but not on or past its line end.
+fn byte_offset(contents: &str, line_starts: &[usize], line: u32, column: u32) -> Option<usize> {
+    let start = *line_starts.get((line as usize).checked_sub(1)?)?;
+    let text = contents[start..].split_inclusive('\n').next().unwrap_or_default();
+    let text = text.trim_end_matches(['\r', '\n']);
+    Some(start + char_offset(text, (column as usize).checked_sub(1)?)?)
+}
+
+/// Return the byte offset of the character at index `chars` in `s`, or of the end of `s` if
+/// `chars` is its length.
+fn char_offset(s: &str, chars: usize) -> Option<usize> {
+    s.char_indices().map(|(i, _)| i).chain(std::iter::once(s.len())).nth(chars)
+}
+
+/// Find the text of the script item `key = { ... }`, from the start of the key to the end of the
+/// block's closing brace.
+///
+/// Returns where the span starts, and the line and column just after it, or `None` if the item
+/// isn't in a file as such.
+pub(crate) fn item_span(key: &Token, block: &Block) -> Option<(Loc, (u32, u32))> {
+    let (line, column) = block.close;
+    if key.loc.link_idx.is_some() || key.loc.line == 0 || line == 0 {
+        return None;
+    }
+    Some((key.loc, (line, column + 1)))
+}
@@ -1,24 +1,37 @@
 //! Error report collection and printing facilities.
 
+pub use baseline::{
+    BaselineEntry, clear_baseline, load_baseline, stale_baseline_entries, suppress_from_json,
+    write_baseline,
+};
 pub(crate) use builder::{ReportBuilderFull, err, fatal, report, tips, untidy, warn};
+pub use changed_lines::ChangedLines;
 pub(crate) use error_key::ErrorKey;
 pub(crate) use error_loc::ErrorLoc;
 pub use errors::*;
 pub(crate) use filter::FilterRule;
+pub use fix::apply_fixes;
+pub(crate) use fix::item_span;
 pub(crate) use output_style::OutputStyle;
 pub use report_struct::{
-    Confidence, LogReport, LogReportMetadata, LogReportPointers, LogReportStyle, PointedMessage,
-    Severity,
+    Confidence, Fix, LogReport, LogReportMetadata, LogReportPointers, LogReportStyle,
+    PointedMessage, Severity,
 };
-pub use suppress::suppress_from_json;
+pub(crate) use writer::kind_tag;
+pub(crate) use writer_json::{game_id, location_json};
+pub(crate) use writer_xml::xml_escape;
 
+mod baseline;
 mod builder;
+mod changed_lines;
 mod error_key;
 mod error_loc;
 mod errors;
 mod filter;
+mod fix;
 mod output_style;
 mod report_struct;
-mod suppress;
 mod writer;
 mod writer_json;
+mod writer_sarif;
+mod writer_xml;
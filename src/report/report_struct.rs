@@ -29,6 +29,30 @@ pub struct LogReportMetadata {
     pub wiki: Option<String>,
     // /// Output style for this report
     pub style: LogReportStyle,
+    /// Machine-applicable fixes for the problem. Applying all of them should resolve the report.
+    pub fixes: Vec<Fix>,
+}
+
+/// A replacement of some text in a file, that fixes the problem described by a report.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Fix {
+    /// Where the text to replace starts.
+    pub loc: Loc,
+    /// The length of the text to replace, in characters, if it's all on one line.
+    pub length: usize,
+    /// Where the text to replace ends, if it goes on past the line it starts on. It's the line and
+    /// the column just after the last character, so `(line, 1)` ends the text before that line.
+    pub end: Option<(u32, u32)>,
+    /// The text to put in its place.
+    pub replacement: String,
+}
+
+impl Fix {
+    /// Return the line and column just after the end of the text to replace.
+    #[allow(clippy::cast_possible_truncation)] // fixes are within a line
+    pub fn end(&self) -> (u32, u32) {
+        self.end.unwrap_or((self.loc.line, self.loc.column + self.length as u32))
+    }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -1,52 +1,169 @@
-use std::io::Write;
+//! Write the reports as a JSON document. The format is described in `json.md`, and changes to it
+//! must bump [`JSON_SCHEMA_VERSION`].
 
-use serde_json::json;
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io::{Cursor, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use murmur3::murmur3_x64_128;
+use serde_json::{Value, json};
+use strum::IntoEnumIterator;
 
 use crate::fileset::FileStage;
+use crate::game::Game;
 use crate::report::errors::Errors;
 use crate::report::writer::kind_tag;
-use crate::report::{LogReportMetadata, LogReportPointers};
+use crate::report::{LogReportMetadata, LogReportPointers, Severity};
+use crate::token::Loc;
+
+/// The version of the JSON output format. It is increased whenever a field is removed or changes
+/// meaning. Adding fields does not change the version.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
 
-/// Log the report in JSON format.
-pub fn log_report_json<O: Write + Send>(
+/// Log all the reports as one JSON document, with information about the run around them.
+pub fn log_reports_json<O: Write + Send>(
     errors: &Errors,
     output: &mut O,
-    report: &LogReportMetadata,
-    pointers: &LogReportPointers,
+    reports: &[(&LogReportMetadata, Cow<'_, LogReportPointers>, usize)],
 ) {
-    let pointers: Vec<_> = pointers
+    let mut summary = serde_json::Map::new();
+    for severity in Severity::iter() {
+        let count = reports.iter().filter(|(report, _, _)| report.severity == severity).count();
+        summary.insert(severity.to_string(), json!(count));
+    }
+    let reports: Vec<Value> =
+        reports.iter().map(|(report, pointers, _)| report_json(errors, report, pointers)).collect();
+    let document = json!({
+        "schema_version": JSON_SCHEMA_VERSION,
+        "tiger_version": env!("CARGO_PKG_VERSION"),
+        "game": game_id(),
+        "mod_path": errors.mod_root,
+        "timestamp": timestamp(SystemTime::now()),
+        "summary": summary,
+        "reports": reports,
+    });
+
+    if let Err(e) = serde_json::to_writer_pretty(&mut *output, &document) {
+        eprintln!("JSON error: {e:#}");
+    }
+    _ = writeln!(output);
+}
+
+/// Build the JSON description of one report.
+fn report_json(errors: &Errors, report: &LogReportMetadata, pointers: &LogReportPointers) -> Value {
+    let locations: Vec<_> = pointers
         .iter()
         .map(|pointer| {
-            let path = pointer.loc.pathname();
-            let fullpath = pointer.loc.fullpath();
+            let mut location = location_json(errors, pointer.loc);
+            location["length"] =
+                json!(if pointer.length == 0 { None } else { Some(pointer.length) });
+            location["line"] = json!(errors.cache.get_line(pointer.loc));
+            location["tag"] = json!(pointer.msg);
+            location
+        })
+        .collect();
+    let fixes: Vec<_> = report
+        .fixes
+        .iter()
+        .map(|fix| {
+            let (end_linenr, end_column) = fix.end();
             json!({
-                "path": path,
-                "from": kind_tag(errors, pointer.loc.kind),
-                "stage": stage_desc(pointer.loc.stage),
-                "fullpath": fullpath,
-                "linenr": if pointer.loc.line == 0 { None } else { Some(pointer.loc.line) },
-                "column": if pointer.loc.column == 0 { None } else { Some(pointer.loc.column) },
-                "length": if pointer.length == 0 { None } else { Some(pointer.length) },
-                "line": errors.cache.get_line(pointer.loc),
-                "tag": pointer.msg,
+                "path": fix.loc.pathname(),
+                "fullpath": fix.loc.fullpath(),
+                "linenr": fix.loc.line,
+                "column": fix.loc.column,
+                "length": fix.length,
+                "end_linenr": end_linenr,
+                "end_column": end_column,
+                "replacement": &fix.replacement,
             })
         })
         .collect();
-    let report = json!({
+    json!({
+        "id": report_id(errors, report, pointers),
         "severity": report.severity,
         "confidence": report.confidence,
         "key": report.key,
         "message": &report.msg,
         "info": &report.info,
         "wiki": &report.wiki,
-        "locations": pointers,
-    });
+        "locations": locations,
+        "fixes": fixes,
+    })
+}
 
-    if let Err(e) = serde_json::to_writer_pretty(output, &report) {
-        eprintln!("JSON error: {e:#}");
+/// Build the JSON description of a location, with the fields that all locations have.
+pub(crate) fn location_json(errors: &Errors, loc: Loc) -> Value {
+    json!({
+        "path": loc.pathname(),
+        "from": kind_tag(errors, loc.kind),
+        "stage": stage_desc(loc.stage),
+        "fullpath": loc.fullpath(),
+        "linenr": if loc.line == 0 { None } else { Some(loc.line) },
+        "column": if loc.column == 0 { None } else { Some(loc.column) },
+    })
+}
+
+/// Make an id for the report that stays the same between runs, as long as the report's key,
+/// message and locations are the same. Locations are identified by their path relative to the
+/// mod or game root, so the id does not depend on where those are installed.
+pub(crate) fn report_id(
+    errors: &Errors,
+    report: &LogReportMetadata,
+    pointers: &LogReportPointers,
+) -> String {
+    let mut text = format!("{}\0{}", report.key, report.msg);
+    for pointer in pointers {
+        let loc = pointer.loc;
+        let path = loc.pathname().to_string_lossy().replace('\\', "/");
+        _ = write!(text, "\0{}:{path}:{}:{}", kind_tag(errors, loc.kind), loc.line, loc.column);
+    }
+    // Reading from a Cursor can't fail.
+    let hash = murmur3_x64_128(&mut Cursor::new(text.as_bytes()), 0).unwrap();
+    format!("{:016x}", hash & u128::from(u64::MAX))
+}
+
+pub(crate) fn game_id() -> &'static str {
+    match Game::game() {
+        #[cfg(feature = "ck3")]
+        Game::Ck3 => "ck3",
+        #[cfg(feature = "vic3")]
+        Game::Vic3 => "vic3",
+        #[cfg(feature = "imperator")]
+        Game::Imperator => "imperator",
+        #[cfg(feature = "eu5")]
+        Game::Eu5 => "eu5",
+        #[cfg(feature = "hoi4")]
+        Game::Hoi4 => "hoi4",
     }
 }
 
+/// Format the time as an RFC 3339 timestamp in UTC, such as `2024-05-01T12:34:56Z`.
+#[allow(clippy::integer_division)] // the calendar calculations rely on it
+fn timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let days = secs / 86400;
+    let secs_of_day = secs % 86400;
+    // Convert the day count to a civil date, using the algorithm from
+    // <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
 fn stage_desc(stage: FileStage) -> Option<&'static str> {
     match stage {
         #[cfg(feature = "eu5")]
@@ -58,3 +175,17 @@ fn stage_desc(stage: FileStage) -> Option<&'static str> {
         FileStage::NoStage => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_210_096);
+        assert_eq!(timestamp(time), "2024-02-29T12:34:56Z");
+    }
+}
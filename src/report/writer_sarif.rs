@@ -0,0 +1,184 @@
+//! Write the reports as a SARIF 2.1.0 log, which code scanning tools such as GitHub's and
+//! GitLab's can show natively.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use serde_json::{Value, json};
+
+use crate::fileset::FileKind;
+use crate::helpers::TigerHashMap;
+use crate::report::{
+    Confidence, ErrorKey, Fix, LogReportMetadata, LogReportPointers, PointedMessage, Severity,
+};
+use crate::token::Loc;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Log all the reports as one SARIF document.
+pub fn log_reports_sarif<O: Write + Send>(
+    output: &mut O,
+    reports: &[(&LogReportMetadata, Cow<'_, LogReportPointers>, usize)],
+) {
+    // One rule per error key, in order of first appearance. The help texts come from the first
+    // report of that key that has them.
+    let mut rules: Vec<Value> = Vec::new();
+    let mut rule_index: TigerHashMap<ErrorKey, usize> = TigerHashMap::default();
+    for (report, _, _) in reports {
+        let index = *rule_index.entry(report.key).or_insert_with(|| {
+            rules.push(json!({
+                "id": report.key.to_string(),
+                "defaultConfiguration": { "level": sarif_level(report.severity) },
+            }));
+            rules.len() - 1
+        });
+        let rule = rules[index].as_object_mut().unwrap();
+        if let Some(info) = &report.info {
+            rule.entry("help").or_insert_with(|| json!({ "text": info }));
+        }
+        if let Some(wiki) = &report.wiki {
+            rule.entry("helpUri").or_insert_with(|| json!(wiki));
+        }
+    }
+
+    let results: Vec<Value> = reports
+        .iter()
+        .map(|(report, pointers, _)| {
+            let mut result = json!({
+                "ruleId": report.key.to_string(),
+                "ruleIndex": rule_index[&report.key],
+                "level": sarif_level(report.severity),
+                "rank": sarif_rank(report.confidence),
+                "message": { "text": &report.msg },
+            });
+            let result_map = result.as_object_mut().unwrap();
+            if let Some(first) = pointers.first() {
+                result_map.insert("locations".to_owned(), json!([sarif_location(first, None)]));
+            }
+            if pointers.len() > 1 {
+                let related: Vec<Value> = pointers
+                    .iter()
+                    .enumerate()
+                    .skip(1)
+                    .map(|(i, pointer)| sarif_location(pointer, Some(i)))
+                    .collect();
+                result_map.insert("relatedLocations".to_owned(), json!(related));
+            }
+            if !report.fixes.is_empty() {
+                let changes: Vec<Value> = report.fixes.iter().map(sarif_change).collect();
+                result_map.insert("fixes".to_owned(), json!([{ "artifactChanges": changes }]));
+            }
+            result
+        })
+        .collect();
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "tiger",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/amtep/tiger",
+                    "rules": rules,
+                },
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }],
+    });
+
+    if let Err(e) = serde_json::to_writer_pretty(&mut *output, &log) {
+        eprintln!("JSON error: {e:#}");
+    }
+    _ = writeln!(output);
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Fatal | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Untidy | Severity::Tips => "note",
+    }
+}
+
+/// SARIF ranks go from 0.0 to 100.0, with higher meaning more important.
+fn sarif_rank(confidence: Confidence) -> f64 {
+    match confidence {
+        Confidence::Weak => 25.0,
+        Confidence::Reasonable => 50.0,
+        Confidence::Strong => 75.0,
+    }
+}
+
+/// Build a SARIF location for a pointer. Related locations need an `id`, which is `index`.
+fn sarif_location(pointer: &PointedMessage, index: Option<usize>) -> Value {
+    let loc = pointer.loc;
+    let mut physical = json!({ "artifactLocation": sarif_artifact(loc) });
+    if loc.line > 0 {
+        let mut region = json!({ "startLine": loc.line });
+        if loc.column > 0 {
+            region["startColumn"] = json!(loc.column);
+            if pointer.length > 0 {
+                region["endColumn"] = json!(loc.column as usize + pointer.length);
+            }
+        }
+        physical["region"] = region;
+    }
+    let mut location = json!({ "physicalLocation": physical });
+    if let Some(index) = index {
+        location["id"] = json!(index);
+    }
+    if let Some(msg) = &pointer.msg {
+        location["message"] = json!({ "text": msg });
+    }
+    location
+}
+
+/// Build a SARIF artifact change that replaces the text of a fix.
+fn sarif_change(fix: &Fix) -> Value {
+    let (end_line, end_column) = fix.end();
+    let region = json!({
+        "startLine": fix.loc.line,
+        "startColumn": fix.loc.column,
+        "endLine": end_line,
+        "endColumn": end_column,
+    });
+    json!({
+        "artifactLocation": sarif_artifact(fix.loc),
+        "replacements": [{
+            "deletedRegion": region,
+            "insertedContent": { "text": &fix.replacement },
+        }],
+    })
+}
+
+/// Files in the mod are given relative to the mod root, which is usually the root of the
+/// repository being scanned. Other files are given by their full path.
+fn sarif_artifact(loc: Loc) -> Value {
+    if loc.kind == FileKind::Mod {
+        json!({ "uri": uri_escape(&loc.pathname().to_string_lossy()), "uriBaseId": "%SRCROOT%" })
+    } else {
+        let fullpath = loc.fullpath().to_string_lossy().replace('\\', "/");
+        let separator = if fullpath.starts_with('/') { "" } else { "/" };
+        json!({ "uri": format!("file://{separator}{}", uri_escape(&fullpath)) })
+    }
+}
+
+/// Percent-encode the characters of a path that are not allowed in a URI.
+fn uri_escape(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.replace('\\', "/").chars() {
+        if c.is_ascii_alphanumeric() || "/-._~:".contains(c) {
+            escaped.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                _ = write!(escaped, "%{byte:02X}");
+            }
+        }
+    }
+    escaped
+}
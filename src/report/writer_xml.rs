@@ -0,0 +1,154 @@
+//! Write the reports in the XML formats that CI servers understand: `JUnit` and Checkstyle.
+
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use crate::fileset::FileKind;
+use crate::helpers::TigerHashMap;
+use crate::report::{LogReportMetadata, LogReportPointers, PointedMessage, Severity};
+use crate::token::Loc;
+
+type FlatReport<'a> = (&'a LogReportMetadata, Cow<'a, LogReportPointers>, usize);
+
+/// Log all the reports as one `JUnit` XML document, with a testcase per file and a failure per
+/// report in that file.
+pub fn log_reports_junit<O: Write + Send>(output: &mut O, reports: &[FlatReport]) {
+    let files = group_by_file(reports);
+    _ = writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    _ = writeln!(
+        output,
+        r#"<testsuites name="tiger" tests="{}" failures="{}">"#,
+        files.len(),
+        reports.len()
+    );
+    _ = writeln!(
+        output,
+        r#"  <testsuite name="tiger" tests="{}" failures="{}">"#,
+        files.len(),
+        reports.len()
+    );
+    for (path, reports) in &files {
+        _ = writeln!(output, r#"    <testcase name="{}" classname="tiger">"#, xml_escape(path));
+        for (report, pointers, _) in reports {
+            _ = writeln!(
+                output,
+                r#"      <failure message="{}" type="{}">{}</failure>"#,
+                xml_escape(&report.msg),
+                xml_escape(&format!("{}({})", report.severity, report.key)),
+                xml_escape(&failure_details(report, pointers)),
+            );
+        }
+        _ = writeln!(output, "    </testcase>");
+    }
+    _ = writeln!(output, "  </testsuite>");
+    _ = writeln!(output, "</testsuites>");
+}
+
+/// Log all the reports as one Checkstyle XML document.
+pub fn log_reports_checkstyle<O: Write + Send>(output: &mut O, reports: &[FlatReport]) {
+    _ = writeln!(output, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    _ = writeln!(output, r#"<checkstyle version="4.3">"#);
+    for (path, reports) in &group_by_file(reports) {
+        _ = writeln!(output, r#"  <file name="{}">"#, xml_escape(path));
+        for (report, pointers, _) in reports {
+            let loc = pointers.first().map(|p| p.loc);
+            let mut attributes = String::new();
+            if let Some(loc) = loc.filter(|loc| loc.line > 0) {
+                _ = write!(attributes, r#" line="{}""#, loc.line);
+                if loc.column > 0 {
+                    _ = write!(attributes, r#" column="{}""#, loc.column);
+                }
+            }
+            let mut message = report.msg.clone();
+            if let Some(info) = &report.info {
+                message.push_str(": ");
+                message.push_str(info);
+            }
+            _ = writeln!(
+                output,
+                r#"    <error{attributes} severity="{}" message="{}" source="tiger.{}"/>"#,
+                checkstyle_severity(report.severity),
+                xml_escape(&message),
+                report.key,
+            );
+        }
+        _ = writeln!(output, "  </file>");
+    }
+    _ = writeln!(output, "</checkstyle>");
+}
+
+/// Group the reports by the file of their first pointer, keeping the order in which the files
+/// first appear.
+fn group_by_file<'a>(reports: &'a [FlatReport<'a>]) -> Vec<(String, Vec<&'a FlatReport<'a>>)> {
+    let mut files: Vec<(String, Vec<&FlatReport>)> = Vec::new();
+    let mut index: TigerHashMap<String, usize> = TigerHashMap::default();
+    for report in reports {
+        let path = report.1.first().map_or_else(String::new, |p| report_path(p.loc));
+        let i = *index.entry(path.clone()).or_insert_with(|| {
+            files.push((path, Vec::new()));
+            files.len() - 1
+        });
+        files[i].1.push(report);
+    }
+    files
+}
+
+/// Files in the mod are given relative to the mod root, which is usually the root of the
+/// repository being checked. Other files are given by their full path.
+fn report_path(loc: Loc) -> String {
+    if loc.kind == FileKind::Mod {
+        loc.pathname().to_string_lossy().replace('\\', "/")
+    } else {
+        loc.fullpath().to_string_lossy().into_owned()
+    }
+}
+
+/// The body of a `JUnit` failure: where the problem is, and any extra information about it.
+fn failure_details(report: &LogReportMetadata, pointers: &[PointedMessage]) -> String {
+    let mut details = String::new();
+    for pointer in pointers {
+        _ = write!(details, "{}", report_path(pointer.loc));
+        if pointer.loc.line > 0 {
+            _ = write!(details, ":{}:{}", pointer.loc.line, pointer.loc.column);
+        }
+        if let Some(msg) = &pointer.msg {
+            _ = write!(details, ": {msg}");
+        }
+        details.push('\n');
+    }
+    if let Some(info) = &report.info {
+        _ = writeln!(details, "Info: {info}");
+    }
+    if let Some(wiki) = &report.wiki {
+        _ = writeln!(details, "Wiki: {wiki}");
+    }
+    details
+}
+
+fn checkstyle_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Fatal | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Untidy | Severity::Tips => "info",
+    }
+}
+
+/// Escape the characters that are special in XML text and attribute values.
+pub(crate) fn xml_escape(s: &str) -> Cow<'_, str> {
+    if !s.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(s);
+    }
+    let mut escaped = String::with_capacity(s.len() + 16);
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
@@ -475,20 +475,13 @@ pub enum ArgumentValue {
 ///
 /// Returns a pair of `Scopes`. The first is the scope types this token can accept as input,
 /// and the second is the scope types it may return.
+/// Event links that are only known from the game's `script_docs` logs are accepted too.
 #[allow(unused_variables)] // inscopes is only used for vic3
-pub fn scope_to_scope(name: &Token, inscopes: Scopes) -> Option<(Scopes, Scopes)> {
-    let scope_to_scope = match Game::game() {
-        #[cfg(feature = "ck3")]
-        Game::Ck3 => crate::ck3::tables::targets::scope_to_scope,
-        #[cfg(feature = "vic3")]
-        Game::Vic3 => crate::vic3::tables::targets::scope_to_scope,
-        #[cfg(feature = "imperator")]
-        Game::Imperator => crate::imperator::tables::targets::scope_to_scope,
-        #[cfg(feature = "eu5")]
-        Game::Eu5 => crate::eu5::tables::targets::scope_to_scope,
-        #[cfg(feature = "hoi4")]
-        Game::Hoi4 => crate::hoi4::tables::targets::scope_to_scope,
-    };
+pub fn scope_to_scope(
+    name: &Token,
+    inscopes: Scopes,
+    data: &Everything,
+) -> Option<(Scopes, Scopes)> {
     let scope_to_scope_removed = match Game::game() {
         #[cfg(feature = "ck3")]
         Game::Ck3 => crate::ck3::tables::targets::scope_to_scope_removed,
@@ -504,7 +497,7 @@ pub fn scope_to_scope(name: &Token, inscopes: Scopes) -> Option<(Scopes, Scopes)
 
     let name_lc = name.as_str().to_ascii_lowercase();
     #[allow(unused_assignments)] // `from` only used for vic3
-    if let scopes @ Some((from, _)) = scope_to_scope(&name_lc) {
+    if let scopes @ Some((from, _)) = builtin_scope_to_scope(&name_lc) {
         #[cfg(feature = "vic3")]
         if Game::is_vic3() && name_lc == "type" {
             // Special case for "type" because it goes from specific scope types to specific
@@ -550,10 +543,27 @@ pub fn scope_to_scope(name: &Token, inscopes: Scopes) -> Option<(Scopes, Scopes)
         err(ErrorKey::Removed).strong().msg(msg).info(explanation).loc(name).push();
         Some((Scopes::all(), Scopes::all_but_none()))
     } else {
-        None
+        data.script_docs.target(&name_lc)
     }
 }
 
+/// Look up an event link in the built-in tables. `name_lc` must be lowercase.
+pub(crate) fn builtin_scope_to_scope(name_lc: &str) -> Option<(Scopes, Scopes)> {
+    let scope_to_scope = match Game::game() {
+        #[cfg(feature = "ck3")]
+        Game::Ck3 => crate::ck3::tables::targets::scope_to_scope,
+        #[cfg(feature = "vic3")]
+        Game::Vic3 => crate::vic3::tables::targets::scope_to_scope,
+        #[cfg(feature = "imperator")]
+        Game::Imperator => crate::imperator::tables::targets::scope_to_scope,
+        #[cfg(feature = "eu5")]
+        Game::Eu5 => crate::eu5::tables::targets::scope_to_scope,
+        #[cfg(feature = "hoi4")]
+        Game::Hoi4 => crate::hoi4::tables::targets::scope_to_scope,
+    };
+    scope_to_scope(name_lc)
+}
+
 /// Look up a prefixed token that is used to look up items in the game database.
 ///
 /// For example, `character:alexander_the_great` to fetch that character as a scope value.
@@ -611,23 +621,13 @@ pub fn needs_prefix(arg: &str, data: &Everything, scopes: Scopes) -> Option<&'st
 /// Returns a pair of `Scopes`. The first is the scope types this token can accept as input,
 /// and the second is the scope types it may return.
 /// The first will be `Scopes::None` if it needs no input.
+///
+/// Iterators that are only known from the game's `script_docs` logs are accepted too.
 pub fn scope_iterator(
     name: &Token,
     data: &Everything,
     sc: &mut ScopeContext,
 ) -> Option<(Scopes, Scopes)> {
-    let scope_iterator = match Game::game() {
-        #[cfg(feature = "ck3")]
-        Game::Ck3 => crate::ck3::tables::iterators::iterator,
-        #[cfg(feature = "vic3")]
-        Game::Vic3 => crate::vic3::tables::iterators::iterator,
-        #[cfg(feature = "imperator")]
-        Game::Imperator => crate::imperator::tables::iterators::iterator,
-        #[cfg(feature = "eu5")]
-        Game::Eu5 => crate::eu5::tables::iterators::iterator,
-        #[cfg(feature = "hoi4")]
-        Game::Hoi4 => crate::hoi4::tables::iterators::iterator,
-    };
     let scope_iterator_removed = match Game::game() {
         #[cfg(feature = "ck3")]
         Game::Ck3 => crate::ck3::tables::iterators::iterator_removed,
@@ -641,10 +641,10 @@ pub fn scope_iterator(
         Game::Hoi4 => crate::hoi4::tables::iterators::iterator_removed,
     };
 
-    let name_lc = Lowercase::new(name.as_str());
-    if let scopes @ Some(_) = scope_iterator(&name_lc, name, data) {
+    if let scopes @ Some(_) = builtin_scope_iterator(name, data) {
         return scopes;
     }
+    let name_lc = Lowercase::new(name.as_str());
     if let Some((version, explanation)) = scope_iterator_removed(name_lc.as_str()) {
         let msg = format!("`{name}` iterators were removed in {version}");
         err(ErrorKey::Removed).strong().msg(msg).info(explanation).loc(name).push();
@@ -653,14 +653,26 @@ pub fn scope_iterator(
     #[cfg(feature = "jomini")]
     if Game::is_jomini() && data.scripted_lists.exists(name.as_str()) {
         data.scripted_lists.validate_call(name, data, sc);
-        return data
-            .scripted_lists
-            .base(name)
-            .and_then(|base| scope_iterator(&Lowercase::new(base.as_str()), base, data));
+        return data.scripted_lists.base(name).and_then(|base| builtin_scope_iterator(base, data));
     }
     #[cfg(feature = "hoi4")]
-    let _ = &data; // mark parameter used
-    #[cfg(feature = "hoi4")]
     let _ = &sc; // mark parameter used
-    None
+    data.script_docs.iterator(name_lc.as_str())
+}
+
+/// Look up an iterator in the built-in tables.
+pub(crate) fn builtin_scope_iterator(name: &Token, data: &Everything) -> Option<(Scopes, Scopes)> {
+    let scope_iterator = match Game::game() {
+        #[cfg(feature = "ck3")]
+        Game::Ck3 => crate::ck3::tables::iterators::iterator,
+        #[cfg(feature = "vic3")]
+        Game::Vic3 => crate::vic3::tables::iterators::iterator,
+        #[cfg(feature = "imperator")]
+        Game::Imperator => crate::imperator::tables::iterators::iterator,
+        #[cfg(feature = "eu5")]
+        Game::Eu5 => crate::eu5::tables::iterators::iterator,
+        #[cfg(feature = "hoi4")]
+        Game::Hoi4 => crate::hoi4::tables::iterators::iterator,
+    };
+    scope_iterator(&Lowercase::new(name.as_str()), name, data)
 }
@@ -0,0 +1,368 @@
+//! Reading the logs that the games write with their `script_docs` console command.
+//!
+//! The built-in tables of triggers, effects, iterators and event targets are generated from these
+//! logs when tiger is updated for a new game version. Reading the logs at runtime covers the time
+//! in between: names that the built-in tables don't know are accepted with the scopes that the
+//! logs give for them, and the entries that disagree with the built-in tables are reported.
+
+use std::fs::read;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::effect::{Effect, builtin_scope_effect};
+use crate::everything::Everything;
+use crate::fileset::{FileKind, FileStage};
+use crate::helpers::{TigerHashMap, TigerHashSet};
+use crate::report::{ErrorKey, tips};
+use crate::scopes::{Scopes, builtin_scope_iterator, builtin_scope_to_scope};
+use crate::token::{Loc, Token};
+#[cfg(any(feature = "ck3", feature = "vic3", feature = "eu5"))]
+use crate::trigger::Trigger;
+use crate::trigger::builtin_scope_trigger;
+
+/// The prefixes that turn an iterator name into a trigger or effect.
+const ITERATOR_EFFECT_PREFIXES: &[&str] = &["every_", "random_", "ordered_"];
+
+/// The triggers, effects, iterators, event targets and modifiers listed in the `script_docs` logs.
+///
+/// All the maps are keyed by the lowercased name. The token is the name as written in the log,
+/// pointing at its line there.
+#[derive(Debug, Default)]
+pub(crate) struct ScriptDocs {
+    /// Triggers and their supported scopes, if the log gave them.
+    triggers: TigerHashMap<String, (Token, Option<Scopes>)>,
+    /// Effects and their supported scopes, if the log gave them.
+    effects: TigerHashMap<String, (Token, Option<Scopes>)>,
+    /// Iterators without their `any_`, `every_`, `random_` or `ordered_` prefix, and their
+    /// supported scopes and target scopes, if the log gave them.
+    iterators: TigerHashMap<String, (Token, Option<Scopes>, Option<Scopes>)>,
+    /// Event targets and their input and output scopes, if the log gave them.
+    targets: TigerHashMap<String, (Token, Option<Scopes>, Option<Scopes>)>,
+    modifiers: TigerHashMap<String, Token>,
+}
+
+impl ScriptDocs {
+    /// Read the log files in `dir`, replacing whatever was read before.
+    ///
+    /// Returns the number of log files found, which is 0 if `dir` has none of them.
+    pub(crate) fn load(&mut self, dir: &Path) -> io::Result<usize> {
+        *self = ScriptDocs::default();
+        let mut found = 0;
+
+        let triggers = read_log(dir, "triggers.log", &mut found)?;
+        let effects = read_log(dir, "effects.log", &mut found)?;
+        let targets = read_log(dir, "event_targets.log", &mut found)?;
+        let modifiers = read_log(dir, "modifiers.log", &mut found)?;
+
+        // An iterator is documented as its `any_` trigger plus its `every_`, `random_` and
+        // `ordered_` effects.
+        let effect_names: TigerHashSet<String> =
+            effects.iter().map(|entry| entry.name.as_str().to_ascii_lowercase()).collect();
+        let mut iterator_names = TigerHashSet::default();
+        for entry in triggers {
+            let name_lc = entry.name.as_str().to_ascii_lowercase();
+            let supported = entry.scopes("Supported Scopes");
+            if let Some(iterator) = name_lc.strip_prefix("any_")
+                && ITERATOR_EFFECT_PREFIXES
+                    .iter()
+                    .all(|prefix| effect_names.contains(&format!("{prefix}{iterator}")))
+            {
+                let mut loc = entry.name.loc;
+                loc.column += 4;
+                let token = entry.name.subtoken(4.., loc);
+                let targets = entry.scopes("Supported Targets");
+                iterator_names.insert(iterator.to_owned());
+                self.iterators.insert(iterator.to_owned(), (token, supported, targets));
+            } else {
+                self.triggers.insert(name_lc, (entry.name, supported));
+            }
+        }
+        for entry in effects {
+            let name_lc = entry.name.as_str().to_ascii_lowercase();
+            let is_iterator = ITERATOR_EFFECT_PREFIXES.iter().any(|prefix| {
+                name_lc.strip_prefix(prefix).is_some_and(|name| iterator_names.contains(name))
+            });
+            if !is_iterator {
+                let supported = entry.scopes("Supported Scopes");
+                self.effects.insert(name_lc, (entry.name, supported));
+            }
+        }
+        for entry in targets {
+            // These are prefixes like `title:`, which the built-in tables handle separately.
+            if entry.field("Requires Data").is_some_and(|value| value.eq_ignore_ascii_case("yes")) {
+                continue;
+            }
+            let inscopes = if entry
+                .field("Global Link")
+                .is_some_and(|value| value.eq_ignore_ascii_case("yes"))
+            {
+                Some(Scopes::None)
+            } else {
+                entry.scopes("Input Scopes")
+            };
+            let outscopes = entry.scopes("Output Scopes");
+            let name_lc = entry.name.as_str().to_ascii_lowercase();
+            self.targets.insert(name_lc, (entry.name, inscopes, outscopes));
+        }
+        for entry in modifiers {
+            self.modifiers.insert(entry.name.as_str().to_ascii_lowercase(), entry.name);
+        }
+        Ok(found)
+    }
+
+    /// Return the supported scopes of trigger `name`, if the logs list it.
+    pub(crate) fn trigger(&self, name: &str) -> Option<Scopes> {
+        lookup(&self.triggers, name).map(|(_, scopes)| scopes.unwrap_or(Scopes::all()))
+    }
+
+    /// Return the supported scopes of effect `name`, if the logs list it.
+    pub(crate) fn effect(&self, name: &str) -> Option<Scopes> {
+        lookup(&self.effects, name).map(|(_, scopes)| scopes.unwrap_or(Scopes::all()))
+    }
+
+    /// Return the supported scopes and target scopes of iterator `name`, if the logs list it.
+    /// `name` is without the `any_`, `every_`, `random_` or `ordered_` prefix.
+    pub(crate) fn iterator(&self, name: &str) -> Option<(Scopes, Scopes)> {
+        lookup(&self.iterators, name).map(|(_, inscopes, outscopes)| {
+            (inscopes.unwrap_or(Scopes::all()), outscopes.unwrap_or(Scopes::all()))
+        })
+    }
+
+    /// Return the input and output scopes of event target `name`, if the logs list it.
+    pub(crate) fn target(&self, name: &str) -> Option<(Scopes, Scopes)> {
+        lookup(&self.targets, name).map(|(_, inscopes, outscopes)| {
+            (inscopes.unwrap_or(Scopes::all()), outscopes.unwrap_or(Scopes::all_but_none()))
+        })
+    }
+
+    /// Return whether the logs list modifier `name`.
+    #[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator", feature = "hoi4"))]
+    pub(crate) fn has_modifier(&self, name: &str) -> bool {
+        lookup(&self.modifiers, name).is_some()
+    }
+
+    /// Report the entries whose scopes disagree with the built-in tables.
+    ///
+    /// Built-in entries that accept any scope are not compared, because they are deliberately
+    /// left unchecked.
+    pub(crate) fn validate(&self, data: &Everything) {
+        for (name, supported) in self.triggers.values() {
+            if let Some(supported) = *supported
+                && let Some((builtin, trigger)) = builtin_scope_trigger(name, data)
+            {
+                #[cfg(any(feature = "ck3", feature = "vic3", feature = "eu5"))]
+                if matches!(trigger, Trigger::Removed(..)) {
+                    continue;
+                }
+                #[cfg(not(any(feature = "ck3", feature = "vic3", feature = "eu5")))]
+                let _ = trigger;
+                report_disagreement("trigger", name, "supported scopes", supported, builtin);
+            }
+        }
+        for (name, supported) in self.effects.values() {
+            if let Some(supported) = *supported
+                && let Some((builtin, effect)) = builtin_scope_effect(name, data)
+                && !matches!(effect, Effect::Removed(..))
+            {
+                report_disagreement("effect", name, "supported scopes", supported, builtin);
+            }
+        }
+        for (name, supported, targets) in self.iterators.values() {
+            if let Some((builtin_in, builtin_out)) = builtin_scope_iterator(name, data) {
+                if let Some(supported) = *supported {
+                    report_disagreement(
+                        "iterator",
+                        name,
+                        "supported scopes",
+                        supported,
+                        builtin_in,
+                    );
+                }
+                if let Some(targets) = *targets {
+                    report_disagreement("iterator", name, "target scopes", targets, builtin_out);
+                }
+            }
+        }
+        for (name, inscopes, outscopes) in self.targets.values() {
+            if let Some((builtin_in, builtin_out)) =
+                builtin_scope_to_scope(&name.as_str().to_ascii_lowercase())
+            {
+                if let Some(inscopes) = *inscopes {
+                    report_disagreement("event target", name, "input scopes", inscopes, builtin_in);
+                }
+                if let Some(outscopes) = *outscopes {
+                    report_disagreement(
+                        "event target",
+                        name,
+                        "output scopes",
+                        outscopes,
+                        builtin_out,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Look up `name` in one of the [`ScriptDocs`] maps, without allocating if the map is empty.
+fn lookup<'a, T>(map: &'a TigerHashMap<String, T>, name: &str) -> Option<&'a T> {
+    if map.is_empty() {
+        return None;
+    }
+    map.get(&name.to_ascii_lowercase())
+}
+
+fn report_disagreement(what: &str, name: &Token, field: &str, logged: Scopes, builtin: Scopes) {
+    if logged != builtin && builtin != Scopes::all() {
+        let msg = format!(
+            "the game's log says the {field} of {what} `{name}` are {logged}, but tiger has {builtin}"
+        );
+        let info = "tiger keeps using its own scopes for it; if the game changed, please report it";
+        tips(ErrorKey::ScriptDocs).msg(msg).info(info).loc(name).push();
+    }
+}
+
+/// One entry from a log file, with the fields that were given for it.
+#[derive(Debug)]
+struct LogEntry {
+    name: Token,
+    fields: Vec<(String, String)>,
+}
+
+impl LogEntry {
+    fn new(name: &str, column: usize, mut loc: Loc) -> Option<Self> {
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+        loc.column = u32::try_from(column + 1).unwrap_or(u32::MAX);
+        Some(LogEntry { name: Token::new(name, loc), fields: Vec::new() })
+    }
+
+    /// Record a `Key: value` line. Lines that are not like that are description text.
+    fn add_field(&mut self, line: &str) {
+        if let Some((key, value)) = line.split_once(':') {
+            let key = key.trim().trim_matches('*').trim();
+            let value = value.trim().trim_start_matches('*').trim();
+            self.fields.push((key.to_owned(), value.to_owned()));
+        }
+    }
+
+    fn field(&self, key: &str) -> Option<&str> {
+        self.fields.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v.as_str())
+    }
+
+    /// Return the scope types listed in field `key`. Scope types that tiger doesn't know are
+    /// left out.
+    fn scopes(&self, key: &str) -> Option<Scopes> {
+        let mut scopes = Scopes::empty();
+        for part in self.field(key)?.split(',') {
+            if let Some(scope) = Scopes::from_snake_case(part.trim()) {
+                scopes |= scope;
+            }
+        }
+        (!scopes.is_empty()).then_some(scopes)
+    }
+}
+
+/// Read and parse the log file `filename` in `dir`, if it exists, and count it in `found`.
+fn read_log(dir: &Path, filename: &str, found: &mut usize) -> io::Result<Vec<LogEntry>> {
+    let fullpath = dir.join(filename);
+    if !fullpath.is_file() {
+        return Ok(Vec::new());
+    }
+    // The logs can contain invalid utf-8.
+    let contents = read(&fullpath)?;
+    *found += 1;
+    // The logs are written by the game, so reports about them are not about the mod.
+    let loc =
+        Loc::for_file(PathBuf::from(filename), FileStage::NoStage, FileKind::Vanilla, fullpath);
+    Ok(parse_log(&String::from_utf8_lossy(&contents), loc))
+}
+
+/// Parse the text of a log file into its entries.
+///
+/// CK3 and Imperator separate the entries with a line of dashes and start each with a
+/// `name - description` line, while Vic3 and EU5 write markdown with a `## name` heading for each
+/// entry. Modifiers are listed as `Tag: name, Categories: ...` lines. The fields of an entry are
+/// `Key: value` lines, where Vic3 and EU5 put the key in bold.
+fn parse_log(text: &str, mut loc: Loc) -> Vec<LogEntry> {
+    let mut entries = Vec::new();
+    let mut current: Option<LogEntry> = None;
+    let mut after_separator = false;
+    for (nr, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        loc.line = u32::try_from(nr + 1).unwrap_or(u32::MAX);
+        if line.len() >= 3 && line.bytes().all(|c| c == b'-') {
+            entries.extend(current.take());
+            after_separator = true;
+        } else if let Some(name) = line.strip_prefix("## ") {
+            entries.extend(current.take());
+            current = LogEntry::new(name, 3, loc);
+        } else if let Some(rest) = line.strip_prefix("Tag: ") {
+            entries.extend(current.take());
+            let (name, fields) = rest.split_once(", ").unwrap_or((rest, ""));
+            current = LogEntry::new(name, 5, loc);
+            if let Some(entry) = &mut current {
+                entry.add_field(fields);
+            }
+        } else if after_separator {
+            after_separator = false;
+            let name = line.split_once(" - ").map_or(line, |(name, _)| name);
+            current = LogEntry::new(name, 0, loc);
+        } else if let Some(entry) = &mut current {
+            entry.add_field(line);
+        }
+    }
+    entries.extend(current);
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_loc(filename: &str) -> Loc {
+        let path = PathBuf::from(filename);
+        Loc::for_file(path.clone(), FileStage::NoStage, FileKind::Mod, path)
+    }
+
+    fn names(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_log_dashes() {
+        let text = "Trigger Documentation:\n\
+            --------------------\n\
+            is_adult - is the character an adult?\n\
+            is_adult = yes\n\
+            Supported Scopes: character\n\
+            \n\
+            --------------------\n\
+            exists - Checks if a target exists\n\
+            Supported Scopes: none\n";
+        let entries = parse_log(text, test_loc("triggers.log"));
+        assert_eq!(names(&entries), ["is_adult", "exists"]);
+        assert_eq!(entries[0].field("Supported Scopes"), Some("character"));
+        assert_eq!(entries[0].name.loc.line, 3);
+        assert_eq!(entries[1].field("supported scopes"), Some("none"));
+    }
+
+    #[test]
+    fn test_parse_log_markdown() {
+        let text = "# Effects\n\
+            ## add_gold\n\
+            Adds gold\n\
+            **Supported Scopes**: country, state\n\
+            Tag: monthly_prestige, Categories: character\n";
+        let entries = parse_log(text, test_loc("effects.log"));
+        assert_eq!(names(&entries), ["add_gold", "monthly_prestige"]);
+        assert_eq!(entries[0].field("Supported Scopes"), Some("country, state"));
+        assert_eq!(entries[0].name.loc.column, 4);
+        assert_eq!(entries[1].field("Categories"), Some("character"));
+    }
+}
@@ -0,0 +1,164 @@
+//! A [`Session`] holds the state that belongs to one validation run: the collected reports, the
+//! path table, and the macro map.
+//!
+//! Several sessions can exist in one process, for example to validate two mods side by side.
+//! Code that runs inside a session finds it through [`SessionState::current`], so the state
+//! doesn't have to be passed around to every place that reports an error or looks up a path.
+//!
+//! The state of a session is never freed, because the references handed out to its reports and
+//! paths are `'static`. Instead, when the last handle to a session is dropped, its state is
+//! cleared and kept with its threads, to be used again by the next [`Session::new`]. A program
+//! thus only has as many sessions' worth of state and threads as it uses at the same time.
+
+use std::cell::Cell;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock, Weak};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::declared_signature::SignatureComments;
+use crate::macros::MacroMap;
+use crate::pathtable::PathTable;
+use crate::report::Errors;
+
+/// The state used by code that hasn't entered any session.
+static DEFAULT: LazyLock<SessionState> = LazyLock::new(SessionState::default);
+
+/// The states of dropped sessions, ready to be used again.
+static IDLE: Mutex<Vec<&'static SessionState>> = Mutex::new(Vec::new());
+
+thread_local! {
+    /// The session state that the current thread is working in, if any.
+    static CURRENT: Cell<Option<&'static SessionState>> = const { Cell::new(None) };
+}
+
+/// The state of one validation run.
+#[derive(Default)]
+pub(crate) struct SessionState {
+    pub(crate) errors: Mutex<Errors<'static>>,
+    pub(crate) macro_map: MacroMap,
+    pub(crate) pathtable: RwLock<PathTable>,
+    pub(crate) signature_comments: Mutex<SignatureComments>,
+    /// The threads that do this session's parallel work. They are all in this session.
+    /// The default state has none, and uses rayon's global pool.
+    pool: OnceLock<ThreadPool>,
+    /// The session that is using this state, or nothing if the state is idle or the default.
+    lease: Mutex<Weak<Lease>>,
+}
+
+impl Debug for SessionState {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.debug_struct("SessionState").finish_non_exhaustive()
+    }
+}
+
+impl SessionState {
+    /// Return the session state the current thread is working in.
+    pub(crate) fn current() -> &'static Self {
+        CURRENT.get().unwrap_or(&DEFAULT)
+    }
+
+    /// Return the session that is using this state, if any.
+    pub(crate) fn session(&self) -> Option<Session> {
+        self.lease.lock().ok()?.upgrade().map(Session)
+    }
+
+    /// Run `f` in this session state, and return its result.
+    ///
+    /// Parallel work started by `f` is done by this state's threads.
+    pub(crate) fn enter<R: Send, F: FnOnce() -> R + Send>(&'static self, f: F) -> R {
+        if let Some(pool) = self.pool.get() {
+            pool.install(f)
+        } else {
+            /// Restores the previous state even if `f` panics.
+            struct Restore(Option<&'static SessionState>);
+            impl Drop for Restore {
+                fn drop(&mut self) {
+                    CURRENT.set(self.0);
+                }
+            }
+            let _restore = Restore(CURRENT.replace(Some(self)));
+            f()
+        }
+    }
+}
+
+/// Holds a session state while a session uses it, and makes it idle when dropped.
+#[derive(Debug)]
+struct Lease(&'static SessionState);
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        let state = self.0;
+        // A state whose locks were poisoned by a panic is not used again.
+        let (Ok(mut errors), Ok(mut pathtable)) = (state.errors.lock(), state.pathtable.write())
+        else {
+            return;
+        };
+        *errors = Errors::default();
+        *pathtable = PathTable::default();
+        state.macro_map.clear();
+        if let Ok(mut signature_comments) = state.signature_comments.lock() {
+            signature_comments.clear();
+        }
+        if let Ok(mut idle) = IDLE.lock() {
+            idle.push(state);
+        }
+    }
+}
+
+/// A validation run, with its own reports and its own threads.
+///
+/// An [`Everything`](crate::Everything) belongs to the session that was current when it was
+/// created, and its methods do their work in that session. It keeps the session alive until it
+/// is dropped. The free functions that deal with reports, such as
+/// [`take_reports`](crate::take_reports) and [`emit_reports`](crate::emit_reports), work on the
+/// current session, so they should be called inside [`Session::enter`] too.
+///
+/// Programs that do only one validation at a time don't need to create a session. They get a
+/// default session that is shared by the whole process.
+///
+/// Cloning a `Session` gives another handle to the same session. When all handles are dropped,
+/// the session's reports are discarded and its threads wait to be used by a new session.
+#[derive(Debug, Clone)]
+pub struct Session(Arc<Lease>);
+
+impl Session {
+    /// Create a new session, with its own reports and its own threads.
+    ///
+    /// # Panics
+    /// May panic if the session's threads can't be started.
+    pub fn new() -> Self {
+        let idle = IDLE.lock().ok().and_then(|mut idle| idle.pop());
+        let state = idle.unwrap_or_else(|| {
+            let state: &'static SessionState = Box::leak(Box::default());
+            let pool = ThreadPoolBuilder::new()
+                // At least two, because a thread may wait on another's work during loading.
+                .num_threads(rayon::current_num_threads().max(2))
+                .thread_name(|i| format!("tiger-session-{i}"))
+                .start_handler(move |_| CURRENT.set(Some(state)))
+                .build()
+                .expect("could not start the session's threads");
+            _ = state.pool.set(pool);
+            state
+        });
+        let lease = Arc::new(Lease(state));
+        if let Ok(mut weak) = state.lease.lock() {
+            *weak = Arc::downgrade(&lease);
+        }
+        Session(lease)
+    }
+
+    /// Run `f` in this session, and return its result.
+    ///
+    /// Parallel work started by `f` is done by this session's threads.
+    pub fn enter<R: Send, F: FnOnce() -> R + Send>(&self, f: F) -> R {
+        self.0.0.enter(f)
+    }
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -15,7 +15,7 @@ use bumpalo::Bump;
 
 use crate::date::Date;
 use crate::fileset::{FileEntry, FileKind, FileStage};
-use crate::macros::{MACRO_MAP, MacroMapIndex};
+use crate::macros::{MacroMapIndex, macro_map};
 use crate::pathtable::{PathTable, PathTableIndex};
 use crate::report::{ErrorKey, err, untidy};
 
@@ -79,8 +79,8 @@ impl Ord for Loc {
             .then(self.column.cmp(&other.column))
             .then(
                 self.link_idx
-                    .map(|link| MACRO_MAP.get_loc(link))
-                    .cmp(&other.link_idx.map(|link| MACRO_MAP.get_loc(link))),
+                    .map(|link| macro_map().get_loc(link))
+                    .cmp(&other.link_idx.map(|link| macro_map().get_loc(link))),
             )
     }
 }
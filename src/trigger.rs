@@ -46,7 +46,15 @@ use crate::validator::Validator;
 /// the database.
 ///
 /// Returns the inscopes valid for the trigger and the output trigger value type.
+/// Triggers that are only known from the game's `script_docs` logs are not checked further.
 pub fn scope_trigger(name: &Token, data: &Everything) -> Option<(Scopes, Trigger)> {
+    builtin_scope_trigger(name, data).or_else(|| {
+        data.script_docs.trigger(name.as_str()).map(|scopes| (scopes, Trigger::UncheckedTodo))
+    })
+}
+
+/// Like [`scope_trigger`], but only looks in the built-in tables.
+pub(crate) fn builtin_scope_trigger(name: &Token, data: &Everything) -> Option<(Scopes, Trigger)> {
     let scope_trigger = match Game::game() {
         #[cfg(feature = "ck3")]
         Game::Ck3 => crate::ck3::tables::triggers::scope_trigger,
@@ -62,6 +70,24 @@ pub fn scope_trigger(name: &Token, data: &Everything) -> Option<(Scopes, Trigger
     scope_trigger(name, data)
 }
 
+/// Return the names of all the builtin triggers for the game being validated.
+///
+/// This does not include scripted triggers, or triggers whose names are constructed from game items.
+pub fn builtin_trigger_names() -> Box<dyn Iterator<Item = &'static str>> {
+    match Game::game() {
+        #[cfg(feature = "ck3")]
+        Game::Ck3 => Box::new(crate::ck3::tables::triggers::iter_trigger_names()),
+        #[cfg(feature = "vic3")]
+        Game::Vic3 => Box::new(crate::vic3::tables::triggers::iter_trigger_names()),
+        #[cfg(feature = "imperator")]
+        Game::Imperator => Box::new(crate::imperator::tables::triggers::iter_trigger_names()),
+        #[cfg(feature = "eu5")]
+        Game::Eu5 => Box::new(crate::eu5::tables::triggers::iter_trigger_names()),
+        #[cfg(feature = "hoi4")]
+        Game::Hoi4 => Box::new(crate::hoi4::tables::triggers::iter_trigger_names()),
+    }
+}
+
 /// The standard interface to trigger validation. Validates a trigger in the given [`ScopeContext`].
 ///
 /// `tooltipped` determines what warnings are emitted related to tooltippability of the triggers
@@ -491,7 +517,9 @@ pub fn validate_trigger_key_bv(
                     #[cfg(feature = "jomini")]
                     data.script_values.validate_call(part, data, sc);
                     sc.replace(Scopes::Value, part.clone());
-                } else if let Some((inscopes, outscope)) = scope_to_scope(part, sc.scopes(data)) {
+                } else if let Some((inscopes, outscope)) =
+                    scope_to_scope(part, sc.scopes(data), data)
+                {
                     #[cfg(feature = "imperator")]
                     if let Some((inscopes, trigger)) = scope_trigger(part, data) {
                         // If a trigger of the same name exists, and it's compatible with this
@@ -1390,7 +1418,9 @@ pub fn validate_target_ok_this(
                     #[cfg(feature = "jomini")]
                     data.script_values.validate_call(part, data, sc);
                     sc.replace(Scopes::Value, part.clone());
-                } else if let Some((inscopes, outscope)) = scope_to_scope(part, sc.scopes(data)) {
+                } else if let Some((inscopes, outscope)) =
+                    scope_to_scope(part, sc.scopes(data), data)
+                {
                     #[cfg(feature = "imperator")]
                     if let Some(inscopes) = trigger_comparevalue(part, data) {
                         // If a trigger of the same name exists, and it's compatible with this
@@ -1434,15 +1464,22 @@ pub fn validate_target_ok_this(
                 } else {
                     // See if the user forgot a prefix like `faith:` or `culture:`
                     let mut opt_info = None;
+                    let mut opt_fix = None;
                     if part_flags.contains(PartFlags::First | PartFlags::Last)
                         && let Some(prefix) = needs_prefix(part.as_str(), data, outscopes)
                     {
                         opt_info = Some(format!("did you mean `{prefix}:{part}` ?"));
+                        opt_fix = Some(format!("{prefix}:{part}"));
                     }
 
                     // TODO: warn if trying to use iterator here
                     let msg = format!("unknown token `{part}`");
-                    err(ErrorKey::UnknownField).msg(msg).opt_info(opt_info).loc(part).push();
+                    err(ErrorKey::UnknownField)
+                        .msg(msg)
+                        .opt_info(opt_info)
+                        .opt_fix(part, opt_fix)
+                        .loc(part)
+                        .push();
                     sc.close();
                     return Scopes::all();
                 }
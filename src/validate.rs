@@ -981,7 +981,9 @@ pub fn validate_scope_chain(
                     data.verify_exists(Item::State, part);
                     #[cfg(feature = "hoi4")]
                     sc.replace(Scopes::State, part.clone());
-                } else if let Some((inscopes, outscope)) = scope_to_scope(part, sc.scopes(data)) {
+                } else if let Some((inscopes, outscope)) =
+                    scope_to_scope(part, sc.scopes(data), data)
+                {
                     validate_inscopes(part_flags, part, inscopes, sc, data);
                     sc.replace(outscope, part.clone());
                 } else {
@@ -8,7 +8,7 @@ use crate::context::ScopeContext;
 use crate::date::Date;
 use crate::effect::validate_effect_internal;
 use crate::everything::Everything;
-use crate::helpers::{AllowInject, TigerHashSet, dup_assign_error};
+use crate::helpers::{AllowInject, TigerHashSet, did_you_mean, dup_assign_error};
 #[cfg(feature = "hoi4")]
 use crate::hoi4::variables::validate_variable;
 use crate::item::Item;
@@ -44,6 +44,10 @@ pub struct Validator<'a> {
     data: &'a Everything,
     /// Fields that have been requested so far
     known_fields: Vec<&'a str>,
+    /// Names of all the fields that have been requested so far, whether present or not, each
+    /// followed by a newline. They are kept in one buffer to avoid an allocation per name.
+    /// Used to suggest corrections for misspelled keys.
+    requested_fields: String,
     /// Whether loose tokens are expected
     accepted_tokens: bool,
     /// Whether subblocks are expected
@@ -88,6 +92,7 @@ impl<'a> Validator<'a> {
             block,
             data,
             known_fields: Vec::new(),
+            requested_fields: String::new(),
             accepted_tokens: false,
             accepted_blocks: false,
             accepted_block_fields: false,
@@ -217,7 +222,13 @@ impl<'a> Validator<'a> {
         });
     }
 
+    fn request(&mut self, name: &str) {
+        self.requested_fields.push_str(name);
+        self.requested_fields.push('\n');
+    }
+
     fn check_key(&mut self, name: &str) -> bool {
+        self.request(name);
         for Field(key, _, _) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -234,6 +245,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Token, &BV),
     {
         let mut found = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -255,6 +267,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Token, &BV),
     {
         let mut found = false;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -286,6 +299,7 @@ impl<'a> Validator<'a> {
     /// TODO: replace this with a `field_validated` variant.
     pub fn field_any_cmp(&mut self, name: &str) -> Option<&BV> {
         let mut found = None;
+        self.request(name);
         for Field(key, _, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -306,6 +320,7 @@ impl<'a> Validator<'a> {
     pub fn field_value(&mut self, name: &str) -> Option<&Token> {
         let mut found = None;
         let mut result = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -331,6 +346,7 @@ impl<'a> Validator<'a> {
     pub fn field_identifier(&mut self, name: &str, kind: &str) -> Option<&Token> {
         let mut found = None;
         let mut result = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -362,6 +378,7 @@ impl<'a> Validator<'a> {
     ) -> Option<&Token> {
         let mut found = None;
         let mut result = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -1134,6 +1151,7 @@ impl<'a> Validator<'a> {
     pub fn multi_field_choice_any_cmp(&mut self, name: &str, choices: &[&str]) -> bool {
         let mut found = false;
         let sev = Severity::Error.at_most(self.max_severity);
+        self.request(name);
         for Field(key, _, bv) in self.block.iter_fields() {
             if key.is(name) {
                 self.known_fields.push(key.as_str());
@@ -1247,6 +1265,7 @@ impl<'a> Validator<'a> {
     /// Just like [`Validator::field_value`], but expect any number of `name` fields in the block.
     pub fn multi_field_value(&mut self, name: &str) -> Vec<&Token> {
         let mut vec = Vec::new();
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if key.is(name) {
                 self.known_fields.push(key.as_str());
@@ -1261,6 +1280,7 @@ impl<'a> Validator<'a> {
 
     /// Just like [`Validator::field_item`], but expect any number of `name` fields in the block.
     pub fn multi_field_item(&mut self, name: &str, itype: Item) {
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if key.is(name) {
                 self.known_fields.push(key.as_str());
@@ -1275,6 +1295,7 @@ impl<'a> Validator<'a> {
     /// Just like [`Validator::field_any_cmp`], but expect any number of `name` fields in the block.
     pub fn multi_field_any_cmp(&mut self, name: &str) -> bool {
         let mut found = false;
+        self.request(name);
         for Field(key, _, _) in self.block.iter_fields() {
             if key.is(name) {
                 self.known_fields.push(key.as_str());
@@ -1291,6 +1312,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&BV, &Everything),
     {
         let mut found = false;
+        self.request(name);
         for Field(key, _, bv) in self.block.iter_fields() {
             if key.is(name) {
                 self.known_fields.push(key.as_str());
@@ -1310,6 +1332,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&BV, &Everything),
     {
         let mut found = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if key.is(name) {
                 self.known_fields.push(key.as_str());
@@ -1330,6 +1353,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Token, &BV, &Everything),
     {
         let mut found = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if key.is(name) {
                 self.known_fields.push(key.as_str());
@@ -1385,6 +1409,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&BV, &Everything),
     {
         let mut found = false;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if key.is(name) {
                 self.known_fields.push(key.as_str());
@@ -1403,6 +1428,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Token, &BV, &Everything),
     {
         let mut found = false;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if key.is(name) {
                 self.known_fields.push(key.as_str());
@@ -1434,6 +1460,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Block, &Everything),
     {
         let mut found = false;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -1472,6 +1499,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Block, &Everything),
     {
         let mut found = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -1498,6 +1526,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Block, &Everything),
     {
         let mut found: Option<&Token> = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -1522,6 +1551,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Token, &Block, &Everything),
     {
         let mut found = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -1547,6 +1577,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Block, &Everything, &mut ScopeContext),
     {
         let mut found = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -1572,6 +1603,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Token, &Block, &Everything),
     {
         let mut found = false;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -1618,6 +1650,7 @@ impl<'a> Validator<'a> {
     where
         F: FnMut(&Block, &Everything, &mut ScopeContext),
     {
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -1648,6 +1681,7 @@ impl<'a> Validator<'a> {
         F: FnMut(&Block, &Everything, &mut ScopeContext),
     {
         let mut found = None;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -1672,6 +1706,7 @@ impl<'a> Validator<'a> {
     #[cfg(feature = "ck3")] // vic3 happens not to use; silence dead code warning
     pub fn multi_field_block(&mut self, name: &str) -> bool {
         let mut found = false;
+        self.request(name);
         for Field(key, cmp, bv) in self.block.iter_fields() {
             if (self.case_sensitive && key.is(name))
                 || (!self.case_sensitive && key.lowercase_is(name))
@@ -2029,18 +2064,14 @@ impl<'a> Validator<'a> {
                     BV::Value(_) => {
                         if !self.accepted_value_fields && !self.known_fields.contains(&key.as_str())
                         {
-                            let msg = format!("unknown field `{key}`");
-                            let sev = Severity::Error.at_most(self.max_severity);
-                            report(ErrorKey::UnknownField, sev).weak().msg(msg).loc(key).push();
+                            self.warn_unknown_field(key);
                             warned = true;
                         }
                     }
                     BV::Block(_) => {
                         if !self.accepted_block_fields && !self.known_fields.contains(&key.as_str())
                         {
-                            let msg = format!("unknown field `{key}`");
-                            let sev = Severity::Error.at_most(self.max_severity);
-                            report(ErrorKey::UnknownField, sev).weak().msg(msg).loc(key).push();
+                            self.warn_unknown_field(key);
                             warned = true;
                         }
                     }
@@ -2067,6 +2098,20 @@ impl<'a> Validator<'a> {
         warned
     }
 
+    fn warn_unknown_field(&self, key: &Token) {
+        let msg = format!("unknown field `{key}`");
+        let sev = Severity::Error.at_most(self.max_severity);
+        let candidates =
+            self.requested_fields.lines().filter(|name| !self.known_fields.contains(name));
+        let suggestion = did_you_mean(key.as_str(), candidates);
+        report(ErrorKey::UnknownField, sev)
+            .weak()
+            .msg(msg)
+            .opt_info(suggestion.map(|s| format!("did you mean `{s}`?")))
+            .loc(key)
+            .push();
+    }
+
     fn expect_eq_qeq(&self, key: &Token, cmp: Comparator) {
         #[allow(clippy::collapsible_else_if)]
         if self.allow_questionmark_equals {
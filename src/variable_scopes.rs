@@ -26,6 +26,11 @@ impl VariableScopes {
         self.scopes.upsert_sync(name, (scopes, true));
     }
 
+    /// Forget the scopes that were deduced during validation, but keep the configured ones.
+    pub fn clear_deduced(&mut self) {
+        self.scopes.retain_sync(|_, (_, overridden)| *overridden);
+    }
+
     pub fn scopes(&self, name: &str) -> Scopes {
         self.scopes.read_sync(name, |_, (s, _)| *s).unwrap_or(Scopes::all())
     }
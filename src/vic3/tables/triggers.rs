@@ -23,6 +23,11 @@ static TRIGGER_MAP: LazyLock<TigerHashMap<&'static str, (Scopes, Trigger)>> = La
     hash
 });
 
+/// Iterate over the names of all the builtin triggers, except the ones that have been removed from the game.
+pub fn iter_trigger_names() -> impl Iterator<Item = &'static str> {
+    TRIGGER.iter().filter(|(_, _, v)| !matches!(v, Trigger::Removed(..))).map(|(_, name, _)| *name)
+}
+
 /// LAST UPDATED VIC3 VERSION 1.12.2
 /// See `triggers.log` from the game data dumps
 /// A key ends with '(' if it is the version that takes a parenthesized argument in script.
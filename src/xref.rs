@@ -0,0 +1,218 @@
+//! A cross-reference index of the items that are defined and the places that refer to them.
+//!
+//! Validation already looks up every reference it checks. When recording is enabled with
+//! [`Everything::enable_xref`], those lookups are remembered so that they can be written out
+//! with [`Everything::write_xref`]. The format is described in `json.md`.
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use crate::everything::Everything;
+use crate::game::Game;
+use crate::helpers::TigerHashSet;
+use crate::item::Item;
+use crate::report::{Errors, game_id, location_json};
+use crate::token::{Loc, Token};
+
+/// The version of the cross-reference format. It is increased whenever a field is removed or
+/// changes meaning. Adding fields does not change the version.
+pub const XREF_SCHEMA_VERSION: u32 = 1;
+
+/// The number of shards that the recorded references are spread over, so that the validation
+/// threads rarely have to wait for each other.
+const SHARDS: usize = 16;
+
+/// A recorded reference: the item type, the key, and where the reference is.
+type Reference = (Item, Cow<'static, str>, Loc);
+
+/// A recorded dependency: the item type, the key, and the file that refers to it.
+type Dependency = (Item, Cow<'static, str>, &'static Path);
+
+/// The references recorded during validation.
+#[derive(Debug, Default)]
+pub(crate) struct Xref {
+    /// Recording is off unless asked for, because it costs time and memory.
+    enabled: bool,
+    /// Whether to record which files refer to which items, so that [`Everything::reload_files`]
+    /// can find the files that depend on the changed ones.
+    dependencies: bool,
+    /// The references, sharded by validation thread.
+    references: [Mutex<Vec<Reference>>; SHARDS],
+    /// The items that each file refers to, sharded by validation thread. Each file is recorded
+    /// only once per item, however often the reference is validated.
+    depends_on: [Mutex<TigerHashSet<Dependency>>; SHARDS],
+}
+
+/// Return the shard that the current thread records into.
+fn shard() -> usize {
+    rayon::current_thread_index().unwrap_or(0) % SHARDS
+}
+
+impl Xref {
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    pub(crate) fn enable_dependencies(&mut self) {
+        self.dependencies = true;
+    }
+
+    /// Record that `token` refers to the item `key` of type `itype`. The key is often the token
+    /// itself, but it may be derived from it, such as a `_desc` localization key.
+    pub(crate) fn record(&self, itype: Item, key: &str, token: &Token) {
+        // The references to the items in the event graph are always recorded, because
+        // `Everything::check_unused` needs them to find out which events can be reached.
+        let reference = self.enabled
+            || (Game::is_jomini()
+                && matches!(itype, Item::Event | Item::OnAction | Item::ScriptedEffect));
+        if !reference && !self.dependencies {
+            return;
+        }
+        let key = if key == token.as_str() {
+            Cow::Borrowed(token.as_str())
+        } else {
+            Cow::Owned(key.to_owned())
+        };
+        let shard = shard();
+        if self.dependencies {
+            let dependency = (itype, key.clone(), token.loc.pathname());
+            self.depends_on[shard].lock().unwrap().insert(dependency);
+        }
+        if reference {
+            self.references[shard].lock().unwrap().push((itype, key, token.loc));
+        }
+    }
+
+    /// Return the keys and locations of the recorded references to items of type `itype`.
+    #[cfg(feature = "jomini")]
+    pub(crate) fn references_to(&self, itype: Item) -> Vec<(Cow<'static, str>, Loc)> {
+        let mut found = Vec::new();
+        for references in &self.references {
+            let references = references.lock().unwrap();
+            found.extend(
+                references
+                    .iter()
+                    .filter(|(recorded, _, _)| *recorded == itype)
+                    .map(|(_, key, loc)| (key.clone(), *loc)),
+            );
+        }
+        found
+    }
+
+    /// Return the files that have recorded references to any of the items in `keys`.
+    pub(crate) fn files_referring_to(
+        &self,
+        keys: &TigerHashSet<(Item, &str)>,
+    ) -> TigerHashSet<&'static Path> {
+        let mut found = TigerHashSet::default();
+        for depends_on in &self.depends_on {
+            let depends_on = depends_on.lock().unwrap();
+            found.extend(
+                depends_on
+                    .iter()
+                    .filter(|(itype, key, _)| keys.contains(&(*itype, key.as_ref())))
+                    .map(|(_, _, pathname)| *pathname),
+            );
+        }
+        found
+    }
+
+    /// Forget the recorded references, so that the next validation starts fresh.
+    pub(crate) fn clear(&mut self) {
+        for references in &mut self.references {
+            references.get_mut().unwrap().clear();
+        }
+        for depends_on in &mut self.depends_on {
+            depends_on.get_mut().unwrap().clear();
+        }
+    }
+
+    /// Forget the recorded references from the files for which `f` returns true, because they
+    /// will be validated again.
+    pub(crate) fn forget<F: Fn(&Path) -> bool>(&mut self, f: F) {
+        for references in &mut self.references {
+            references.get_mut().unwrap().retain(|(_, _, loc)| !f(loc.pathname()));
+        }
+        for depends_on in &mut self.depends_on {
+            depends_on.get_mut().unwrap().retain(|(_, _, pathname)| !f(pathname));
+        }
+    }
+}
+
+/// The definitions of one item, and the references to it.
+#[derive(Default)]
+struct XrefEntry {
+    definitions: Vec<Loc>,
+    references: Vec<Loc>,
+}
+
+impl Everything {
+    /// Start recording the references that validation resolves, for [`Everything::write_xref`].
+    ///
+    /// This must be called before [`Everything::validate_all`].
+    pub fn enable_xref(&mut self) {
+        self.xref.enable();
+    }
+
+    /// Write a JSON index of all the item definitions and the references to them that were
+    /// found during validation.
+    ///
+    /// Items that are referred to but not defined are included too, without definitions.
+    ///
+    /// # Errors
+    /// Returns an error if writing to `output` fails.
+    ///
+    /// # Panics
+    /// May panic when a mutex has been poisoned by another thread.
+    pub fn write_xref<O: Write>(&self, output: &mut O) -> Result<()> {
+        let mut index: BTreeMap<(&'static str, &str), XrefEntry> = BTreeMap::new();
+        for (itype, key) in self.iter_definitions() {
+            let entry = index.entry((itype.into(), key.as_str())).or_default();
+            entry.definitions.push(key.loc);
+        }
+        let references: Vec<_> =
+            self.xref.references.iter().map(|shard| shard.lock().unwrap()).collect();
+        for (itype, key, loc) in references.iter().flat_map(|shard| shard.iter()) {
+            let entry = index.entry(((*itype).into(), key)).or_default();
+            entry.references.push(*loc);
+        }
+
+        let document = self.session.enter(|| {
+            let errors = Errors::get();
+            let locations = |locs: &mut Vec<Loc>| -> Vec<Value> {
+                // The same reference is seen once for every time its surroundings are validated.
+                locs.sort_unstable_by_key(|loc| (loc.fullpath(), loc.line, loc.column));
+                locs.dedup_by_key(|loc| (loc.fullpath(), loc.line, loc.column));
+                locs.iter().map(|loc| location_json(&errors, *loc)).collect()
+            };
+            let items: Vec<Value> = index
+                .into_iter()
+                .map(|((itype, key), mut entry)| {
+                    json!({
+                        "type": itype,
+                        "key": key,
+                        "definitions": locations(&mut entry.definitions),
+                        "references": locations(&mut entry.references),
+                    })
+                })
+                .collect();
+            json!({
+                "schema_version": XREF_SCHEMA_VERSION,
+                "tiger_version": env!("CARGO_PKG_VERSION"),
+                "game": game_id(),
+                "mod_path": errors.mod_root,
+                "items": items,
+            })
+        });
+
+        serde_json::to_writer_pretty(&mut *output, &document)?;
+        writeln!(output)?;
+        Ok(())
+    }
+}
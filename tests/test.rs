@@ -1,9 +1,15 @@
-use std::path::PathBuf;
+use std::fs::{copy, create_dir_all, read_to_string, remove_dir_all, remove_file, write};
+use std::path::{Path, PathBuf};
 use std::sync::{LazyLock, Mutex};
 
 use tiger_lib::{
-    Everything, LogReportMetadata, LogReportPointers, TigerHashMap, TigerHashSet, take_reports,
+    ChangedLines, Confidence, Definition, Everything, FileKind, Item, Language, LocaFormat,
+    LogReportMetadata, LogReportPointers, OutputFormat, Scopes, Session, Severity, TigerHashMap,
+    TigerHashSet, apply_fixes, clear_baseline, count_failing_reports, emit_reports, load_baseline,
+    set_changed_lines, set_fail_on, set_show_vanilla, stale_baseline_entries, take_emitted_reports,
+    take_reports, write_baseline,
 };
+use walkdir::WalkDir;
 
 static TEST_MUTEX: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
 
@@ -272,3 +278,1025 @@ fn test_mod3() {
     dbg!(&reports);
     assert!(reports.is_empty());
 }
+
+/// Copy a test mod to a scratch directory, so that the test can change its files.
+/// Each test should use its own `tag`, because the tests run at the same time.
+fn copy_mod(modname: &str, tag: &str) -> PathBuf {
+    let from = PathBuf::from(format!("tests/files/{modname}"));
+    let to = std::env::temp_dir().join(format!("tiger-test-{tag}-{modname}"));
+    if to.exists() {
+        remove_dir_all(&to).unwrap();
+    }
+    for entry in WalkDir::new(&from).into_iter().map(Result::unwrap) {
+        let target = to.join(entry.path().strip_prefix(&from).unwrap());
+        if entry.file_type().is_dir() {
+            create_dir_all(target).unwrap();
+        } else {
+            copy(entry.path(), target).unwrap();
+        }
+    }
+    to
+}
+
+/// Copy a test mod like [`copy_mod`], and add `files` to the copy. Each file is given by its
+/// pathname in the mod and its contents.
+fn copy_mod_with(modname: &str, tag: &str, files: &[(&str, &str)]) -> PathBuf {
+    let mod_root = copy_mod(modname, tag);
+    for (pathname, contents) in files {
+        let path = mod_root.join(pathname);
+        create_dir_all(path.parent().unwrap()).unwrap();
+        write(path, contents).unwrap();
+    }
+    mod_root
+}
+
+/// Reduce the reports to something that can be compared between separate loads.
+fn summarize(
+    storage: TigerHashMap<LogReportMetadata, TigerHashSet<LogReportPointers>>,
+) -> Vec<(String, String, u32, u32)> {
+    let mut summary: Vec<_> = storage
+        .into_iter()
+        .flat_map(|(report, occurrences)| {
+            occurrences.into_iter().map(move |pointers| {
+                let loc = pointers[0].loc;
+                (report.msg.clone(), loc.pathname().display().to_string(), loc.line, loc.column)
+            })
+        })
+        .collect();
+    summary.sort();
+    summary
+}
+
+fn load_and_validate(vanilla_dir: &Path, mod_root: &Path) -> Everything {
+    load_and_validate_with(vanilla_dir, mod_root, |_| ())
+}
+
+/// Like [`load_and_validate`], but let `setup` prepare the `Everything` before it loads the mod.
+fn load_and_validate_with(
+    vanilla_dir: &Path,
+    mod_root: &Path,
+    setup: impl FnOnce(&mut Everything),
+) -> Everything {
+    let mut everything =
+        Everything::new(None, Some(vanilla_dir), None, None, mod_root, Vec::new()).unwrap();
+    setup(&mut everything);
+    everything.load_all();
+    everything.validate_all();
+    everything
+}
+
+#[test]
+fn test_reload() {
+    for enable_reload in [false, true] {
+        Session::new().enter(|| {
+            let vanilla_dir = PathBuf::from("tests/files/ck3");
+            let tag = if enable_reload { "reload-enabled" } else { "reload" };
+            let mod_root = copy_mod_with(
+                "mod1",
+                tag,
+                &[("common/scripted_triggers/00_triggers.txt", "my_trigger = { }\n")],
+            );
+            let loca = mod_root.join("localization/english/extra_l_english.yml");
+            let decisions = mod_root.join("common/decisions/zz_decision.txt");
+            let triggers = mod_root.join("common/scripted_triggers/zz_triggers.txt");
+            let change = |on: bool| {
+                if on {
+                    write(&loca, "\u{feff}l_english:\n my_decision:0 \"My decision\"\n").unwrap();
+                    write(&decisions, "my_decision2 = { }\nmy_decision3 = { }\n").unwrap();
+                    write(&triggers, "my_trigger = { always = yes }\n").unwrap();
+                } else {
+                    remove_file(&loca).unwrap();
+                    remove_file(&decisions).unwrap();
+                    remove_file(&triggers).unwrap();
+                }
+            };
+            let load = || {
+                load_and_validate_with(&vanilla_dir, &mod_root, |everything| {
+                    if enable_reload {
+                        everything.enable_reload();
+                    }
+                })
+            };
+
+            let mut everything = load();
+            let before = summarize(take_reports());
+
+            change(true);
+            everything.reload_files(&[loca.clone(), decisions.clone(), triggers.clone()]).unwrap();
+            everything.validate_all();
+            let changed = summarize(take_reports());
+            let missing = |reports: &[(String, String, u32, u32)]| {
+                reports
+                    .iter()
+                    .any(|(msg, _, _, _)| msg == "missing english localization key my_decision")
+            };
+            assert!(missing(&before));
+            assert!(!missing(&changed));
+            assert!(changed.iter().any(|(msg, _, _, _)| msg.contains("my_decision3")));
+            assert!(changed.iter().any(|(msg, _, _, _)| msg.contains("decision is redefined")));
+            assert!(changed.iter().any(|(msg, _, _, _)| msg.contains("trigger is redefined")));
+
+            change(false);
+            everything.reload_files(&[loca.clone(), decisions.clone(), triggers.clone()]).unwrap();
+            everything.validate_all();
+            assert_eq!(before, summarize(take_reports()));
+            drop(everything);
+
+            change(true);
+            let _everything = load();
+            assert_eq!(changed, summarize(take_reports()));
+        });
+    }
+}
+
+#[test]
+fn test_sarif() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let mod_root = PathBuf::from("tests/files/mod1");
+
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+
+        let mut output = Vec::new();
+        assert!(emit_reports(&mut output, OutputFormat::Sarif, false, false));
+        let sarif: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(sarif["version"], "2.1.0");
+
+        let run = &sarif["runs"][0];
+        let results = run["results"].as_array().unwrap();
+        let result = results
+            .iter()
+            .find(|result| {
+                result["message"]["text"] == "missing english localization key my_decision"
+            })
+            .unwrap();
+        assert_eq!(result["ruleId"], "missing-localization");
+        let rule = &run["tool"]["driver"]["rules"]
+            [usize::try_from(result["ruleIndex"].as_u64().unwrap()).unwrap()];
+        assert_eq!(rule["id"], "missing-localization");
+        let location = &result["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "common/decisions/decision.txt");
+        assert_eq!(location["artifactLocation"]["uriBaseId"], "%SRCROOT%");
+    });
+}
+
+#[test]
+fn test_json() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let mod_root = PathBuf::from("tests/files/mod1");
+
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let _everything = load_and_validate(&vanilla_dir, &mod_root);
+            let mut output = Vec::new();
+            assert!(emit_reports(&mut output, OutputFormat::Json, false, false));
+            let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+            assert_eq!(json["schema_version"], 1);
+            assert_eq!(json["game"], "ck3");
+            assert_eq!(json["mod_path"], "tests/files/mod1");
+
+            let reports = json["reports"].as_array().unwrap();
+            let warnings = reports.iter().filter(|report| report["severity"] == "warning").count();
+            assert_eq!(json["summary"]["warning"], warnings);
+            let report = reports
+                .iter()
+                .find(|report| report["message"] == "missing english localization key my_decision")
+                .unwrap();
+            assert_eq!(report["locations"][0]["path"], "common/decisions/decision.txt");
+            ids.push(report["id"].as_str().unwrap().to_owned());
+        }
+        assert_eq!(ids[0], ids[1]);
+    });
+}
+
+#[test]
+fn test_checkstyle() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let mod_root = PathBuf::from("tests/files/mod1");
+
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+
+        let mut output = Vec::new();
+        assert!(emit_reports(&mut output, OutputFormat::Checkstyle, false, false));
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("<?xml"));
+        assert!(output.contains(r#"<file name="common/decisions/decision.txt">"#));
+        assert!(output.contains(
+            r#"severity="warning" message="missing english localization key my_decision" source="tiger.missing-localization"/>"#
+        ));
+        assert!(output.trim_end().ends_with("</checkstyle>"));
+    });
+}
+
+#[test]
+fn test_fail_on() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let mod_root = PathBuf::from("tests/files/mod1");
+
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+
+        set_fail_on(Severity::Fatal, Confidence::Weak);
+        assert_eq!(count_failing_reports(false), 0);
+        set_fail_on(Severity::Warning, Confidence::Weak);
+        let failing = count_failing_reports(false);
+        assert!(failing > 0);
+        set_fail_on(Severity::Warning, Confidence::Strong);
+        assert!(count_failing_reports(false) < failing);
+        set_fail_on(Severity::Warning, Confidence::Weak);
+        assert!(
+            count_failing_reports(false) < take_reports().values().map(TigerHashSet::len).sum()
+        );
+    });
+}
+
+#[test]
+fn test_baseline() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let mod_root = copy_mod("mod1", "baseline");
+        let decisions = mod_root.join("common/decisions/decision.txt");
+        let baseline = std::env::temp_dir().join("tiger-test-baseline.json");
+
+        let everything = load_and_validate(&vanilla_dir, &mod_root);
+        assert!(write_baseline(&baseline).unwrap() > 0);
+        take_reports();
+        drop(everything);
+
+        // Move the lines down, change some whitespace, and remove my_decision2.
+        let orig_decisions = read_to_string(&decisions).unwrap();
+        let (first, _) = orig_decisions.split_once("my_decision2 = {").unwrap();
+        let first = first.trim_start_matches('\u{feff}').replace("    ", "\t");
+        write(&decisions, format!("\u{feff}\n\n{first}")).unwrap();
+
+        load_baseline(&baseline).unwrap();
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+        let stale = stale_baseline_entries();
+        let reports = take_emitted_reports(false);
+        clear_baseline();
+
+        assert!(reports.is_empty(), "{reports:?}");
+        assert!(!stale.is_empty());
+        for entry in stale {
+            assert_eq!(entry.path, Path::new("common/decisions/decision.txt"));
+            // my_decision2 started on line 8.
+            assert!(entry.linenr.unwrap() >= 8, "{entry:?}");
+        }
+    });
+}
+
+#[test]
+fn test_baseline_message() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let mod_root = copy_mod("mod1", "baseline_message");
+        let baseline = std::env::temp_dir().join("tiger-test-baseline-message.json");
+
+        let everything = load_and_validate(&vanilla_dir, &mod_root);
+        write_baseline(&baseline).unwrap();
+        take_reports();
+        drop(everything);
+
+        // A report with another message on the same line is a new report.
+        let mut file: serde_json::Value =
+            serde_json::from_str(&read_to_string(&baseline).unwrap()).unwrap();
+        file["entries"][0]["message"] = "something else entirely".into();
+        write(&baseline, file.to_string()).unwrap();
+
+        load_baseline(&baseline).unwrap();
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+        let stale = stale_baseline_entries();
+        let reports = take_emitted_reports(false);
+        clear_baseline();
+
+        assert_eq!(reports.len(), 1, "{reports:?}");
+        assert_eq!(stale.len(), 1, "{stale:?}");
+        assert_eq!(stale[0].message, "something else entirely");
+    });
+}
+
+#[test]
+fn test_changed_lines() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let mod_root = PathBuf::from("tests/files/mod1");
+
+        let diff = "\
+--- a/common/decisions/decision.txt
++++ b/common/decisions/decision.txt
+@@ -2,0 +2,5 @@
++my_decision = {
+";
+        set_changed_lines(Some(ChangedLines::from_diff(diff)));
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+        let reports = take_emitted_reports(false);
+        set_changed_lines(None);
+
+        assert!(!reports.is_empty());
+        for (report, pointers) in reports {
+            assert!(
+                pointers
+                    .iter()
+                    .any(|p| p.loc.pathname() == Path::new("common/decisions/decision.txt")
+                        && (p.loc.line == 0 || (2..=6).contains(&p.loc.line))),
+                "{report:?}"
+            );
+        }
+    });
+}
+
+#[test]
+fn test_fix() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let mod_root = copy_mod("mod1", "fix");
+        let decisions = mod_root.join("common/decisions/decision.txt");
+
+        let orig_decisions = read_to_string(&decisions).unwrap();
+        let misspelled = orig_decisions
+            .replace("selection_tooltip =", "selection_toltip =")
+            .replace("confirm_text =", "confirm_txt =");
+        // An identical copy of an item is removed.
+        let duplicate = "my_decision = {\n    picture = { reference = \"gfx/dummy.jpg\" }\n}\n";
+        write(&decisions, format!("{misspelled}{duplicate}")).unwrap();
+
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+        // The misspelled fields are only weakly reported, so they are not fixed unattended.
+        assert_eq!(apply_fixes().unwrap(), 1);
+        take_reports();
+
+        assert_eq!(read_to_string(&decisions).unwrap(), misspelled);
+    });
+}
+
+#[test]
+fn test_unused_definitions() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let pathname = "common/scripted_triggers/my_triggers.txt";
+        let triggers = "my_unused_trigger = {\n\talways = yes\n}\n";
+        let mod_root = copy_mod_with("mod1", "unused_definitions", &[(pathname, triggers)]);
+
+        let mut everything = load_and_validate(&vanilla_dir, &mod_root);
+        everything.check_unused();
+        let mut reports = take_reports();
+
+        let (report, _) = take_report(&mut reports, pathname, "Unused scripted trigger").unwrap();
+        assert_eq!(report.severity, Severity::Untidy);
+    });
+}
+
+#[test]
+fn test_unreachable_events() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let pathname = "events/reach.txt";
+        let events = "namespace = reach\n\
+            reach.1 = {\n\ttype = character_event\n\thidden = yes\n\
+            \timmediate = {\n\t\ttrigger_event = reach.2\n\t}\n}\n\
+            reach.2 = {\n\ttype = character_event\n\thidden = yes\n}\n\
+            namespace = console\n\
+            console.1 = {\n\ttype = character_event\n\thidden = yes\n\
+            \timmediate = {\n\t\ttrigger_event = console.2\n\t}\n}\n\
+            console.2 = {\n\ttype = character_event\n\thidden = yes\n}\n";
+        let config = "entry_points = {\n\tevents = { console }\n}\n";
+        let mod_root = copy_mod_with(
+            "mod1",
+            "unreachable_events",
+            &[(pathname, events), ("ck3-tiger.conf", config)],
+        );
+
+        // Nothing has to be enabled before validation to find the unreachable events.
+        let mut everything = load_and_validate(&vanilla_dir, &mod_root);
+        everything.check_unused();
+        let mut reports = take_reports();
+
+        let (report, pointers) = take_report(
+            &mut reports,
+            pathname,
+            "event `reach.2` is fired, but it can't be reached",
+        )
+        .unwrap();
+        assert_eq!(report.severity, Severity::Warning);
+        assert_eq!(pointers[0].loc.line, 9);
+        assert_eq!(pointers[1].loc.line, 6);
+        let (_, pointers) = take_report(&mut reports, pathname, "Event is never fired").unwrap();
+        assert_eq!(pointers[0].loc.line, 2);
+        assert!(take_report_contains(&mut reports, pathname, "console").is_none());
+        assert!(take_report(&mut reports, pathname, "Event is never fired").is_none());
+    });
+}
+
+#[test]
+fn test_event_loops() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let pathname = "events/loops.txt";
+        let event = |id: &str, effect: &str| {
+            format!("{id} = {{\n\ttype = character_event\n\thidden = yes\n\timmediate = {{ {effect} }}\n}}\n")
+        };
+        let events = [
+            "namespace = loop\n".to_owned(),
+            event("loop.1", "trigger_event = loop.2"),
+            event("loop.2", "trigger_event = { id = loop.1 }"),
+            event("loop.3", "trigger_event = { id = loop.4 days = 1 }"),
+            event("loop.4", "trigger_event = loop.3"),
+            event("loop.5", "if = { limit = { is_ai = yes } trigger_event = loop.5 }"),
+            event("loop.6", "trigger_event = loop.7"),
+            "loop.7 = {\n\ttype = character_event\n\toption = { trigger_event = loop.6 }\n}\n"
+                .to_owned(),
+            event("loop.8", "random_list = { 50 = { trigger_event = loop.8 } 50 = { } }"),
+            event("loop.9", "random_courtier = { trigger_event = loop.9 }"),
+        ];
+        let mod_root = copy_mod_with("mod1", "event_loops", &[(pathname, &events.concat())]);
+
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+        let mut reports = take_reports();
+
+        let (report, pointers) =
+            take_report_contains(&mut reports, pathname, "fire each other in a loop").unwrap();
+        assert_eq!(report.msg, "`loop.1` -> `loop.2` -> `loop.1` fire each other in a loop without delay");
+        assert_eq!(report.severity, Severity::Error);
+        assert_eq!(pointers.len(), 3);
+        assert_eq!(pointers[0].loc.line, 2);
+        assert_eq!(pointers[1].loc.line, 5);
+        assert_eq!(pointers[2].loc.line, 10);
+        assert!(take_report_contains(&mut reports, pathname, "fire each other in a loop").is_none());
+    });
+}
+
+#[test]
+fn test_explain() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let effects =
+            "my_explained_effect = {\n\tadd_gold = 1\n\tscope:target = { add_prestige = 1 }\n}\n";
+        let events = "namespace = explain\n\
+            explain.1 = {\n\ttype = character_event\n\thidden = yes\n\
+            \timmediate = {\n\t\tsave_scope_as = target\n\t\tmy_explained_effect = yes\n\t}\n}\n";
+        let on_actions = "yearly_playable_pulse = {\n\ton_actions = { explain_pulse }\n}\n";
+        let mod_root = copy_mod_with(
+            "mod1",
+            "explain",
+            &[
+                ("common/scripted_effects/explain_effects.txt", effects),
+                ("events/explain.txt", events),
+                ("common/on_action/zz_explain.txt", on_actions),
+            ],
+        );
+
+        let everything = load_and_validate_with(&vanilla_dir, &mod_root, |everything| {
+            everything.set_explain("my_explained_effect");
+        });
+        take_reports();
+
+        let mut output = Vec::new();
+        assert!(everything.write_explanation("my_explained_effect", &mut output).unwrap());
+        let text = String::from_utf8(output).unwrap();
+        assert!(text.starts_with("scripted effect `my_explained_effect` at [MOD] "));
+        assert!(text.contains("\n  this: character\n    deduced from `add_gold` here, at [MOD] "));
+        assert!(text.contains("\n  scope:target: character (expected from the caller)\n"));
+        assert!(text.contains(
+            "\n  called from:\n    [MOD] events/explain.txt:7:3, where this is character\n"
+        ));
+
+        assert!(!everything.write_explanation("no_such_thing", &mut Vec::new()).unwrap());
+
+        // Events and on-actions are explained from the scope context they were validated with,
+        // so explaining them doesn't validate them again. An on-action that is defined twice is
+        // explained from the definition that overrides the other.
+        let everything = load_and_validate_with(&vanilla_dir, &mod_root, |everything| {
+            everything.set_explain("yearly_playable_pulse");
+        });
+        take_reports();
+        let mut output = Vec::new();
+        assert!(everything.write_explanation("yearly_playable_pulse", &mut output).unwrap());
+        let text = String::from_utf8(output).unwrap();
+        assert!(
+            text.starts_with(
+                "on-action `yearly_playable_pulse` at [MOD] common/on_action/zz_explain.txt:1:1\n"
+            ),
+            "{text}"
+        );
+        assert!(take_reports().is_empty());
+    });
+}
+
+#[test]
+fn test_declared_signatures() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let pathname = "common/scripted_effects/signature_effects.txt";
+        let effects = "# tiger: scope = character\n\
+            # tiger: scopes = { target = character } args = { AMOUNT = value }\n\
+            signed_effect = {\n\tadd_gold = $AMOUNT$\n\tscope:target = { add_prestige = 1 }\n\
+            \tscope:other = { add_piety = 1 }\n}\n\
+            # tiger: args = { AMOUNT = value } returns = { friend = character }\n\
+            returning_effect = {\n\tadd_gold = $AMOUNT$\n}\n\
+            # tiger: args = { WHO = character }\n\
+            who_effect = {\n\t$WHO$ = { add_gold = 1 }\n}\n";
+        let events = "namespace = signed\n\
+            signed.1 = {\n\ttype = character_event\n\thidden = yes\n\timmediate = {\n\
+            \t\tsave_scope_as = target\n\
+            \t\tsigned_effect = { AMOUNT = 10 }\n\
+            \t\tcapital_province = { signed_effect = { AMOUNT = 10 } }\n\
+            \t\treturning_effect = { AMOUNT = 10 }\n\
+            \t\twho_effect = { WHO = capital_province }\n\
+            \t\tcapital_province = { save_scope_as = target }\n\
+            \t\tsigned_effect = { AMOUNT = 10 }\n\t}\n}\n";
+        let mod_root = copy_mod_with(
+            "mod1",
+            "signatures",
+            &[(pathname, effects), ("events/signed.txt", events)],
+        );
+
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+        let mut reports = take_reports();
+
+        let events = "events/signed.txt";
+        let (_, pointers) = take_report(
+            &mut reports,
+            events,
+            "`signed_effect` expects scope to be character but scope seems to be province",
+        )
+        .unwrap();
+        assert_eq!(pointers[0].loc.line, 8);
+        let (_, pointers) = take_report(
+            &mut reports,
+            events,
+            "`signed_effect` expects scope:target to be character but scope:target seems to be province",
+        )
+        .unwrap();
+        assert_eq!(pointers[0].loc.line, 12);
+        let (_, pointers) =
+            take_report(&mut reports, events, "`capital_province` produces province but expected character")
+                .unwrap();
+        assert_eq!(pointers[0].loc.line, 10);
+
+        let (_, pointers) =
+            take_report(&mut reports, pathname, "scope:other might not be available here").unwrap();
+        assert_eq!(pointers[0].loc.line, 6);
+        let msg = "`returning_effect` is declared to return scope:friend but never saves it";
+        let (_, pointers) = take_report(&mut reports, pathname, msg).unwrap();
+        assert_eq!(pointers[0].loc.line, 8);
+        assert!(take_report_contains(&mut reports, pathname, "parameter").is_none());
+    });
+}
+
+#[test]
+fn test_translation_parity() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let english = "\u{feff}l_english:\n\
+            \x20parity_gain:0 \"[ROOT.Char.GetName] gains $VALUE$ @gold_icon! #bold gold#!\"\n\
+            \x20parity_same:0 \"[ROOT.Char.GetName] is #bold done#!\"\n\
+            \x20parity_empty:0 \"Hello [ROOT.Char.GetName]\"\n";
+        let pathname = "localization/french/parity_l_french.yml";
+        let french = "\u{feff}l_french:\n\
+            \x20parity_gain:0 \"gagne $VALEUR$ @gold_icon! #bold or\"\n\
+            \x20parity_same:0 \"#bold fini#! pour [ROOT.Char.GetName]\"\n\
+            \x20parity_empty:0 \"\"\n";
+        let mod_root = copy_mod_with(
+            "mod1",
+            "parity",
+            &[("localization/english/parity_l_english.yml", english), (pathname, french)],
+        );
+
+        // The translations are only compared if the config file asks for it.
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+        let mut reports = take_reports();
+        assert!(take_report_contains(&mut reports, pathname, "english localization").is_none());
+        write(mod_root.join("ck3-tiger.conf"), "languages = {\n\tparity = yes\n}\n").unwrap();
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+        let mut reports = take_reports();
+
+        let msg = "french localization is missing `$VALUE$`, which the english localization has";
+        let (_, pointers) = take_report(&mut reports, pathname, msg).unwrap();
+        assert_eq!(pointers[0].loc.line, 2);
+        assert_eq!(
+            pointers[1].loc.pathname(),
+            Path::new("localization/english/parity_l_english.yml")
+        );
+        let msg = "french localization has `$VALEUR$`, which the english localization doesn't have";
+        let (_, pointers) = take_report(&mut reports, pathname, msg).unwrap();
+        assert_eq!(pointers[0].loc.column, 24);
+        let msg = "french localization is missing `[ROOT.Char.GetName]`";
+        let (report, pointers) = take_report_contains(&mut reports, pathname, msg).unwrap();
+        assert_eq!(report.confidence, Confidence::Weak);
+        assert_eq!(pointers[0].loc.line, 2);
+        let msg = "does not close its #markup the same way";
+        let (_, pointers) = take_report_contains(&mut reports, pathname, msg).unwrap();
+        assert_eq!(pointers[0].loc.line, 2);
+        assert!(take_report_contains(&mut reports, pathname, "english localization").is_none());
+    });
+}
+
+#[test]
+fn test_stale_translations() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let mod_root = copy_mod("mod1", "stale");
+    let snapshot = mod_root.join("loca_snapshot.json");
+    let english_path = mod_root.join("localization/english/stale_l_english.yml");
+    let pathname = "localization/french/stale_l_french.yml";
+    create_dir_all(mod_root.join("localization/french")).unwrap();
+    let write_loca = |english: &str, french: &str| {
+        write(&english_path, format!("\u{feff}l_english:\n{english}")).unwrap();
+        write(mod_root.join(pathname), format!("\u{feff}l_french:\n{french}")).unwrap();
+    };
+    let stale_msg = "french translation has not been updated since the english text changed";
+
+    write_loca(
+        " stale_version:1 \"New text\"\n stale_snap:0 \"Hello\"\n stale_fresh:0 \"Bye\"\n",
+        " stale_version:0 \"Vieux texte\"\n stale_snap:0 \"Bonjour\"\n stale_fresh:0 \"Au revoir\"\n",
+    );
+    let version_msg = "french translation is version 0, but the english text is version 1";
+    // Without a snapshot, nothing is reported as stale.
+    Session::new().enter(|| {
+        let everything = load_and_validate(&vanilla_dir, &mod_root);
+        let mut reports = take_reports();
+        assert!(take_report_contains(&mut reports, pathname, "french translation").is_none());
+        assert_eq!(everything.write_loca_snapshot(&snapshot).unwrap(), 3);
+    });
+
+    write_loca(
+        " stale_version:1 \"New text\"\n stale_snap:0 \"Hello there\"\n stale_fresh:0 \"Bye now\"\n",
+        " stale_version:0 \"Vieux texte\"\n stale_snap:0 \"Bonjour\"\n stale_fresh:0 \"Au revoir, alors\"\n",
+    );
+    // The second time, the snapshot has been rewritten but still knows the translation is stale.
+    for _ in 0..2 {
+        Session::new().enter(|| {
+            let everything = load_and_validate_with(&vanilla_dir, &mod_root, |everything| {
+                everything.load_loca_snapshot(&snapshot).unwrap();
+            });
+            let mut reports = take_reports();
+            let (_, pointers) = take_report(&mut reports, pathname, version_msg).unwrap();
+            assert_eq!(pointers[0].loc.line, 2);
+            let (_, pointers) = take_report(&mut reports, pathname, stale_msg).unwrap();
+            assert_eq!(pointers[0].loc.line, 3);
+            assert!(take_report_contains(&mut reports, pathname, "french translation").is_none());
+            everything.write_loca_snapshot(&snapshot).unwrap();
+        });
+    }
+}
+
+#[test]
+fn test_missing_loca_stubs() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let english = "\u{feff}l_english:\n my_decision:3 \"My decision\"\n";
+    let french = "\u{feff}l_french:\n my_decision_desc:0 \"Ma d\u{e9}cision\"\n";
+    let mod_root = copy_mod_with(
+        "mod1",
+        "stubs",
+        &[
+            ("localization/english/stubs_l_english.yml", english),
+            ("localization/french/stubs_l_french.yml", french),
+        ],
+    );
+    let english_stub = mod_root.join("localization/english/zz_tiger_missing_l_english.yml");
+    let french_stub = mod_root.join("localization/french/zz_tiger_missing_l_french.yml");
+
+    Session::new().enter(|| {
+        let everything = load_and_validate(&vanilla_dir, &mod_root);
+        let written = everything.write_missing_loca().unwrap();
+        assert!(written.iter().any(|(path, _)| path == &english_stub));
+        assert!(written.iter().any(|(path, _)| path == &french_stub));
+    });
+    let stubs = read_to_string(&english_stub).unwrap();
+    assert!(stubs.starts_with("\u{feff}l_english:\n"));
+    assert!(stubs.contains(" my_decision_desc:0 \"my_decision_desc\"\n"));
+    assert!(!stubs.contains(" my_decision:"));
+    let stubs = read_to_string(&french_stub).unwrap();
+    assert!(stubs.starts_with("\u{feff}l_french:\n"));
+    assert!(stubs.contains(" my_decision:0 \"My decision\"\n"));
+    assert!(!stubs.contains(" my_decision_desc:"));
+
+    // The stub files are only added to when they are written again, so translated stubs,
+    // comments and entries that lose out to other files are kept as they are.
+    let edited = format!(
+        "{}# done\n my_decision_desc:0 \"Autre\"",
+        stubs.replace("\"My decision\"", "\"Ma d\u{e9}cision\"")
+    );
+    write(&french_stub, &edited).unwrap();
+    write(mod_root.join("common/decisions/stubs.txt"), "stubs_decision = { }\n").unwrap();
+    Session::new().enter(|| {
+        let everything = load_and_validate(&vanilla_dir, &mod_root);
+        let mut reports = take_reports();
+        let decisions = "common/decisions/decision.txt";
+        let msg = "missing french localization key my_decision";
+        assert!(take_report(&mut reports, decisions, msg).is_none());
+        everything.write_missing_loca().unwrap();
+    });
+    let stubs = read_to_string(&french_stub).unwrap();
+    assert!(stubs.starts_with(&format!("{edited}\n")));
+    assert_eq!(stubs.matches(" my_decision:").count(), 1);
+    assert!(stubs.contains(" stubs_decision:0 \"stubs_decision\"\n"));
+}
+
+#[test]
+fn test_loca_export_import() {
+    // How the file formats are written and read is tested with each format.
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let english = "\u{feff}l_english:\n tr_hello:1 \"Hello\"\n tr_bye:0 \"Bye\"\n";
+    let french_pathname = "localization/french/tr_l_french.yml";
+    let mod_root = copy_mod_with(
+        "mod1",
+        "export",
+        &[
+            ("localization/english/tr_l_english.yml", english),
+            (french_pathname, "\u{feff}l_french:\n # Greetings\n tr_bye:0 \"Salut\"\n\n"),
+        ],
+    );
+    let french = mod_root.join(french_pathname);
+    let export_dir = mod_root.join("export");
+    let exported = export_dir.join("french.po");
+
+    Session::new().enter(|| {
+        let everything = load_and_validate(&vanilla_dir, &mod_root);
+        let written = everything.export_loca(&export_dir, LocaFormat::Po).unwrap();
+        assert!(written.iter().any(|(path, _)| path == &exported));
+    });
+    let contents = read_to_string(&exported).unwrap();
+    assert!(contents.contains("tr_l_english.yml:2"));
+    let untranslated = "msgid \"Hello\"\nmsgstr \"\"";
+    assert!(contents.contains(untranslated), "{contents}");
+    write(&exported, contents.replace(untranslated, "msgid \"Hello\"\nmsgstr \"Bonjour\""))
+        .unwrap();
+
+    Session::new().enter(|| {
+        let everything = load_and_validate(&vanilla_dir, &mod_root);
+        let written = everything.import_loca(&exported).unwrap();
+        assert_eq!(written, vec![(french.clone(), 2)]);
+    });
+    let contents = read_to_string(&french).unwrap();
+    assert_eq!(
+        contents,
+        "\u{feff}l_french:\n # Greetings\n tr_bye:0 \"Salut\"\n\n tr_hello:1 \"Bonjour\"\n"
+    );
+}
+
+/// Make a TrueType font whose character map has only the printable ASCII characters.
+fn ascii_font() -> Vec<u8> {
+    let mut cmap = Vec::new();
+    // One subtable, for Windows Unicode BMP, right after the header.
+    for n in [0u16, 1, 3, 1, 0, 12] {
+        cmap.extend(n.to_be_bytes());
+    }
+    // Format 4, with the segments 0x20..=0x7E and the final 0xFFFF.
+    let delta = 1u16.wrapping_sub(0x20);
+    for n in [4u16, 32, 0, 4, 4, 1, 0, 0x7E, 0xFFFF, 0, 0x20, 0xFFFF, delta, 1, 0, 0] {
+        cmap.extend(n.to_be_bytes());
+    }
+    let mut font = Vec::new();
+    font.extend(0x0001_0000u32.to_be_bytes());
+    for n in [1u16, 16, 0, 0] {
+        font.extend(n.to_be_bytes());
+    }
+    font.extend(b"cmap");
+    for n in [0, 28, u32::try_from(cmap.len()).unwrap()] {
+        font.extend(n.to_be_bytes());
+    }
+    font.extend(cmap);
+    font
+}
+
+#[test]
+fn test_font_glyphs() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let fonts = "fontfiles = {\n\
+            \tname = \"AsciiFont\"\n\
+            \tgroup = {\n\
+            \t\tlanguages = { l_english l_polish }\n\
+            \t\tfiles = { \"fonts/ascii.ttf\" }\n\
+            \t}\n\
+            }\n";
+        let english = "\u{feff}l_english:\n glyphs_key:0 \"Zazolc gesla jazn\"\n";
+        let pathname = "localization/polish/glyphs_l_polish.yml";
+        let polish = "\u{feff}l_polish:\n glyphs_key:0 \"Za\u{17c}\u{f3}\u{142}\u{107} g\u{119}\u{15b}l\u{105} ja\u{17a}\u{144}\"\n";
+        let mod_root = copy_mod_with(
+            "mod1",
+            "glyphs",
+            &[
+                ("fonts/fonts.font", fonts),
+                ("localization/english/glyphs_l_english.yml", english),
+                (pathname, polish),
+            ],
+        );
+        write(mod_root.join("fonts/ascii.ttf"), ascii_font()).unwrap();
+
+        let mut everything = load_and_validate(&vanilla_dir, &mod_root);
+        everything.check_unused();
+        let mut reports = take_reports();
+        let msg = "the polish fonts have no glyph for `\u{17c}` (U+017C), `\u{f3}` (U+00F3)";
+        let (_, pointers) = take_report_contains(&mut reports, pathname, msg).unwrap();
+        assert_eq!((pointers[0].loc.line, pointers[0].loc.column), (2, 18));
+        assert!(take_report_contains(&mut reports, pathname, "fonts have no glyph").is_none());
+        let english = "localization/english/glyphs_l_english.yml";
+        assert!(take_report_contains(&mut reports, english, "fonts have no glyph").is_none());
+        // The game loads the fontfiles itself.
+        assert!(take_report_contains(&mut reports, "fonts/fonts.font", "Unused").is_none());
+
+        // The fonts are read again only when their files change.
+        everything.reload_files(&[mod_root.join(english)]).unwrap();
+        everything.validate_all();
+        let mut reports = take_reports();
+        assert!(take_report_contains(&mut reports, pathname, msg).is_some());
+        write(mod_root.join("fonts/fonts.font"), fonts.replace(" l_polish", "")).unwrap();
+        everything.reload_files(&[mod_root.join("fonts/fonts.font")]).unwrap();
+        everything.validate_all();
+        let mut reports = take_reports();
+        assert!(take_report_contains(&mut reports, pathname, "fonts have no glyph").is_none());
+    });
+}
+
+#[test]
+fn test_sessions() {
+    let vanilla_dir = PathBuf::from("tests/files/ck3");
+    let validate = |modname: &str| {
+        let mod_root = PathBuf::from(format!("tests/files/{modname}"));
+        Session::new().enter(|| {
+            let _everything = load_and_validate(&vanilla_dir, &mod_root);
+            summarize(take_reports())
+        })
+    };
+    let (mod1, mod2) = std::thread::scope(|s| {
+        let mod1 = s.spawn(|| validate("mod1"));
+        let mod2 = s.spawn(|| validate("mod2"));
+        (mod1.join().unwrap(), mod2.join().unwrap())
+    });
+    assert!(!mod1.is_empty());
+    assert_eq!(mod1, validate("mod1"));
+    assert_eq!(mod2, validate("mod2"));
+    assert_ne!(mod1, mod2);
+
+    // A dropped session's reports don't show up in a session that reuses its threads.
+    let session = Session::new();
+    session.enter(|| {
+        let _everything = load_and_validate(&vanilla_dir, Path::new("tests/files/mod1"));
+    });
+    drop(session);
+    assert!(Session::new().enter(take_reports).is_empty());
+}
+
+#[test]
+fn test_query() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let triggers = "my_ruler_trigger = {\n\tis_ruler = yes\n\texists = scope:my_target\n}\n";
+        let mod_root = copy_mod_with(
+            "mod1",
+            "query",
+            &[("common/scripted_triggers/my_triggers.txt", triggers)],
+        );
+
+        let everything = load_and_validate(&vanilla_dir, &mod_root);
+        take_reports();
+
+        let Definition::Found(key, block) =
+            everything.get_definition(Item::Decision, "my_decision")
+        else {
+            panic!("my_decision not found");
+        };
+        assert_eq!(key.loc.pathname(), Path::new("common/decisions/decision.txt"));
+        assert!(block.get_field_block("picture").is_some());
+        assert!(matches!(
+            everything.get_definition(Item::Decision, "no_such_decision"),
+            Definition::NotFound
+        ));
+        assert!(matches!(
+            everything.get_definition(Item::ScriptedTrigger, "my_ruler_trigger"),
+            Definition::Found(..)
+        ));
+        assert!(matches!(
+            everything.get_definition(Item::Localization, "dummy_key"),
+            Definition::Unsupported
+        ));
+
+        assert!(everything.languages().any(|lang| lang == Language::English));
+        let text = everything.get_localization("dummy_key", Language::English).unwrap();
+        assert_eq!(text.as_str(), "dummy");
+
+        let signature = everything.scripted_trigger_signature("my_ruler_trigger").unwrap();
+        assert_eq!(signature.scopes, Scopes::Character);
+        assert!(signature.named_scopes.iter().any(|(name, _)| *name == "my_target"));
+    });
+}
+
+#[test]
+fn test_script_docs() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let pathname = "common/scripted_triggers/my_triggers.txt";
+        let triggers = "my_trigger = {\n\tis_brand_new_trigger = yes\n\tis_adult = yes\n}\n";
+        let mod_root = copy_mod_with("mod1", "script_docs", &[(pathname, triggers)]);
+
+        let _everything = load_and_validate(&vanilla_dir, &mod_root);
+        let mut reports = take_reports();
+        assert!(take_report_contains(&mut reports, pathname, "is_brand_new_trigger").is_some());
+
+        // Separate test runs may happen at the same time, so they each need their own logs.
+        let logs = std::env::temp_dir()
+            .join(format!("tiger-test-script_docs-logs-{}", std::process::id()));
+        create_dir_all(&logs).unwrap();
+        let log = "--------------------\n\
+            is_brand_new_trigger - is this new?\n\
+            Supported Scopes: character\n\
+            --------------------\n\
+            is_adult - is the character an adult?\n\
+            Supported Scopes: province\n";
+        write(logs.join("triggers.log"), log).unwrap();
+
+        // Reports about the logs are only shown along with the ones about vanilla.
+        set_show_vanilla(true);
+        let everything = load_and_validate_with(&vanilla_dir, &mod_root, |everything| {
+            everything.load_script_docs(&logs).unwrap();
+        });
+        let mut reports = take_reports();
+        assert!(take_report_contains(&mut reports, pathname, "is_brand_new_trigger").is_none());
+        let (report, pointers) = take_report_contains(&mut reports, "triggers.log", "is_adult")
+            .expect("disagreement not reported");
+        assert_eq!(report.severity, Severity::Tips);
+        assert_eq!(pointers[0].loc.line, 5);
+        // The logs are the game's, not the mod's.
+        assert_eq!(pointers[0].loc.kind, FileKind::Vanilla);
+        remove_dir_all(&logs).unwrap();
+
+        let mut everything = everything;
+        assert!(everything.load_script_docs(&mod_root).is_err());
+    });
+}
+
+#[test]
+fn test_xref() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let pathname = "common/scripted_effects/my_effects.txt";
+        let effects = "my_xref_effect = {\n\tadd_gold = 1\n}\n\
+            my_caller_effect = {\n\tmy_xref_effect = yes\n}\n";
+        let mod_root = copy_mod_with("mod1", "xref", &[(pathname, effects)]);
+
+        let everything = load_and_validate_with(&vanilla_dir, &mod_root, Everything::enable_xref);
+        take_reports();
+
+        let mut output = Vec::new();
+        everything.write_xref(&mut output).unwrap();
+        let xref: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(xref["schema_version"], 1);
+        let items = xref["items"].as_array().unwrap();
+        let item = items
+            .iter()
+            .find(|item| item["type"] == "scripted_effect" && item["key"] == "my_xref_effect")
+            .unwrap();
+        assert_eq!(item["definitions"].as_array().unwrap().len(), 1);
+        assert_eq!(item["definitions"][0]["path"], pathname);
+        assert_eq!(item["references"].as_array().unwrap().len(), 1);
+        assert_eq!(item["references"][0]["linenr"], 5);
+        assert_eq!(item["references"][0]["from"], "MOD");
+    });
+}
+
+#[test]
+fn test_event_graph() {
+    Session::new().enter(|| {
+        let vanilla_dir = PathBuf::from("tests/files/ck3");
+        let effects = "my_graph_effect = {\n\ttrigger_event = graph.3\n}\n";
+        let on_actions =
+            "my_graph_on_action = {\n\tevents = {\n\t\tdelay = { days = 5 }\n\t\tgraph.1\n\t}\n}\n";
+        let events = "namespace = graph\n\
+            graph.1 = {\n\ttype = character_event\n\thidden = yes\n\timmediate = {\n\
+            \t\ttrigger_event = { id = graph.2 days = 3 }\n\t\tmy_graph_effect = yes\n\t}\n}\n\
+            graph.2 = {\n\ttype = character_event\n\thidden = yes\n}\n\
+            graph.3 = {\n\ttype = character_event\n\thidden = yes\n}\n";
+        let mod_root = copy_mod_with(
+            "mod1",
+            "event_graph",
+            &[
+                ("common/scripted_effects/graph_effects.txt", effects),
+                ("common/on_action/graph_on_actions.txt", on_actions),
+                ("events/graph.txt", events),
+            ],
+        );
+
+        let everything = load_and_validate(&vanilla_dir, &mod_root);
+        take_reports();
+
+        let mut output = Vec::new();
+        everything.event_graph().write_json(&mut output).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json["schema_version"], 1);
+        let edges = json["edges"].as_array().unwrap();
+        let has_edge = |from: &str, to: &str, kind: &str| {
+            edges.iter().find(|e| e["from"] == from && e["to"] == to && e["kind"] == kind).cloned()
+        };
+        let edge = has_edge("on_action:my_graph_on_action", "event:graph.1", "events").unwrap();
+        assert_eq!(edge["delay"], "5 days");
+        let edge = has_edge("event:graph.1", "event:graph.2", "trigger_event").unwrap();
+        assert_eq!(edge["delay"], "3 days");
+        assert_eq!(edge["location"]["linenr"], 6);
+        has_edge("event:graph.1", "scripted_effect:my_graph_effect", "scripted_effect").unwrap();
+        let edge = has_edge("scripted_effect:my_graph_effect", "event:graph.3", "trigger_event");
+        assert!(edge.unwrap()["delay"].is_null());
+    });
+}
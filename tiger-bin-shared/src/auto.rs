@@ -8,7 +8,7 @@ use console::Term;
 use tiger_lib::ModFile;
 #[cfg(any(feature = "vic3", feature = "eu5"))]
 use tiger_lib::ModMetadata;
-use tiger_lib::{Everything, emit_reports};
+use tiger_lib::{Everything, OutputFormat, emit_reports};
 
 use crate::GameConsts;
 use crate::gamedir::{
@@ -165,14 +165,14 @@ fn validate_mod(
     // The colors can be enabled again in the config file.
     everything.load_output_settings(false);
     everything.load_config_filtering_rules();
-    if emit_reports(&mut output, false, false, false) {
+    if emit_reports(&mut output, OutputFormat::Text, false, false) {
         bail!("Invalid config");
     }
 
     everything.load_all();
     everything.validate_all();
     everything.check_rivers();
-    emit_reports(&mut output, false, false, true);
+    emit_reports(&mut output, OutputFormat::Text, false, true);
 
     // Properly dropping `everything` takes a noticeable amount of time, and we're exiting anyway.
     forget(everything);
@@ -45,6 +45,16 @@ pub fn find_paradox_directory(dir_under: &Path) -> Option<PathBuf> {
     None
 }
 
+/// Find the folder where the game writes the logs of its `script_docs` console command,
+/// if there are any.
+pub fn find_script_docs_directory(paradox_dir: &Path) -> Option<PathBuf> {
+    ["logs/script_documentation", "logs", "docs"]
+        .iter()
+        .map(|dir| paradox_dir.join(dir))
+        .find(|dir| dir.join("triggers.log").is_file())
+        .map(fix_slashes_for_target_platform)
+}
+
 /// Redo a path so that all the slashes lean the correct way for the target platform.
 /// This is mostly for Windows users, to avoid showing them paths with a mix of slashes.
 fn fix_slashes_for_target_platform<P: std::borrow::Borrow<Path>>(path: P) -> PathBuf {
@@ -1,8 +1,10 @@
 mod auto;
 mod gamedir;
+mod lsp;
 mod tiger;
 mod update;
 mod version;
+mod watch;
 
 /// String constants associated with the game being verified.
 #[allow(missing_copy_implementations)]
@@ -0,0 +1,444 @@
+//! A Language Server Protocol front end, so that editors can show the reports inline.
+//!
+//! The server speaks LSP over stdin and stdout. It validates the mod when it starts, and reloads
+//! and revalidates whenever a file is saved, and publishes the reports as diagnostics. It also offers
+//! go-to-definition for game items, localization keys and events, and completion of trigger and
+//! effect names.
+
+use std::fs::read_to_string;
+use std::mem::forget;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics, ShowMessage,
+};
+use lsp_types::request::{Completion, GotoDefinition, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionResponse, Diagnostic,
+    DiagnosticRelatedInformation, DiagnosticSeverity, GotoDefinitionResponse, InitializeParams,
+    InitializeResult, Location, MessageType, NumberOrString, Position, PositionEncodingKind,
+    PublishDiagnosticsParams, Range, SaveOptions, ServerCapabilities, ShowMessageParams,
+    TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextDocumentSyncOptions, TextDocumentSyncSaveOptions, Url,
+};
+use tiger_lib::{
+    Everything, FileKind, Item, Loc, LogReport, Severity, TigerHashMap, TigerHashSet,
+    builtin_effect_names, builtin_trigger_names, take_emitted_reports,
+};
+
+/// Everything the server knows about the mod, as of the last validation.
+struct State {
+    everything: Everything,
+    /// Completion candidates for trigger and effect keys.
+    completions: Vec<CompletionItem>,
+    /// Where each item key is defined, for go-to-definition.
+    definitions: TigerHashMap<&'static str, Vec<Loc>>,
+    /// How the client counts the columns in positions.
+    encoding: Encoding,
+    /// The files that currently have diagnostics published for them.
+    published: TigerHashSet<Url>,
+    /// The contents of files that are open in the editor. They may differ from what's on disk.
+    open_files: TigerHashMap<Url, String>,
+}
+
+/// Run the language server until the client asks it to exit.
+///
+/// `validate` is called at startup to load and validate the mod, and `revalidate` is called after
+/// every save with the path of the saved file. Both should leave the reports in the global report
+/// storage.
+pub fn run<F, G>(validate: F, revalidate: G) -> Result<()>
+where
+    F: Fn() -> Result<Everything>,
+    G: Fn(&mut Everything, &[PathBuf]) -> Result<()>,
+{
+    let (connection, io_threads) = Connection::stdio();
+
+    let (id, params) = connection.initialize_start()?;
+    let encoding = Encoding::negotiate(&serde_json::from_value(params)?);
+    let capabilities = ServerCapabilities {
+        position_encoding: Some(encoding.kind()),
+        text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
+            open_close: Some(true),
+            change: Some(TextDocumentSyncKind::FULL),
+            save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                include_text: Some(false),
+            })),
+            ..Default::default()
+        })),
+        definition_provider: Some(lsp_types::OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        ..Default::default()
+    };
+    let result = InitializeResult { capabilities, ..Default::default() };
+    connection.initialize_finish(id, serde_json::to_value(result)?)?;
+
+    let everything = validate()?;
+    let mut state = State {
+        completions: completions(&everything),
+        definitions: definitions(&everything),
+        encoding,
+        everything,
+        published: TigerHashSet::default(),
+        open_files: TigerHashMap::default(),
+    };
+    publish_diagnostics(&connection, &mut state)?;
+
+    for msg in &connection.receiver {
+        match msg {
+            Message::Request(req) => {
+                if connection.handle_shutdown(&req)? {
+                    break;
+                }
+                let response = handle_request(&state, req);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(not) => {
+                // A bad notification or a failed reload shouldn't take the server down.
+                if let Err(e) = handle_notification(&connection, &mut state, not, &revalidate) {
+                    show_error(&connection, &format!("{e:#}"))?;
+                }
+            }
+            Message::Response(_) => (),
+        }
+    }
+
+    // The writer thread only finishes once the connection is gone.
+    drop(connection);
+    io_threads.join()?;
+    forget(state);
+    Ok(())
+}
+
+fn handle_notification<G>(
+    connection: &Connection,
+    state: &mut State,
+    not: Notification,
+    revalidate: &G,
+) -> Result<()>
+where
+    G: Fn(&mut Everything, &[PathBuf]) -> Result<()>,
+{
+    match not.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params =
+                not.extract::<lsp_types::DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)?;
+            state.open_files.insert(params.text_document.uri, params.text_document.text);
+        }
+        DidChangeTextDocument::METHOD => {
+            let mut params = not
+                .extract::<lsp_types::DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD)?;
+            // With full text sync, the last change holds the whole document.
+            if let Some(change) = params.content_changes.pop() {
+                state.open_files.insert(params.text_document.uri, change.text);
+            }
+        }
+        DidCloseTextDocument::METHOD => {
+            let params =
+                not.extract::<lsp_types::DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)?;
+            state.open_files.remove(&params.text_document.uri);
+        }
+        DidSaveTextDocument::METHOD => {
+            let params =
+                not.extract::<lsp_types::DidSaveTextDocumentParams>(DidSaveTextDocument::METHOD)?;
+            if let Ok(path) = params.text_document.uri.to_file_path() {
+                let result = revalidate(&mut state.everything, &[path]);
+                // Publish whatever was reported, even if the reload failed partway.
+                state.completions = completions(&state.everything);
+                state.definitions = definitions(&state.everything);
+                publish_diagnostics(connection, state)?;
+                result?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+/// Tell the user about an error with `window/showMessage`, and log it to stderr.
+fn show_error(connection: &Connection, message: &str) -> Result<()> {
+    eprintln!("tiger language server: {message}");
+    let params = ShowMessageParams { typ: MessageType::ERROR, message: message.to_owned() };
+    let not = Notification::new(ShowMessage::METHOD.to_owned(), params);
+    connection.sender.send(Message::Notification(not))?;
+    Ok(())
+}
+
+fn handle_request(state: &State, req: Request) -> Response {
+    let id = req.id.clone();
+    match req.method.as_str() {
+        GotoDefinition::METHOD => {
+            match req.extract::<lsp_types::GotoDefinitionParams>(GotoDefinition::METHOD) {
+                Ok((id, params)) => {
+                    let result = goto_definition(state, &params.text_document_position_params);
+                    Response::new_ok(id, result)
+                }
+                Err(e) => Response::new_err(id, ErrorCode::InvalidParams as i32, e.to_string()),
+            }
+        }
+        Completion::METHOD => {
+            let response = CompletionResponse::Array(state.completions.clone());
+            Response::new_ok(id, response)
+        }
+        _ => Response::new_err(
+            id,
+            ErrorCode::MethodNotFound as i32,
+            format!("unhandled method {}", req.method),
+        ),
+    }
+}
+
+/// Take the reports from the last validation and send them to the client, one batch per file.
+/// Files that had diagnostics before but have none now get an empty batch, to clear them.
+fn publish_diagnostics(connection: &Connection, state: &mut State) -> Result<()> {
+    let mut diagnostics: TigerHashMap<Url, Vec<Diagnostic>> = TigerHashMap::default();
+    let mut columns = Columns::new(state.encoding);
+    for report in take_emitted_reports(false) {
+        if let Some((uri, diagnostic)) = report_to_diagnostic(&report, &mut columns) {
+            diagnostics.entry(uri).or_default().push(diagnostic);
+        }
+    }
+
+    let published = diagnostics.keys().cloned().collect();
+    for uri in state.published.drain() {
+        diagnostics.entry(uri).or_default();
+    }
+    state.published = published;
+
+    for (uri, diagnostics) in diagnostics {
+        let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+        let not = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+        connection.sender.send(Message::Notification(not))?;
+    }
+    Ok(())
+}
+
+fn report_to_diagnostic(
+    (report, pointers): &LogReport,
+    columns: &mut Columns,
+) -> Option<(Url, Diagnostic)> {
+    let primary = pointers.first()?;
+    if primary.loc.kind != FileKind::Mod {
+        return None;
+    }
+    let uri = Url::from_file_path(primary.loc.fullpath()).ok()?;
+
+    let severity = match report.severity {
+        Severity::Fatal | Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Untidy => DiagnosticSeverity::INFORMATION,
+        Severity::Tips => DiagnosticSeverity::HINT,
+    };
+    let mut message = report.msg.clone();
+    if let Some(info) = &report.info {
+        message.push('\n');
+        message.push_str(info);
+    }
+    let related_information = pointers
+        .iter()
+        .skip(1)
+        .filter_map(|pointer| {
+            Some(DiagnosticRelatedInformation {
+                location: Location::new(
+                    Url::from_file_path(pointer.loc.fullpath()).ok()?,
+                    columns.range(pointer.loc, pointer.length.max(1)),
+                ),
+                message: pointer.msg.clone().unwrap_or_else(|| "here".to_owned()),
+            })
+        })
+        .collect();
+
+    let diagnostic = Diagnostic {
+        range: columns.range(primary.loc, primary.length.max(1)),
+        severity: Some(severity),
+        code: Some(NumberOrString::String(report.key.to_string())),
+        source: Some("tiger".to_owned()),
+        message,
+        related_information: Some(related_information),
+        ..Default::default()
+    };
+    Some((uri, diagnostic))
+}
+
+/// How the client counts the columns in positions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    /// In chars, like a [`Loc`] does.
+    Utf32,
+    /// In UTF-16 code units. This is the LSP default, so it's used if the client offers nothing
+    /// better.
+    Utf16,
+}
+
+impl Encoding {
+    fn negotiate(params: &InitializeParams) -> Self {
+        let offered =
+            params.capabilities.general.as_ref().and_then(|g| g.position_encodings.as_ref());
+        if offered.is_some_and(|kinds| kinds.contains(&PositionEncodingKind::UTF32)) {
+            Encoding::Utf32
+        } else {
+            Encoding::Utf16
+        }
+    }
+
+    fn kind(self) -> PositionEncodingKind {
+        match self {
+            Encoding::Utf32 => PositionEncodingKind::UTF32,
+            Encoding::Utf16 => PositionEncodingKind::UTF16,
+        }
+    }
+
+    /// Convert a column in chars to a column in the client's units.
+    fn client_column(self, line: &str, column: usize) -> u32 {
+        let units = match self {
+            Encoding::Utf32 => column,
+            Encoding::Utf16 => {
+                line.chars().take(column).map(char::len_utf16).sum::<usize>()
+                    + column.saturating_sub(line.chars().count())
+            }
+        };
+        u32::try_from(units).unwrap_or(u32::MAX)
+    }
+
+    /// Convert a column in the client's units to a column in chars.
+    fn char_column(self, line: &str, column: u32) -> usize {
+        let column = column as usize;
+        match self {
+            Encoding::Utf32 => column,
+            Encoding::Utf16 => {
+                let mut units = 0;
+                line.chars()
+                    .take_while(|c| {
+                        units += c.len_utf16();
+                        units <= column
+                    })
+                    .count()
+            }
+        }
+    }
+}
+
+/// Converts [`Loc`] columns to LSP positions, reading the lines they are on when the client
+/// doesn't count in chars.
+struct Columns {
+    encoding: Encoding,
+    /// The lines of the files read so far.
+    files: TigerHashMap<PathBuf, Vec<String>>,
+}
+
+impl Columns {
+    fn new(encoding: Encoding) -> Self {
+        Self { encoding, files: TigerHashMap::default() }
+    }
+
+    /// Return the range that starts at `loc` and is `length` chars long. Lines and columns in a
+    /// `Loc` start at 1, and a line of 0 means the whole file.
+    fn range(&mut self, loc: Loc, length: usize) -> Range {
+        let encoding = self.encoding;
+        let line = loc.line.saturating_sub(1);
+        let column = loc.column.saturating_sub(1) as usize;
+        let text = match encoding {
+            Encoding::Utf32 => "",
+            Encoding::Utf16 => self.line(loc.fullpath(), line as usize),
+        };
+        let start = encoding.client_column(text, column);
+        let end = encoding.client_column(text, column + length);
+        Range::new(Position::new(line, start), Position::new(line, end))
+    }
+
+    fn line(&mut self, path: &Path, line: usize) -> &str {
+        let lines = self.files.entry(path.to_path_buf()).or_insert_with(|| {
+            read_to_string(path)
+                .map(|text| text.lines().map(str::to_owned).collect())
+                .unwrap_or_default()
+        });
+        lines.get(line).map_or("", String::as_str)
+    }
+}
+
+fn goto_definition(
+    state: &State,
+    params: &TextDocumentPositionParams,
+) -> Option<GotoDefinitionResponse> {
+    let uri = &params.text_document.uri;
+    let text = match state.open_files.get(uri) {
+        Some(text) => text.clone(),
+        None => read_to_string(uri.to_file_path().ok()?).ok()?,
+    };
+    let line = text.lines().nth(params.position.line as usize)?;
+    let column = state.encoding.char_column(line, params.position.character);
+    let word = word_at(line, column)?;
+
+    let mut columns = Columns::new(state.encoding);
+    let locations: Vec<Location> = state
+        .definitions
+        .get(word)?
+        .iter()
+        .filter_map(|loc| {
+            let uri = Url::from_file_path(loc.fullpath()).ok()?;
+            Some(Location::new(uri, columns.range(*loc, word.chars().count())))
+        })
+        .collect();
+    if locations.is_empty() { None } else { Some(GotoDefinitionResponse::Array(locations)) }
+}
+
+/// Return the identifier that contains the character at `column` in `line`.
+fn word_at(line: &str, column: usize) -> Option<&str> {
+    fn is_word_char(c: char) -> bool {
+        c.is_alphanumeric() || c == '_'
+    }
+
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let mut start = column.min(chars.len());
+    while start > 0 && is_word_char(chars[start - 1].1) {
+        start -= 1;
+    }
+    let mut end = column.min(chars.len());
+    while end < chars.len() && is_word_char(chars[end].1) {
+        end += 1;
+    }
+    if start == end {
+        return None;
+    }
+    let from = chars[start].0;
+    let to = chars.get(end).map_or(line.len(), |(i, _)| *i);
+    Some(&line[from..to])
+}
+
+/// Index the definitions of all items except files by their key.
+fn definitions(everything: &Everything) -> TigerHashMap<&'static str, Vec<Loc>> {
+    let mut definitions: TigerHashMap<&'static str, Vec<Loc>> = TigerHashMap::default();
+    for (itype, key) in everything.iter_definitions() {
+        if itype != Item::File {
+            definitions.entry(key.as_str()).or_default().push(key.loc);
+        }
+    }
+    definitions
+}
+
+fn completions(everything: &Everything) -> Vec<CompletionItem> {
+    let mut completions = Vec::new();
+    let mut add = |label: &str, detail: &str| {
+        completions.push(CompletionItem {
+            label: label.to_owned(),
+            kind: Some(CompletionItemKind::FUNCTION),
+            detail: Some(detail.to_owned()),
+            ..Default::default()
+        });
+    };
+    for name in builtin_trigger_names() {
+        add(name, "trigger");
+    }
+    for name in builtin_effect_names() {
+        add(name, "effect");
+    }
+    for key in everything.iter_keys(Item::ScriptedTrigger) {
+        add(key.as_str(), "scripted trigger");
+    }
+    for key in everything.iter_keys(Item::ScriptedEffect) {
+        add(key.as_str(), "scripted effect");
+    }
+    completions
+}
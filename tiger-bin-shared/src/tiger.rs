@@ -1,23 +1,32 @@
-use std::io::stdout;
-use std::{mem::forget, path::PathBuf};
+use std::fs::{File, read_to_string};
+use std::io::{BufWriter, Write, stdout};
+use std::mem::forget;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
 use clap::{Args, Parser, Subcommand, error::ErrorKind};
 #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
 use tiger_lib::ModFile;
 #[cfg(any(feature = "vic3", feature = "eu5"))]
 use tiger_lib::ModMetadata;
 use tiger_lib::{
-    Everything, disable_ansi_colors, emit_reports, get_version_from_launcher, set_show_loaded_mods,
-    set_show_vanilla, suppress_from_json, validate_config_file,
+    ChangedLines, Confidence, Everything, LocaFormat, OutputFormat, Severity, apply_fixes,
+    count_failing_reports, disable_ansi_colors, emit_given_reports, emit_reports,
+    get_version_from_launcher, load_baseline, set_changed_lines, set_fail_on, set_show_loaded_mods,
+    set_show_vanilla, stale_baseline_entries, take_emitted_reports, validate_config_file,
+    write_baseline,
 };
 
 use crate::GameConsts;
 use crate::gamedir::{
-    find_game_directory_steam, find_paradox_directory, find_workshop_directory_steam,
+    find_game_directory_steam, find_paradox_directory, find_script_docs_directory,
+    find_workshop_directory_steam,
 };
+use crate::lsp;
 use crate::update::update;
 use crate::version::warn_versions;
+use crate::watch;
 
 #[derive(Parser)]
 #[clap(args_conflicts_with_subcommands = true)]
@@ -64,15 +73,23 @@ struct ValidateArgs {
     /// Path to custom .conf file.
     #[clap(long)]
     config: Option<PathBuf>,
+    /// Path to the folder with the game's `script_docs` logs, to accept triggers and effects
+    /// that are newer than this version of tiger.
+    /// By default they are looked for in the paradox directory.
+    #[clap(long)]
+    script_docs: Option<PathBuf>,
     /// Show errors in the base game script code as well
     #[clap(long)]
     show_vanilla: bool,
     /// Show errors in other loaded mods as well
     #[clap(long)]
     show_mods: bool,
-    /// Output the reports in JSON format
-    #[clap(long)]
+    /// Output the reports in JSON format. Same as `--format json`.
+    #[clap(long, conflicts_with = "format")]
     json: bool,
+    /// Output format for the reports: text, json, sarif, junit, or checkstyle.
+    #[clap(long, default_value_t)]
+    format: OutputFormat,
     /// Consolidate certain repeated errors
     #[clap(long, short)]
     consolidate: bool,
@@ -87,9 +104,73 @@ struct ValidateArgs {
     /// Can also be configured in the config file.
     #[clap(long)]
     no_color: bool,
-    /// Load a JSON file of reports to remove from the output.
+    /// Exit with an error code if any report at or above this severity is printed:
+    /// tips, untidy, warning, error, or fatal.
+    /// Can also be configured in the config file.
     #[clap(long)]
-    suppress: Option<PathBuf>,
+    fail_on: Option<Severity>,
+    /// Together with --fail-on, only count reports at or above this confidence:
+    /// weak, reasonable, or strong.
+    #[clap(long, requires = "fail_on")]
+    fail_on_confidence: Option<Confidence>,
+    /// Load a baseline file of reports to remove from the output.
+    /// The output of --json is accepted too.
+    #[clap(long, visible_alias = "suppress")]
+    baseline: Option<PathBuf>,
+    /// Only show reports about lines that changed since this git revision.
+    #[clap(long, conflicts_with = "changed_lines")]
+    changed_since: Option<String>,
+    /// Only show reports about lines that are added or changed in this unified diff.
+    /// File names in the diff should be relative to the mod directory.
+    #[clap(long)]
+    changed_lines: Option<PathBuf>,
+    /// Write the reports to a baseline file instead of printing them.
+    #[clap(long, conflicts_with_all = ["lsp", "watch"])]
+    write_baseline: Option<PathBuf>,
+    /// Load a localization snapshot, to report the translations whose base language text has
+    /// changed since the snapshot was written, or whose version number is behind it.
+    #[clap(long)]
+    loca_snapshot: Option<PathBuf>,
+    /// Write a snapshot of the localization texts to this file, to load with --loca-snapshot later.
+    /// Translations that are stale according to the loaded snapshot keep their old record.
+    #[clap(long, conflicts_with_all = ["lsp", "watch"])]
+    write_loca_snapshot: Option<PathBuf>,
+    /// Write the localization keys that are reported missing to a stub file for each language,
+    /// `zz_tiger_missing_l_<lang>.yml`, in the mod's localization folders.
+    #[clap(long, conflicts_with_all = ["lsp", "watch"])]
+    write_missing_loca: bool,
+    /// Export the mod's localization to a file for each language in this folder, for translation
+    /// tools.
+    #[clap(long, conflicts_with_all = ["lsp", "watch"])]
+    export_loca: Option<PathBuf>,
+    /// Format of the files for --export-loca: csv, po, or xliff.
+    #[clap(long, default_value_t, requires = "export_loca")]
+    loca_format: LocaFormat,
+    /// Import translations from a .csv, .po or .xlf file made with --export-loca, writing them to
+    /// the mod's localization files. Can be given more than once.
+    #[clap(long, conflicts_with_all = ["lsp", "watch"])]
+    import_loca: Vec<PathBuf>,
+    /// Write an index of all item definitions and the references to them to this file, in JSON format.
+    #[clap(long, conflicts_with_all = ["lsp", "watch"])]
+    xref: Option<PathBuf>,
+    /// Write the graph of which events and on-actions fire which others to this file.
+    /// The format is JSON if the file name ends in .json, otherwise DOT.
+    #[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator", feature = "eu5"))]
+    #[clap(long, conflicts_with_all = ["lsp", "watch"])]
+    event_graph: Option<PathBuf>,
+    /// Explain the scope types that tiger deduced for the scripted trigger, scripted effect, event
+    /// or on-action with this name, and where they came from.
+    #[clap(long, conflicts_with_all = ["lsp", "watch", "write_baseline", "fix"])]
+    explain: Option<String>,
+    /// Apply the suggested fixes that can be made automatically, editing the mod's files in place.
+    #[clap(long, conflicts_with_all = ["lsp", "watch", "write_baseline"])]
+    fix: bool,
+    /// Run as a language server, speaking the Language Server Protocol over stdin and stdout.
+    #[clap(long, conflicts_with_all = ["json", "consolidate"])]
+    lsp: bool,
+    /// Keep running and validate again whenever files in the mod change, printing the new and fixed reports.
+    #[clap(long, conflicts_with_all = ["json", "lsp"])]
+    watch: bool,
 }
 
 /// Run the main tiger application.
@@ -118,11 +199,22 @@ pub fn run(
         None => {
             let mut args = cli.validate_args.unwrap();
 
-            if args.json && args.consolidate {
+            if args.json {
+                args.format = OutputFormat::Json;
+            }
+            if args.format != OutputFormat::Text && args.consolidate {
+                Cli::command()
+                    .error(
+                        ErrorKind::ArgumentConflict,
+                        format!("Can't use report consolidation with {} output.", args.format),
+                    )
+                    .exit();
+            }
+            if args.format != OutputFormat::Text && (args.lsp || args.watch) {
                 Cli::command()
                     .error(
                         ErrorKind::ArgumentConflict,
-                        "Can't use report consolidation with JSON output.",
+                        "The --lsp and --watch modes only support the text output format.",
                     )
                     .exit();
             }
@@ -142,6 +234,9 @@ pub fn run(
             if args.paradox.is_none() {
                 args.paradox = find_paradox_directory(&PathBuf::from(paradox_dir));
             }
+            if args.script_docs.is_none() {
+                args.script_docs = args.paradox.as_deref().and_then(find_script_docs_directory);
+            }
             if let Some(ref mut game) = args.game {
                 eprintln!("Using {name_short} directory: {}", game.display());
                 let mut sig = game.clone();
@@ -183,9 +278,9 @@ pub fn run(
 
             args.config = validate_config_file(args.config);
 
-            if let Some(suppress) = args.suppress {
-                eprintln!("Suppressing reports from: {}", suppress.display());
-                suppress_from_json(&suppress)?;
+            if let Some(baseline) = &args.baseline {
+                eprintln!("Suppressing reports from: {}", baseline.display());
+                load_baseline(baseline)?;
             }
 
             if args.show_vanilla {
@@ -200,7 +295,7 @@ pub fn run(
 
             if args.unused {
                 eprintln!(
-                    "Showing warnings for unused localization. There will be many false positives."
+                    "Showing warnings for unused definitions. There will be many false positives."
                 );
             }
 
@@ -214,46 +309,35 @@ pub fn run(
                 disable_ansi_colors();
             }
 
-            let mut everything;
-
             #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
-            {
-                if args.modpath.is_dir() {
-                    args.modpath.push("descriptor.mod");
-                }
-
-                let modfile = ModFile::read(&args.modpath)?;
-                let modpath = modfile.modpath();
-                if !modpath.exists() {
-                    eprintln!("Looking for mod in {}", modpath.display());
-                    bail!("Cannot find mod directory. Please make sure the .mod file is correct.");
-                }
-                eprintln!("Using mod directory: {}", modpath.display());
-
-                everything = Everything::new(
-                    args.config.as_deref(),
-                    args.game.as_deref(),
-                    args.workshop.as_deref(),
-                    args.paradox.as_deref(),
-                    &modpath,
-                    modfile.replace_paths(),
-                )?;
-            }
-            #[cfg(any(feature = "vic3", feature = "eu5"))]
-            {
-                let metadata = ModMetadata::read(&args.modpath)?;
-                eprintln!("Using mod directory: {}", metadata.modpath().display());
+            if args.modpath.is_dir() {
+                args.modpath.push("descriptor.mod");
+            }
 
-                everything = Everything::new(
-                    args.config.as_deref(),
-                    args.game.as_deref(),
-                    args.workshop.as_deref(),
-                    args.paradox.as_deref(),
-                    &args.modpath,
-                    metadata.replace_paths(),
-                )?;
+            if args.lsp {
+                return lsp::run(
+                    || {
+                        let mut everything = new_everything(&args)?;
+                        everything.load_output_settings(false);
+                        everything.load_config_filtering_rules();
+                        apply_filter_args(&args);
+                        everything.enable_reload();
+                        if args.unused {
+                            everything.enable_check_unused();
+                        }
+                        validate(&mut everything, &args);
+                        Ok(everything)
+                    },
+                    |everything, changed| {
+                        everything.reload_files(changed)?;
+                        run_checks(everything, &args);
+                        Ok(())
+                    },
+                );
             }
 
+            let mut everything = new_everything(&args)?;
+
             // Print a blank line between the preamble and the first report:
             eprintln!();
 
@@ -262,7 +346,9 @@ pub fn run(
 
             let mut output = stdout();
 
-            if !args.json && emit_reports(&mut output, false, args.consolidate, false) {
+            if args.format == OutputFormat::Text
+                && emit_reports(&mut output, OutputFormat::Text, args.consolidate, false)
+            {
                 bail!("Invalid config");
             }
 
@@ -271,36 +357,267 @@ pub fn run(
             if args.no_color {
                 disable_ansi_colors();
             }
-            // Same logic applies to showing vanilla and other mods
-            if args.show_vanilla {
-                set_show_vanilla(true);
+            apply_filter_args(&args);
+            apply_changed_lines(&args, everything.mod_root())?;
+
+            if args.xref.is_some() {
+                everything.enable_xref();
             }
-            if args.show_mods {
-                set_show_loaded_mods(true);
+            if args.unused {
+                everything.enable_check_unused();
+            }
+            if args.watch {
+                everything.enable_reload();
+            }
+            if let Some(key) = &args.explain {
+                everything.set_explain(key);
             }
 
-            everything.load_all();
-            everything.validate_all();
-            everything.check_rivers();
+            validate(&mut everything, &args);
 
-            #[cfg(feature = "ck3")]
-            if args.pod {
-                everything.check_pod();
+            if let Some(xref) = &args.xref {
+                let file = File::create(xref)
+                    .with_context(|| format!("could not create {}", xref.display()))?;
+                let mut output = BufWriter::new(file);
+                everything.write_xref(&mut output)?;
+                output.flush()?;
+                eprintln!("Wrote the cross-reference index to {}", xref.display());
             }
 
-            if args.unused {
-                everything.check_unused();
+            if args.write_missing_loca {
+                for (path, count) in everything.write_missing_loca()? {
+                    eprintln!("Wrote {count} missing localization keys to {}", path.display());
+                }
+            }
+
+            if let Some(dir) = &args.export_loca {
+                for (path, count) in everything.export_loca(dir, args.loca_format)? {
+                    eprintln!("Exported {count} localization keys to {}", path.display());
+                }
+            }
+
+            for import in &args.import_loca {
+                for (path, count) in everything.import_loca(import)? {
+                    eprintln!(
+                        "Imported {count} translations from {} to {}",
+                        import.display(),
+                        path.display()
+                    );
+                }
+            }
+
+            if let Some(path) = &args.write_loca_snapshot {
+                let count = everything.write_loca_snapshot(path)?;
+                eprintln!(
+                    "Wrote {count} translations to localization snapshot: {}",
+                    path.display()
+                );
+            }
+
+            #[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator", feature = "eu5"))]
+            if let Some(path) = &args.event_graph {
+                write_event_graph(&everything, path)?;
+                eprintln!("Wrote the event graph to {}", path.display());
+            }
+
+            if let Some(key) = &args.explain {
+                println!();
+                if !everything.write_explanation(key, &mut output)? {
+                    bail!(
+                        "There is no scripted trigger, scripted effect, event or on-action named `{key}`."
+                    );
+                }
+            }
+
+            if args.watch {
+                let reports = take_emitted_reports(args.consolidate);
+                emit_given_reports(&mut output, &reports, true);
+                let mod_root = everything.mod_root().to_path_buf();
+                return watch::run(&mut output, &mod_root, reports, |changed| {
+                    everything.reload_files(changed)?;
+                    run_checks(&mut everything, &args);
+                    Ok(take_emitted_reports(args.consolidate))
+                });
             }
 
-            let any_printed = emit_reports(&mut output, args.json, args.consolidate, true);
+            if let Some(baseline) = &args.write_baseline {
+                let count = write_baseline(baseline)?;
+                eprintln!("Wrote {count} reports to baseline: {}", baseline.display());
+                forget(everything);
+                return Ok(());
+            }
+
+            let fixed = if args.fix { Some(apply_fixes()?) } else { None };
+            let stale = stale_baseline_entries();
+            let failing = count_failing_reports(args.consolidate);
+            let any_printed = emit_reports(&mut output, args.format, args.consolidate, true);
 
-            if !args.json && !any_printed {
+            if args.format == OutputFormat::Text && !any_printed {
                 eprintln!("No problems found.");
             }
 
+            if !stale.is_empty() {
+                eprintln!(
+                    "{} baseline entries no longer match any report. Use --write-baseline to update the baseline.",
+                    stale.len()
+                );
+                for entry in &stale {
+                    let linenr = entry.linenr.map(|nr| format!(":{nr}")).unwrap_or_default();
+                    eprintln!(
+                        "  ({}) {}{linenr}: {}",
+                        entry.key,
+                        entry.path.display(),
+                        entry.message
+                    );
+                }
+            }
+
+            if let Some(fixed) = fixed {
+                eprintln!("Applied {fixed} fixes. The reports above are from before fixing.");
+            }
+
             // Properly dropping `everything` takes a noticeable amount of time, and we're exiting anyway.
             forget(everything);
+
+            if failing > 0 {
+                bail!("{failing} of the reports are severe enough to fail the run.");
+            }
             Ok(())
         }
     }
 }
+
+/// Create the [`Everything`] for the mod named on the command line, ready to load.
+fn new_everything(args: &ValidateArgs) -> Result<Everything> {
+    #[cfg(any(feature = "ck3", feature = "imperator", feature = "hoi4"))]
+    {
+        let modfile = ModFile::read(&args.modpath)?;
+        let modpath = modfile.modpath();
+        if !modpath.exists() {
+            eprintln!("Looking for mod in {}", modpath.display());
+            bail!("Cannot find mod directory. Please make sure the .mod file is correct.");
+        }
+        eprintln!("Using mod directory: {}", modpath.display());
+
+        let mut everything = Everything::new(
+            args.config.as_deref(),
+            args.game.as_deref(),
+            args.workshop.as_deref(),
+            args.paradox.as_deref(),
+            &modpath,
+            modfile.replace_paths(),
+        )?;
+        load_script_docs(&mut everything, args)?;
+        load_loca_snapshot(&mut everything, args)?;
+        Ok(everything)
+    }
+    #[cfg(any(feature = "vic3", feature = "eu5"))]
+    {
+        let metadata = ModMetadata::read(&args.modpath)?;
+        eprintln!("Using mod directory: {}", metadata.modpath().display());
+
+        let mut everything = Everything::new(
+            args.config.as_deref(),
+            args.game.as_deref(),
+            args.workshop.as_deref(),
+            args.paradox.as_deref(),
+            &args.modpath,
+            metadata.replace_paths(),
+        )?;
+        load_script_docs(&mut everything, args)?;
+        load_loca_snapshot(&mut everything, args)?;
+        Ok(everything)
+    }
+}
+
+/// Load the game's `script_docs` logs, if `--script-docs` names them or they were found.
+fn load_script_docs(everything: &mut Everything, args: &ValidateArgs) -> Result<()> {
+    if let Some(dir) = &args.script_docs {
+        eprintln!("Using script_docs logs from: {}", dir.display());
+        everything.load_script_docs(dir)?;
+    }
+    Ok(())
+}
+
+/// Load the localization snapshot named by `--loca-snapshot`, if any.
+fn load_loca_snapshot(everything: &mut Everything, args: &ValidateArgs) -> Result<()> {
+    if let Some(path) = &args.loca_snapshot {
+        eprintln!("Comparing translations with the localization snapshot: {}", path.display());
+        everything.load_loca_snapshot(path)?;
+    }
+    Ok(())
+}
+
+/// Write the event graph to `path`, in JSON format if it ends in `.json` and DOT format otherwise.
+#[cfg(any(feature = "ck3", feature = "vic3", feature = "imperator", feature = "eu5"))]
+fn write_event_graph(everything: &Everything, path: &Path) -> Result<()> {
+    let graph = everything.event_graph();
+    let file =
+        File::create(path).with_context(|| format!("could not create {}", path.display()))?;
+    let mut output = BufWriter::new(file);
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+        graph.write_json(&mut output)?;
+    } else {
+        graph.write_dot(&mut output)?;
+    }
+    output.flush()?;
+    Ok(())
+}
+
+/// Apply the command line options that override the config file's report filter.
+fn apply_filter_args(args: &ValidateArgs) {
+    if args.show_vanilla {
+        set_show_vanilla(true);
+    }
+    if args.show_mods {
+        set_show_loaded_mods(true);
+    }
+    if let Some(severity) = args.fail_on {
+        set_fail_on(severity, args.fail_on_confidence.unwrap_or(Confidence::Weak));
+    }
+}
+
+/// Limit the reports to changed lines, if `--changed-since` or `--changed-lines` ask for it.
+fn apply_changed_lines(args: &ValidateArgs, mod_root: &Path) -> Result<()> {
+    let diff = if let Some(rev) = &args.changed_since {
+        eprintln!("Showing only reports about lines changed since {rev}.");
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(mod_root)
+            .args(["diff", "--relative", "--no-color", "--no-ext-diff", "-U0", rev])
+            .output()
+            .context("could not run git")?;
+        if !output.status.success() {
+            bail!("git diff failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    } else if let Some(patch) = &args.changed_lines {
+        eprintln!("Showing only reports about lines changed in {}.", patch.display());
+        read_to_string(patch).with_context(|| format!("could not read {}", patch.display()))?
+    } else {
+        return Ok(());
+    };
+    set_changed_lines(Some(ChangedLines::from_diff(&diff)));
+    Ok(())
+}
+
+/// Load all the game and mod files and run the validations requested on the command line.
+fn validate(everything: &mut Everything, args: &ValidateArgs) {
+    everything.load_all();
+    run_checks(everything, args);
+}
+
+/// Validate the loaded files and run the optional checks that `args` ask for.
+fn run_checks(everything: &mut Everything, args: &ValidateArgs) {
+    everything.validate_all();
+    everything.check_rivers();
+
+    #[cfg(feature = "ck3")]
+    if args.pod {
+        everything.check_pod();
+    }
+
+    if args.unused {
+        everything.check_unused();
+    }
+}
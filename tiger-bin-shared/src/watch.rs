@@ -0,0 +1,167 @@
+//! Watch mode: keep running after the first validation, and validate again whenever files in the
+//! mod directory change.
+//!
+//! After each run, only the difference with the previous run is printed: the reports that are new
+//! and the ones that have been fixed.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use anyhow::Result;
+use notify::{EventKind, RecursiveMode, Watcher, recommended_watcher};
+use tiger_lib::{LogReport, TigerHashMap, emit_given_reports};
+
+/// How long to wait for more changes before validating again. Editors and version control tools
+/// often write several files, or write one file in several steps.
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// Watch `mod_root` until the process is interrupted.
+///
+/// `reports` are the reports from the first run, which have already been printed.
+/// `revalidate` is called with the full paths of the changed files, and should return the reports
+/// from validating again.
+pub fn run<O, F>(
+    output: &mut O,
+    mod_root: &Path,
+    reports: Vec<LogReport>,
+    mut revalidate: F,
+) -> Result<()>
+where
+    O: Write + Send,
+    F: FnMut(&[PathBuf]) -> Result<Vec<LogReport>>,
+{
+    let (sender, receiver) = channel();
+    let mut watcher = recommended_watcher(sender)?;
+    watcher.watch(mod_root, RecursiveMode::Recursive)?;
+    eprintln!("Watching {} for changes. Press Ctrl-C to stop.", mod_root.display());
+
+    let mut previous = reports;
+    loop {
+        let mut changed = Vec::new();
+        // The watcher is gone only if the sender was dropped, and then nothing more will come.
+        add_changed_paths(&mut changed, receiver.recv()?);
+        while let Ok(event) = receiver.recv_timeout(SETTLE_TIME) {
+            add_changed_paths(&mut changed, event);
+        }
+        changed.sort();
+        changed.dedup();
+        if changed.is_empty() {
+            continue;
+        }
+
+        eprintln!();
+        for path in &changed {
+            eprintln!("Changed: {}", path.display());
+        }
+        match revalidate(&changed) {
+            Ok(reports) => {
+                print_diff(output, &previous, &reports);
+                previous = reports;
+            }
+            // Keep the previous reports, so that the next successful run is compared with them.
+            Err(e) => eprintln!("Could not validate again: {e:#}"),
+        }
+    }
+}
+
+fn add_changed_paths(changed: &mut Vec<PathBuf>, event: notify::Result<notify::Event>) {
+    let event = match event {
+        Ok(event) => event,
+        Err(e) => {
+            eprintln!("Error while watching for changes: {e}");
+            // The error may say which files it was about. They may have changed.
+            changed.extend(e.paths);
+            return;
+        }
+    };
+    if matches!(event.kind, EventKind::Access(_)) {
+        return;
+    }
+    changed.extend(
+        event
+            .paths
+            .into_iter()
+            .filter(|path| !path.components().any(|c| c.as_os_str() == ".git") && !path.is_dir()),
+    );
+}
+
+/// The message and the path of the first location of a report. Together with the error key, they
+/// are what a report in `previous` and one in `current` must have in common to be the same report.
+/// Line numbers are left out, so that inserting or removing lines doesn't make every later report
+/// in the file look new.
+type MatchKey<'a> = (&'a str, Option<&'a Path>);
+
+fn match_key((report, pointers): &LogReport) -> MatchKey<'_> {
+    (&report.msg, pointers.first().map(|pointer| pointer.loc.pathname()))
+}
+
+fn first_line((_, pointers): &LogReport) -> u32 {
+    pointers.first().map_or(0, |pointer| pointer.loc.line)
+}
+
+/// Print the reports in `current` that were not in `previous`, and a short line for each of the
+/// reports in `previous` that are not in `current` anymore.
+///
+/// Reports are paired up the way baselines do it: by key, message and file. When a file has
+/// several reports that are alike, each report is paired with the one whose line is nearest.
+fn print_diff<O: Write + Send>(output: &mut O, previous: &[LogReport], current: &[LogReport]) {
+    let mut unmatched: TigerHashMap<MatchKey, Vec<&LogReport>> = TigerHashMap::default();
+    for report in previous {
+        unmatched.entry(match_key(report)).or_default().push(report);
+    }
+    let mut new = Vec::new();
+    for report in current {
+        let line = first_line(report);
+        let candidates = unmatched.get_mut(&match_key(report));
+        let nearest = candidates.as_ref().and_then(|candidates| {
+            (0..candidates.len())
+                .filter(|&i| candidates[i].0.key == report.0.key)
+                .min_by_key(|&i| first_line(candidates[i]).abs_diff(line))
+        });
+        if let (Some(candidates), Some(nearest)) = (candidates, nearest) {
+            candidates.swap_remove(nearest);
+        } else {
+            new.push(report.clone());
+        }
+    }
+    let mut fixed: Vec<&LogReport> = unmatched.into_values().flatten().collect();
+    fixed.sort_by_key(|(_, pointers)| {
+        pointers
+            .first()
+            .map(|pointer| (pointer.loc.pathname(), pointer.loc.line, pointer.loc.column))
+    });
+
+    if !new.is_empty() {
+        _ = writeln!(output, "New reports:\n");
+        emit_given_reports(output, &new, false);
+    }
+    if !fixed.is_empty() {
+        _ = writeln!(output, "Fixed reports:");
+        for (report, pointers) in &fixed {
+            // The file may have changed, so only print where the report was and not the source line.
+            if let Some(pointer) = pointers.first() {
+                let loc = pointer.loc;
+                _ = writeln!(
+                    output,
+                    "  {}({}): {}\n    --> {}:{}:{}",
+                    report.severity,
+                    report.key,
+                    report.msg,
+                    loc.pathname().display(),
+                    loc.line,
+                    loc.column
+                );
+            }
+        }
+        _ = writeln!(output);
+    }
+    _ = writeln!(
+        output,
+        "{} new, {} fixed, {} reports in total.",
+        new.len(),
+        fixed.len(),
+        current.len()
+    );
+}